@@ -0,0 +1,81 @@
+//!
+//! A Program Module Consuming the Main Input Module
+//!
+//! The other examples are producers (the input modules); this one is the consumer side.  The main
+//! input module is stood in for by the shared request handling (`InputSource`) behind an in-memory
+//! i2c bus, so the example runs on the host and checks every value the driver reads.  On hardware
+//! the bus would be the program module's i2c peripheral and the source the main input module.
+//!
+//! Run with `cargo run --example program_module`.
+//!
+
+use core::convert::Infallible;
+
+use common::prelude::*;
+use embedded_hal::i2c::{ErrorType, I2c, Operation, SevenBitAddress};
+
+/// The address the main input module answers on
+const MAIN_INPUT_ADDRESS: u8 = 0x42;
+
+/// An in-memory i2c bus where a write is a request to the main input module and a read takes its
+/// response (padded with zeros past the response)
+struct MemoryBus<S> {
+    source: S,
+    response: [u8; MAX_SOURCE_RESPONSE_LEN],
+    len: usize,
+}
+
+impl<S: InputSource> MemoryBus<S> {
+    fn new(source: S) -> Self {
+        Self { source, response: [0u8; MAX_SOURCE_RESPONSE_LEN], len: 0 }
+    }
+}
+
+impl<S> ErrorType for MemoryBus<S> {
+    type Error = Infallible;
+}
+
+impl<S: InputSource> I2c<SevenBitAddress> for MemoryBus<S> {
+    fn transaction(&mut self, address: SevenBitAddress, operations: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+        assert_eq!(address, MAIN_INPUT_ADDRESS);
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => self.len = self.source.respond(bytes[0], &mut self.response),
+                Operation::Read(buffer) => {
+                    buffer.fill(0);
+                    let len = self.len.min(buffer.len());
+                    buffer[..len].copy_from_slice(&self.response[..len]);
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+fn main() {
+    // The state of the main input module: "7", "k" and "?" held with a joystick pushed up
+    let mut state = Input::from_str_keys("7k?").unwrap();
+    state.analog.a0 = 2048;
+    state.analog.a1 = 4095;
+
+    let mut driver = InputModuleDriver::new(MAIN_INPUT_ADDRESS, MemoryBus::new(state));
+
+    let input = driver.get_input().unwrap();
+    println!("input:  {}", input.summary());
+    assert_eq!(input, state);
+
+    let numpad = driver.get_numpad().unwrap();
+    let digits: String = numpad.pressed_digits().collect();
+    println!("numpad: {}", digits);
+    assert_eq!(digits, "7");
+
+    let analog = driver.get_analog().unwrap();
+    let percent = analog.percent(&Calibration::default());
+    println!("analog: {:?} ({:?}%)", analog.channels(), percent);
+    assert_eq!(analog, state.analog);
+    assert_eq!(percent[1], 100);
+
+    let normalized = driver.get_analog_calibrated(&Calibration::default()).unwrap();
+    println!("stick:  x={} y={}", normalized[0], normalized[1]);
+    assert_eq!((normalized[0], normalized[1]), (0.0, 1.0));
+}
@@ -0,0 +1,147 @@
+//!
+//! Table-driven CRCs for framing packed data
+//!
+//! The lookup tables are generated by `const fn`s at compile time so nothing is computed at
+//! runtime on the input modules.
+//!
+
+use defmt::Format;
+
+/// The CRC-8 polynomial (x^8 + x^2 + x + 1)
+pub const CRC8_POLY: u8 = 0x07;
+/// The CRC-16-CCITT polynomial (x^16 + x^12 + x^5 + 1)
+pub const CRC16_CCITT_POLY: u16 = 0x1021;
+
+/// CRC-8 lookup table for `CRC8_POLY`
+pub const CRC8_TABLE: [u8; 256] = crc8_table(CRC8_POLY);
+/// CRC-16-CCITT lookup table for `CRC16_CCITT_POLY`
+pub const CRC16_CCITT_TABLE: [u16; 256] = crc16_table(CRC16_CCITT_POLY);
+
+/// Generate the lookup table of a (non-reflected) CRC-8 with the given polynomial
+pub const fn crc8_table(poly: u8) -> [u8; 256] {
+    let mut table = [0u8; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u8;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x80 != 0 { (crc << 1) ^ poly } else { crc << 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// Generate the lookup table of a (non-reflected) CRC-16 with the given polynomial
+pub const fn crc16_table(poly: u16) -> [u16; 256] {
+    let mut table = [0u16; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = (i as u16) << 8;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ poly } else { crc << 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// CRC-8 (poly 0x07, init 0x00) of the data
+pub fn crc8(data: &[u8]) -> u8 {
+    data.iter().fold(0, |crc, byte| CRC8_TABLE[(crc ^ byte) as usize])
+}
+
+/// CRC-16-CCITT (poly 0x1021, init 0xFFFF) of the data
+pub fn crc16_ccitt(data: &[u8]) -> u16 {
+    data.iter().fold(0xFFFF, |crc, byte| {
+        (crc << 8) ^ CRC16_CCITT_TABLE[((crc >> 8) as u8 ^ byte) as usize]
+    })
+}
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq, Default)]
+/// The CRC used to check a frame
+pub enum Crc {
+    /// 1 byte CRC-8, cheaper for short frames
+    #[default]
+    Crc8,
+    /// 2 byte CRC-16-CCITT, stronger for long frames
+    Crc16Ccitt,
+}
+
+impl Crc {
+    /// The number of bytes the CRC occupies in a frame
+    pub fn size(&self) -> usize {
+        match self {
+            Crc::Crc8 => 1,
+            Crc::Crc16Ccitt => 2,
+        }
+    }
+
+    /// Compute the CRC of the data
+    pub fn checksum(&self, data: &[u8]) -> u16 {
+        match self {
+            Crc::Crc8 => crc8(data) as u16,
+            Crc::Crc16Ccitt => crc16_ccitt(data),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHECK_INPUT: &[u8] = b"123456789";
+
+    fn reference_crc8(data: &[u8]) -> u8 {
+        let mut crc = 0u8;
+        for byte in data {
+            crc ^= byte;
+            for _ in 0..8 {
+                crc = if crc & 0x80 != 0 { (crc << 1) ^ CRC8_POLY } else { crc << 1 };
+            }
+        }
+        crc
+    }
+
+    fn reference_crc16_ccitt(init: u16, data: &[u8]) -> u16 {
+        let mut crc = init;
+        for byte in data {
+            crc ^= (*byte as u16) << 8;
+            for _ in 0..8 {
+                crc = if crc & 0x8000 != 0 { (crc << 1) ^ CRC16_CCITT_POLY } else { crc << 1 };
+            }
+        }
+        crc
+    }
+
+    #[test]
+    fn test_crc8_table() {
+        for (i, entry) in CRC8_TABLE.iter().enumerate() {
+            assert_eq!(*entry, reference_crc8(&[i as u8]));
+        }
+        assert_eq!(crc8(CHECK_INPUT), 0xF4);
+        assert_eq!(crc8(&[0x12, 0x34, 0xAB]), reference_crc8(&[0x12, 0x34, 0xAB]));
+    }
+
+    #[test]
+    fn test_crc16_ccitt_table() {
+        for (i, entry) in CRC16_CCITT_TABLE.iter().enumerate() {
+            assert_eq!(*entry, reference_crc16_ccitt(0, &[i as u8]));
+        }
+        assert_eq!(crc16_ccitt(CHECK_INPUT), 0x29B1);
+        assert_eq!(crc16_ccitt(&[0x12, 0x34, 0xAB]), reference_crc16_ccitt(0xFFFF, &[0x12, 0x34, 0xAB]));
+    }
+
+    #[test]
+    fn test_crc_selection() {
+        assert_eq!(Crc::Crc8.checksum(CHECK_INPUT), 0xF4);
+        assert_eq!(Crc::Crc16Ccitt.checksum(CHECK_INPUT), 0x29B1);
+        assert_eq!(Crc::Crc8.size(), 1);
+        assert_eq!(Crc::Crc16Ccitt.size(), 2);
+    }
+}
@@ -6,7 +6,7 @@ use core::{fmt::Debug, ops::{BitOr, BitOrAssign}};
 use derive_builder::Builder;
 use defmt::Format;
 
-use embedded_hal::i2c::{SevenBitAddress, I2c};
+use embedded_hal::i2c::{Error, ErrorKind, SevenBitAddress, I2c};
 pub mod numpad;
 use numpad::Numpad;
 
@@ -17,12 +17,141 @@ pub mod auxiliary;
 use auxiliary::Auxiliary;
 
 pub mod analog;
-use analog::AnalogInputs;
+use analog::{AnalogInputs, Calibration, TimedAnalog};
 
 pub mod other;
-use other::{OtherInput, DecodeInstructions};
+use other::{Decode, DecodeError, DecodedValue, DecodeInstructions, FieldName, OtherInput};
 
-use crate::packing::{Pack, PackingError, Unpack};
+pub mod config;
+use config::{Config, ConfigError, ReportMode, CONFIG_LEN, MODE_ACCUMULATE, MODE_LATCH_KEYS};
+
+pub mod raw_buttons;
+use raw_buttons::RawButtons;
+
+pub mod mapping;
+
+pub mod capabilities;
+use capabilities::Capabilities;
+
+pub mod transitions;
+
+pub mod repeat;
+
+pub mod changes;
+use changes::SectionChanges;
+
+pub mod chord;
+
+pub mod dpad;
+
+pub mod debug_frame;
+use debug_frame::DebugFrame;
+
+pub mod alert;
+use alert::ChangeFrame;
+
+pub mod command_log;
+use command_log::CommandLog;
+
+pub mod extension_status;
+use extension_status::ExtensionStatus;
+
+pub mod threshold;
+use threshold::{ThresholdConfig, ThresholdEvent, NO_THRESHOLD_EVENT};
+
+pub mod source;
+
+use crate::{packing::{Pack, PackedSize, PackingError, Unpack}, protocol::{ANALOG_LEN, AUXILIARY_LEN, DECODE_INSTRUCTIONS_LEN, FULL_FRAME_LEN, KEYPAD_LEN, NUMPAD_LEN, OTHER_INPUT_LEN, RAW_BUTTONS_LEN}};
+
+/// The default number of times a read is retried while the main input module is clock stretching
+pub const DEFAULT_STRETCH_RETRIES: u8 = 3;
+
+/// The length of a firmware build identifier
+pub const BUILD_ID_LEN: usize = 8;
+
+/// A firmware build identifier (e.g. the start of the git hash the firmware was built from)
+pub type BuildId = [u8; BUILD_ID_LEN];
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq)]
+/// Error from the input module driver
+pub enum DriverError<E> {
+    /// The underlying i2c bus returned an error
+    I2c(E),
+    /// The main input module held the clock longer than the stretch timeout allowed
+    Timeout,
+    /// The other input could not be decoded with the module's decode instructions
+    Decode(DecodeError),
+    /// The response could not be unpacked (i.e. it was corrupted on a flaky bus)
+    Packing(PackingError),
+    /// The config is invalid, so the main input module would reject it
+    Config(ConfigError),
+}
+
+impl<E> From<E> for DriverError<E> {
+    fn from(value: E) -> Self {
+        Self::I2c(value)
+    }
+}
+
+/// Check if the request written as `instruction` can be sent again after a bus error while the
+/// main input module runs with `mode_flags`
+fn retryable(instruction: &[u8], mode_flags: u8) -> bool {
+    instruction
+        .first()
+        .and_then(|opcode| InputRequest::try_from(*opcode).ok())
+        .is_some_and(|request| request.is_idempotent(mode_flags))
+}
+
+/// Write `instruction` then read the response into `buffer`, retrying up to `retries` times while
+/// the bus reports the peripheral is still stretching the clock.
+///
+/// HALs report a clock stretch that outlasts their own timeout as a bus error, so a bus error is
+/// retried and surfaced as `DriverError::Timeout` once the retries are exhausted.  A bus error does
+/// not mean the module never answered, so only requests that are idempotent under `mode_flags` (see
+/// `InputRequest::is_idempotent`) are retried and a request that changes the module returns the
+/// bus error right away.  Every other error is returned immediately.
+fn stretch_tolerant_write_read<I2C: I2c<SevenBitAddress>>(
+    i2c: &mut I2C,
+    address: SevenBitAddress,
+    instruction: &[u8],
+    buffer: &mut [u8],
+    retries: u8,
+    mode_flags: u8,
+) -> Result<(), DriverError<I2C::Error>> {
+    let retryable = retryable(instruction, mode_flags);
+    for _ in 0..=retries {
+        match i2c.write_read(address, instruction, buffer) {
+            Err(e) if retryable && e.kind() == ErrorKind::Bus => continue,
+            result => return result.map_err(DriverError::I2c),
+        }
+    }
+
+    Err(DriverError::Timeout)
+}
+
+/// Decode every declared field of an other input into owned values
+fn decode_all<E>(
+    other_input: &OtherInput,
+    decode_instructions: &DecodeInstructions,
+) -> Result<heapless::Vec<(DecodedValue, FieldName), 24>, DriverError<E>> {
+    let mut decoded = heapless::Vec::new();
+    for value in other_input.iter_decoded(decode_instructions) {
+        let value = value.map_err(DriverError::Decode)?;
+        // At most 24 fields are declared so the vec cannot overflow
+        let _ = decoded.push(value.into());
+    }
+    Ok(decoded)
+}
+
+/// Write nothing to `address`, returning whether a device acknowledged it (every error other than
+/// the missing acknowledge is returned)
+fn probe_address<I2C: I2c<SevenBitAddress>>(i2c: &mut I2C, address: SevenBitAddress) -> Result<bool, I2C::Error> {
+    match i2c.write(address, &[]) {
+        Ok(()) => Ok(true),
+        Err(e) if matches!(e.kind(), ErrorKind::NoAcknowledge(_)) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
 
 /// Driver for programming modules to use to interface with the main input module
 pub struct InputModuleDriver<I2C> {
@@ -30,6 +159,13 @@ pub struct InputModuleDriver<I2C> {
     address: SevenBitAddress,
     /// The i2c peripheral
     i2c: I2C,
+    /// The number of times a read is retried while the main input module is clock stretching
+    stretch_retries: u8,
+    /// Whether fetched decode instructions are checked with `DecodeInstructions::validate`
+    validate_decode: bool,
+    /// The mode flags the main input module is assumed to run with (decides whether the input
+    /// reads can be retried, see `InputRequest::is_idempotent`)
+    mode_flags: u8,
 }
 
 impl<I2C, I2CErr> InputModuleDriver<I2C> where
@@ -40,11 +176,50 @@ I2CErr: Debug + Format {
         Self {
             address: address.into(),
             i2c,
+            stretch_retries: DEFAULT_STRETCH_RETRIES,
+            validate_decode: false,
+            mode_flags: 0,
         }
     }
 
+    /// Set the number of times a read is retried while the main input module is clock stretching
+    /// before giving up with `DriverError::Timeout`.
+    ///
+    /// This is the only timeout the driver has: embedded-hal has no clock stretch timeout, so how
+    /// long each attempt waits is up to the HAL.  Only idempotent requests are retried.
+    pub fn set_stretch_retries(&mut self, retries: u8) {
+        self.stretch_retries = retries;
+    }
+
+    /// Set the mode flags (see `Config::mode_flags`) the main input module runs with.  The input
+    /// reads are retried after a bus error unless the module latches or accumulates buttons, as
+    /// reading them clears the latched buttons.  Assumes no flags until set (or a config is written
+    /// or read).
+    pub fn set_mode_flags(&mut self, mode_flags: u8) {
+        self.mode_flags = mode_flags;
+    }
+
+    /// Set whether fetched decode instructions are checked with `DecodeInstructions::validate`, so
+    /// instructions with named fields that do not fit fail with `DriverError::Decode` (off by
+    /// default)
+    pub fn set_validate_decode(&mut self, validate: bool) {
+        self.validate_decode = validate;
+    }
+
+    /// Write an instruction and read the response, tolerating clock stretching
+    fn write_read(&mut self, instruction: &[u8], buffer: &mut [u8]) -> Result<(), DriverError<I2CErr>> {
+        stretch_tolerant_write_read(&mut self.i2c, self.address, instruction, buffer, self.stretch_retries, self.mode_flags)
+    }
+
+    /// Check whether a module answers at the driver's address with an empty write (i.e. to skip
+    /// an empty extension slot before reading from it).  A module that does not acknowledge its
+    /// address is `Ok(false)`, while any other bus error is returned.
+    pub fn probe(&mut self) -> Result<bool, DriverError<I2CErr>> {
+        probe_address(&mut self.i2c, self.address).map_err(DriverError::I2c)
+    }
+
     /// Set the i2c address for the main input module
-    pub fn set_address(&mut self, new_address: u8) -> Result<(), I2CErr> {
+    pub fn set_address(&mut self, new_address: u8) -> Result<(), DriverError<I2CErr>> {
         let buffer = [InputRequest::SetAddress as u8, new_address];
         self.i2c.write(self.address, &buffer)?;
         self.address = new_address;
@@ -52,76 +227,426 @@ I2CErr: Debug + Format {
     }
 
     /// Get the full input information from the main input module
-    pub fn get_input(&mut self) -> Result<Input, I2CErr> {
+    pub fn get_input(&mut self) -> Result<Input, DriverError<I2CErr>> {
         let instruction = [InputRequest::FullInput as u8];
-        let mut buffer = [0u8; 71];
-        self.i2c.write_read(self.address, &instruction, &mut buffer)?;
-        Ok(Input::unpack(&buffer).unwrap())
+        let mut buffer = [0u8; FULL_FRAME_LEN];
+        self.write_read(&instruction, &mut buffer)?;
+        Input::unpack(&buffer).map_err(DriverError::Packing)
     }
 
     /// Get the numpad input information from the main input module
-    pub fn get_numpad(&mut self) -> Result<Numpad, I2CErr> {
+    pub fn get_numpad(&mut self) -> Result<Numpad, DriverError<I2CErr>> {
         let instruction = [InputRequest::Numpad as u8];
-        let mut buffer = [0u8; 2];
-        self.i2c.write_read(self.address, &instruction, &mut buffer)?;
-        Ok(Numpad::unpack(&buffer).unwrap())
+        let mut buffer = [0u8; NUMPAD_LEN];
+        self.write_read(&instruction, &mut buffer)?;
+        Numpad::unpack(&buffer).map_err(DriverError::Packing)
     }
 
     /// Get the keypad input information from the main input module
-    pub fn get_keypad(&mut self) -> Result<Keypad, I2CErr> {
+    pub fn get_keypad(&mut self) -> Result<Keypad, DriverError<I2CErr>> {
         let instruction = [InputRequest::Keypad as u8];
-        let mut buffer = [0u8; 4];
-        self.i2c.write_read(self.address, &instruction, &mut buffer)?;
-        Ok(Keypad::unpack(&buffer).unwrap())
+        let mut buffer = [0u8; KEYPAD_LEN];
+        self.write_read(&instruction, &mut buffer)?;
+        Keypad::unpack(&buffer).map_err(DriverError::Packing)
     }
 
     /// Get the auxiliary input information from the main input module
-    pub fn get_auxiliary(&mut self) -> Result<Auxiliary, I2CErr> {
+    pub fn get_auxiliary(&mut self) -> Result<Auxiliary, DriverError<I2CErr>> {
         let instruction = [InputRequest::Auxiliary as u8];
-        let mut buffer = [0u8; 4];
-        self.i2c.write_read(self.address, &instruction, &mut buffer)?;
-        Ok(Auxiliary::unpack(&buffer).unwrap())
+        let mut buffer = [0u8; AUXILIARY_LEN];
+        self.write_read(&instruction, &mut buffer)?;
+        Auxiliary::unpack(&buffer).map_err(DriverError::Packing)
     }
 
     /// Get the analog input information from the main input module
-    pub fn get_analog(&mut self) -> Result<AnalogInputs, I2CErr> {
+    pub fn get_analog(&mut self) -> Result<AnalogInputs, DriverError<I2CErr>> {
         let instruction = [InputRequest::Analog as u8];
-        let mut buffer = [0u8; 12];
-        self.i2c.write_read(self.address, &instruction, &mut buffer)?;
-        Ok(AnalogInputs::unpack(&buffer).unwrap())
+        let mut buffer = [0u8; ANALOG_LEN];
+        self.write_read(&instruction, &mut buffer)?;
+        AnalogInputs::unpack(&buffer).map_err(DriverError::Packing)
+    }
+
+    /// Get the analog inputs from the main input module, normalized by a calibration (see
+    /// `Calibration::normalized`)
+    pub fn get_analog_calibrated(&mut self, cal: &Calibration) -> Result<[f32; 6], DriverError<I2CErr>> {
+        Ok(self.get_analog()?.channels().map(|raw| cal.normalized(raw)))
+    }
+
+    /// Get the decode instructions for the other input module at `index`
+    pub fn get_decode(&mut self, index: u8) -> Result<DecodeInstructions, DriverError<I2CErr>> {
+        let instruction = [InputRequest::DecodeN as u8, index];
+        let mut buffer = [0u8; DECODE_INSTRUCTIONS_LEN];
+        self.write_read(&instruction, &mut buffer)?;
+        let decode_instructions = DecodeInstructions::unpack(&buffer).map_err(DriverError::Packing)?;
+        if self.validate_decode {
+            decode_instructions.validate().map_err(DriverError::Decode)?;
+        }
+        Ok(decode_instructions)
+    }
+
+    /// Get the input data for the other input module at `index`
+    pub fn get_other(&mut self, index: u8) -> Result<OtherInput, DriverError<I2CErr>> {
+        let instruction = [InputRequest::OtherN as u8, index];
+        let mut buffer = [0u8; OTHER_INPUT_LEN];
+        self.write_read(&instruction, &mut buffer)?;
+        Ok(buffer)
     }
 
     /// Get the decode instructions for the first other input module
-    pub fn get_decode_one(&mut self) -> Result<DecodeInstructions, I2CErr> {
-        let instruction = [InputRequest::DecodeOne as u8];
-        let mut buffer = [0u8; 248];
-        self.i2c.write_read(self.address, &instruction, &mut buffer)?;
-        Ok(DecodeInstructions::unpack(&buffer).unwrap())
+    pub fn get_decode_one(&mut self) -> Result<DecodeInstructions, DriverError<I2CErr>> {
+        self.get_decode(0)
     }
 
     /// Get the input data for the first other input module
-    pub fn get_other_one(&mut self) -> Result<OtherInput, I2CErr> {
-        let instruction = [InputRequest::OtherOne as u8];
-        let mut buffer = [0u8; 24];
-        self.i2c.write_read(self.address, &instruction, &mut buffer)?;
-        Ok(buffer)
+    pub fn get_other_one(&mut self) -> Result<OtherInput, DriverError<I2CErr>> {
+        self.get_other(0)
     }
 
     /// Get the decode instructions for the second other input module
-    pub fn get_decode_two(&mut self) -> Result<DecodeInstructions, I2CErr> {
-        let instruction = [InputRequest::DecodeTwo as u8];
-        let mut buffer = [0u8; 248];
-        self.i2c.write_read(self.address, &instruction, &mut buffer)?;
-        Ok(DecodeInstructions::unpack(&buffer).unwrap())
+    pub fn get_decode_two(&mut self) -> Result<DecodeInstructions, DriverError<I2CErr>> {
+        self.get_decode(1)
     }
 
     /// Get the input data for the second other input module
-    pub fn get_other_two(&mut self) -> Result<OtherInput, I2CErr> {
-        let instruction = [InputRequest::OtherTwo as u8];
-        let mut buffer = [0u8; 24];
-        self.i2c.write_read(self.address, &instruction, &mut buffer)?;
+    pub fn get_other_two(&mut self) -> Result<OtherInput, DriverError<I2CErr>> {
+        self.get_other(1)
+    }
+
+    /// Get the full input and the decode instructions of both other input modules (i.e. to refresh
+    /// a whole UI at once).
+    ///
+    /// This is three transactions, in order: `FullInput`, then `DecodeN` for index 0 and for
+    /// index 1.  In total 5 bytes are written and `FULL_FRAME_LEN + 2 * DECODE_INSTRUCTIONS_LEN`
+    /// bytes (566 with the `other-input` feature) are read.
+    pub fn get_everything(&mut self) -> Result<(Input, DecodeInstructions, DecodeInstructions), DriverError<I2CErr>> {
+        let input = self.get_input()?;
+        let decode_one = self.get_decode_one()?;
+        let decode_two = self.get_decode_two()?;
+        Ok((input, decode_one, decode_two))
+    }
+
+    /// Get every declared field of the first other input module decoded into owned values
+    pub fn get_decoded_one_vec(&mut self) -> Result<heapless::Vec<(DecodedValue, FieldName), 24>, DriverError<I2CErr>> {
+        let decode_instructions = self.get_decode_one()?;
+        let other_input = self.get_other_one()?;
+        decode_all(&other_input, &decode_instructions)
+    }
+
+    /// Get every declared field of the second other input module decoded into owned values
+    pub fn get_decoded_two_vec(&mut self) -> Result<heapless::Vec<(DecodedValue, FieldName), 24>, DriverError<I2CErr>> {
+        let decode_instructions = self.get_decode_two()?;
+        let other_input = self.get_other_two()?;
+        decode_all(&other_input, &decode_instructions)
+    }
+
+    /// Get the raw level of each physical button on the main input module
+    pub fn get_raw_buttons(&mut self) -> Result<RawButtons, DriverError<I2CErr>> {
+        let instruction = [InputRequest::RawButtons as u8];
+        let mut buffer = [0u8; RAW_BUTTONS_LEN];
+        self.write_read(&instruction, &mut buffer)?;
+        RawButtons::unpack(&buffer).map_err(DriverError::Packing)
+    }
+
+    /// Write (and persist) the full configuration of the main input module.  The module rejects the
+    /// entire config if any field is invalid (staying on its old address), so the config is
+    /// validated first and an invalid config is never written.
+    pub fn write_config(&mut self, config: Config) -> Result<(), DriverError<I2CErr>> {
+        config.validate().map_err(DriverError::Config)?;
+
+        let mut buffer = [0u8; CONFIG_LEN + 1];
+        buffer[0] = InputRequest::WriteConfig as u8;
+        config.pack(&mut buffer[1..]).unwrap();
+        self.i2c.write(self.address, &buffer)?;
+        self.address = config.address;
+        self.mode_flags = config.mode_flags;
+        Ok(())
+    }
+
+    /// Read the full configuration of the main input module
+    pub fn read_config(&mut self) -> Result<Config, DriverError<I2CErr>> {
+        let instruction = [InputRequest::ReadConfig as u8];
+        let mut buffer = [0u8; CONFIG_LEN];
+        self.write_read(&instruction, &mut buffer)?;
+        let config = Config::unpack(&buffer).map_err(DriverError::Packing)?;
+        self.mode_flags = config.mode_flags;
+        Ok(config)
+    }
+
+    /// Set (and persist) how the buttons shared by a digit and letters are reported
+    pub fn set_report_mode(&mut self, mode: ReportMode) -> Result<(), DriverError<I2CErr>> {
+        let buffer = [InputRequest::ReportMode as u8, mode as u8];
+        self.i2c.write(self.address, &buffer)?;
+        Ok(())
+    }
+
+    /// Get how the buttons shared by a digit and letters are reported (`ReportMode::Both` for an
+    /// unknown report mode)
+    pub fn get_report_mode(&mut self) -> Result<ReportMode, DriverError<I2CErr>> {
+        let instruction = [InputRequest::ReportMode as u8];
+        let mut buffer = [0u8; 1];
+        self.write_read(&instruction, &mut buffer)?;
+        Ok(ReportMode::try_from(buffer[0]).unwrap_or_default())
+    }
+
+    /// Get why each extension is disabled (i.e. to tell a faulty extension from an unplugged one)
+    pub fn get_extension_status(&mut self) -> Result<ExtensionStatus, DriverError<I2CErr>> {
+        let instruction = [InputRequest::ExtensionStatus as u8];
+        let mut buffer = [0u8; ExtensionStatus::PACKED_SIZE];
+        self.write_read(&instruction, &mut buffer)?;
+        ExtensionStatus::unpack(&buffer).map_err(DriverError::Packing)
+    }
+
+    /// Set the threshold of each analog channel, discarding the queued threshold events (the
+    /// thresholds are not persisted)
+    pub fn set_thresholds(&mut self, config: ThresholdConfig) -> Result<(), DriverError<I2CErr>> {
+        let mut buffer = [0u8; ThresholdConfig::PACKED_SIZE + 1];
+        buffer[0] = InputRequest::ThresholdEvents as u8;
+        config.pack(&mut buffer[1..]).unwrap();
+        self.i2c.write(self.address, &buffer)?;
+        Ok(())
+    }
+
+    /// Take the oldest analog threshold event (`None` while no event is queued)
+    pub fn get_threshold_event(&mut self) -> Result<Option<ThresholdEvent>, DriverError<I2CErr>> {
+        let instruction = [InputRequest::ThresholdEvents as u8];
+        let mut buffer = [0u8; ThresholdEvent::PACKED_SIZE];
+        self.write_read(&instruction, &mut buffer)?;
+        if buffer[0] == NO_THRESHOLD_EVENT {
+            return Ok(None);
+        }
+        ThresholdEvent::unpack(&buffer).map(Some).map_err(DriverError::Packing)
+    }
+
+    /// Get the identifier of the firmware build running on the main input module
+    pub fn get_build_id(&mut self) -> Result<BuildId, DriverError<I2CErr>> {
+        let instruction = [InputRequest::BuildId as u8];
+        let mut buffer = [0u8; BUILD_ID_LEN];
+        self.write_read(&instruction, &mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Get the sections of the input that changed since the last time this was called
+    pub fn get_changed_sections(&mut self) -> Result<SectionChanges, DriverError<I2CErr>> {
+        let instruction = [InputRequest::ChangedSections as u8];
+        let mut buffer = [0u8; SectionChanges::PACKED_SIZE];
+        self.write_read(&instruction, &mut buffer)?;
+        SectionChanges::unpack(&buffer).map_err(DriverError::Packing)
+    }
+
+    /// Get the exact packed input bytes the main input module sent (behind its length and checksum
+    /// header) along with the checksum computed over the received bytes for comparison
+    pub fn get_debug_frame(&mut self) -> Result<(DebugFrame, u8), DriverError<I2CErr>> {
+        let instruction = [InputRequest::DebugFrame as u8];
+        let mut buffer = [0u8; DebugFrame::PACKED_SIZE];
+        self.write_read(&instruction, &mut buffer)?;
+        let frame = DebugFrame::unpack(&buffer).map_err(DriverError::Packing)?;
+        Ok((frame, frame.computed_checksum()))
+    }
+
+    /// Read the change frame after the main input module asserts its attention line (clearing the
+    /// alert)
+    pub fn get_change_frame(&mut self) -> Result<ChangeFrame, DriverError<I2CErr>> {
+        let instruction = [InputRequest::AlertFrame as u8];
+        let mut buffer = [0u8; ChangeFrame::PACKED_SIZE];
+        self.write_read(&instruction, &mut buffer)?;
+        ChangeFrame::unpack(&buffer).map_err(DriverError::Packing)
+    }
+
+    /// Dump the latest requests received by the main input module (oldest first, not including
+    /// this request)
+    pub fn get_command_log(&mut self) -> Result<CommandLog, DriverError<I2CErr>> {
+        let instruction = [InputRequest::CommandLog as u8];
+        let mut buffer = [0u8; CommandLog::PACKED_SIZE];
+        self.write_read(&instruction, &mut buffer)?;
+        CommandLog::unpack(&buffer).map_err(DriverError::Packing)
+    }
+
+    /// Get the analog inputs with the time they were sampled (for computing rates such as joystick
+    /// velocity)
+    pub fn get_timed_analog(&mut self) -> Result<TimedAnalog, DriverError<I2CErr>> {
+        let instruction = [InputRequest::TimedAnalog as u8];
+        let mut buffer = [0u8; TimedAnalog::PACKED_SIZE];
+        self.write_read(&instruction, &mut buffer)?;
+        TimedAnalog::unpack(&buffer).map_err(DriverError::Packing)
+    }
+}
+
+/// The async counterpart of `stretch_tolerant_write_read`
+#[cfg(feature = "async")]
+async fn async_stretch_tolerant_write_read<I2C: embedded_hal_async::i2c::I2c<SevenBitAddress>>(
+    i2c: &mut I2C,
+    address: SevenBitAddress,
+    instruction: &[u8],
+    buffer: &mut [u8],
+    retries: u8,
+    mode_flags: u8,
+) -> Result<(), DriverError<I2C::Error>> {
+    let retryable = retryable(instruction, mode_flags);
+    for _ in 0..=retries {
+        match i2c.write_read(address, instruction, buffer).await {
+            Err(e) if retryable && e.kind() == ErrorKind::Bus => continue,
+            result => return result.map_err(DriverError::I2c),
+        }
+    }
+
+    Err(DriverError::Timeout)
+}
+
+#[cfg(feature = "async")]
+/// Async driver for programming modules to use to interface with the main input module (i.e. from
+/// an RTIC task, so polling the module does not stall the executor).  Mirrors the section getters
+/// of `InputModuleDriver`.
+pub struct AsyncInputModuleDriver<I2C> {
+    /// The address of the input module
+    address: SevenBitAddress,
+    /// The i2c peripheral
+    i2c: I2C,
+    /// The number of times a read is retried while the main input module is clock stretching
+    stretch_retries: u8,
+    /// Whether fetched decode instructions are checked with `DecodeInstructions::validate`
+    validate_decode: bool,
+    /// The mode flags the main input module is assumed to run with (decides whether the input
+    /// reads can be retried, see `InputRequest::is_idempotent`)
+    mode_flags: u8,
+}
+
+#[cfg(feature = "async")]
+impl<I2C, I2CErr> AsyncInputModuleDriver<I2C> where
+I2C: embedded_hal_async::i2c::I2c<SevenBitAddress, Error=I2CErr>,
+I2CErr: Debug + Format {
+    /// Initialize a new Async Input Module Driver
+    pub fn new(address: u8, i2c: I2C) -> Self {
+        Self {
+            address,
+            i2c,
+            stretch_retries: DEFAULT_STRETCH_RETRIES,
+            validate_decode: false,
+            mode_flags: 0,
+        }
+    }
+
+    /// Set the number of times a read is retried while the main input module is clock stretching
+    /// before giving up with `DriverError::Timeout`.
+    ///
+    /// This is the only timeout the driver has: embedded-hal has no clock stretch timeout, so how
+    /// long each attempt waits is up to the HAL.  Only idempotent requests are retried.
+    pub fn set_stretch_retries(&mut self, retries: u8) {
+        self.stretch_retries = retries;
+    }
+
+    /// Set the mode flags (see `Config::mode_flags`) the main input module runs with.  The input
+    /// reads are retried after a bus error unless the module latches or accumulates buttons, as
+    /// reading them clears the latched buttons.  Assumes no flags until set (or a config is written
+    /// or read).
+    pub fn set_mode_flags(&mut self, mode_flags: u8) {
+        self.mode_flags = mode_flags;
+    }
+
+    /// Set whether fetched decode instructions are checked with `DecodeInstructions::validate`, so
+    /// instructions with named fields that do not fit fail with `DriverError::Decode` (off by
+    /// default)
+    pub fn set_validate_decode(&mut self, validate: bool) {
+        self.validate_decode = validate;
+    }
+
+    /// Write an instruction and read the response, tolerating clock stretching
+    async fn write_read(&mut self, instruction: &[u8], buffer: &mut [u8]) -> Result<(), DriverError<I2CErr>> {
+        async_stretch_tolerant_write_read(&mut self.i2c, self.address, instruction, buffer, self.stretch_retries, self.mode_flags).await
+    }
+
+    /// Set the i2c address for the main input module
+    pub async fn set_address(&mut self, new_address: u8) -> Result<(), DriverError<I2CErr>> {
+        let buffer = [InputRequest::SetAddress as u8, new_address];
+        self.i2c.write(self.address, &buffer).await?;
+        self.address = new_address;
+        Ok(())
+    }
+
+    /// Get the full input information from the main input module
+    pub async fn get_input(&mut self) -> Result<Input, DriverError<I2CErr>> {
+        let mut buffer = [0u8; FULL_FRAME_LEN];
+        self.write_read(&[InputRequest::FullInput as u8], &mut buffer).await?;
+        Input::unpack(&buffer).map_err(DriverError::Packing)
+    }
+
+    /// Get the numpad input information from the main input module
+    pub async fn get_numpad(&mut self) -> Result<Numpad, DriverError<I2CErr>> {
+        let mut buffer = [0u8; NUMPAD_LEN];
+        self.write_read(&[InputRequest::Numpad as u8], &mut buffer).await?;
+        Numpad::unpack(&buffer).map_err(DriverError::Packing)
+    }
+
+    /// Get the keypad input information from the main input module
+    pub async fn get_keypad(&mut self) -> Result<Keypad, DriverError<I2CErr>> {
+        let mut buffer = [0u8; KEYPAD_LEN];
+        self.write_read(&[InputRequest::Keypad as u8], &mut buffer).await?;
+        Keypad::unpack(&buffer).map_err(DriverError::Packing)
+    }
+
+    /// Get the auxiliary input information from the main input module
+    pub async fn get_auxiliary(&mut self) -> Result<Auxiliary, DriverError<I2CErr>> {
+        let mut buffer = [0u8; AUXILIARY_LEN];
+        self.write_read(&[InputRequest::Auxiliary as u8], &mut buffer).await?;
+        Auxiliary::unpack(&buffer).map_err(DriverError::Packing)
+    }
+
+    /// Get the analog input information from the main input module
+    pub async fn get_analog(&mut self) -> Result<AnalogInputs, DriverError<I2CErr>> {
+        let mut buffer = [0u8; ANALOG_LEN];
+        self.write_read(&[InputRequest::Analog as u8], &mut buffer).await?;
+        AnalogInputs::unpack(&buffer).map_err(DriverError::Packing)
+    }
+
+    /// Get the analog inputs from the main input module, normalized by a calibration (see
+    /// `Calibration::normalized`)
+    pub async fn get_analog_calibrated(&mut self, cal: &Calibration) -> Result<[f32; 6], DriverError<I2CErr>> {
+        Ok(self.get_analog().await?.channels().map(|raw| cal.normalized(raw)))
+    }
+
+    /// Get the decode instructions for the other input module at `index`
+    pub async fn get_decode(&mut self, index: u8) -> Result<DecodeInstructions, DriverError<I2CErr>> {
+        let mut buffer = [0u8; DECODE_INSTRUCTIONS_LEN];
+        self.write_read(&[InputRequest::DecodeN as u8, index], &mut buffer).await?;
+        let decode_instructions = DecodeInstructions::unpack(&buffer).map_err(DriverError::Packing)?;
+        if self.validate_decode {
+            decode_instructions.validate().map_err(DriverError::Decode)?;
+        }
+        Ok(decode_instructions)
+    }
+
+    /// Get the input data for the other input module at `index`
+    pub async fn get_other(&mut self, index: u8) -> Result<OtherInput, DriverError<I2CErr>> {
+        let mut buffer = [0u8; OTHER_INPUT_LEN];
+        self.write_read(&[InputRequest::OtherN as u8, index], &mut buffer).await?;
         Ok(buffer)
     }
+
+    /// Get the decode instructions for the first other input module
+    pub async fn get_decode_one(&mut self) -> Result<DecodeInstructions, DriverError<I2CErr>> {
+        self.get_decode(0).await
+    }
+
+    /// Get the input data for the first other input module
+    pub async fn get_other_one(&mut self) -> Result<OtherInput, DriverError<I2CErr>> {
+        self.get_other(0).await
+    }
+
+    /// Get the decode instructions for the second other input module
+    pub async fn get_decode_two(&mut self) -> Result<DecodeInstructions, DriverError<I2CErr>> {
+        self.get_decode(1).await
+    }
+
+    /// Get the input data for the second other input module
+    pub async fn get_other_two(&mut self) -> Result<OtherInput, DriverError<I2CErr>> {
+        self.get_other(1).await
+    }
+
+    /// Get the raw level of each physical button on the main input module
+    pub async fn get_raw_buttons(&mut self) -> Result<RawButtons, DriverError<I2CErr>> {
+        let mut buffer = [0u8; RAW_BUTTONS_LEN];
+        self.write_read(&[InputRequest::RawButtons as u8], &mut buffer).await?;
+        RawButtons::unpack(&buffer).map_err(DriverError::Packing)
+    }
 }
 
 #[derive(Clone, Copy, Debug, Format, PartialEq, Eq)]
@@ -147,21 +672,224 @@ pub enum InputRequest {
     OtherTwo = 0x08,
     /// Set the I2C Address of the main input module
     SetAddress = 0x09,
+    /// Write (and persist) the full configuration of the main input module
+    WriteConfig = 0x0A,
+    /// Read the full configuration of the main input module
+    ReadConfig = 0x0B,
+    /// Request the raw level of each physical button (before multi-tap decoding)
+    RawButtons = 0x0C,
+    /// Request the other inputs from the I/O Module at the index written after the opcode
+    OtherN = 0x0D,
+    /// Request the other input decode instructions for the I/O Module at the index written after
+    /// the opcode
+    DecodeN = 0x0E,
+    /// Request the identifier of the firmware build running on the main input module
+    BuildId = 0x0F,
+    /// Request the sections of the input that changed since the last `ChangedSections` request
+    ChangedSections = 0x10,
+    /// Request the packed input behind a header (length and checksum) to debug the framing
+    DebugFrame = 0x11,
+    /// Read the change frame after an alert on the attention line (clearing the alert)
+    AlertFrame = 0x12,
+    /// Dump the latest requests received by the module (for post-mortem debugging)
+    CommandLog = 0x13,
+    /// Request the analog inputs with the time they were sampled
+    TimedAnalog = 0x14,
+    /// Read (or write and persist) how the buttons shared by a digit and letters are reported
+    ReportMode = 0x15,
+    /// Request why each extension is disabled
+    ExtensionStatus = 0x16,
+    /// Take the oldest analog threshold event (or write the threshold of each analog channel)
+    ThresholdEvents = 0x17,
+}
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq)]
+/// A request for the other input (or its decode instructions) of the I/O Module at an index
+pub enum OtherRequest {
+    /// Request the decode instructions of the I/O Module at the index
+    Decode(u8),
+    /// Request the other inputs of the I/O Module at the index
+    Other(u8),
+}
+
+impl InputRequest {
+    /// Resolve an other input request to an indexed request.
+    ///
+    /// The indexed opcodes use the `index` written after the opcode while the legacy fixed-slot
+    /// opcodes (`DecodeOne`, `OtherOne`, `DecodeTwo`, `OtherTwo`) are aliases of index 0 and 1.
+    pub fn other_request(&self, index: u8) -> Option<OtherRequest> {
+        match self {
+            InputRequest::DecodeOne => Some(OtherRequest::Decode(0)),
+            InputRequest::OtherOne => Some(OtherRequest::Other(0)),
+            InputRequest::DecodeTwo => Some(OtherRequest::Decode(1)),
+            InputRequest::OtherTwo => Some(OtherRequest::Other(1)),
+            InputRequest::DecodeN => Some(OtherRequest::Decode(index)),
+            InputRequest::OtherN => Some(OtherRequest::Other(index)),
+            _ => None,
+        }
+    }
+
+    /// Check if answering the request leaves the main input module unchanged while it runs with
+    /// `mode_flags` (see `Config::mode_flags`), so the request can be sent again.
+    ///
+    /// The threshold event, alert frame and changed sections reads take what they return.  The
+    /// button reads clear the latched buttons when the module latches or accumulates buttons, so
+    /// they are only idempotent without `MODE_LATCH_KEYS` and `MODE_ACCUMULATE` (a resent section
+    /// read may latch a newer frame, see `FrameSnapshot` in the main input module, which is still
+    /// consistent).
+    pub const fn is_idempotent(&self, mode_flags: u8) -> bool {
+        match self {
+            InputRequest::ChangedSections |
+            InputRequest::AlertFrame |
+            InputRequest::ThresholdEvents => false,
+            InputRequest::FullInput |
+            InputRequest::Numpad |
+            InputRequest::Keypad |
+            InputRequest::Auxiliary => mode_flags & (MODE_LATCH_KEYS | MODE_ACCUMULATE) == 0,
+            InputRequest::Analog |
+            InputRequest::OtherOne |
+            InputRequest::OtherTwo |
+            InputRequest::OtherN |
+            InputRequest::DebugFrame |
+            InputRequest::DecodeOne |
+            InputRequest::DecodeTwo |
+            InputRequest::DecodeN |
+            InputRequest::SetAddress |
+            InputRequest::WriteConfig |
+            InputRequest::ReadConfig |
+            InputRequest::RawButtons |
+            InputRequest::BuildId |
+            InputRequest::CommandLog |
+            InputRequest::TimedAnalog |
+            InputRequest::ReportMode |
+            InputRequest::ExtensionStatus => true,
+        }
+    }
+
+    /// The length of the response to the request (0 for requests that are only written)
+    pub const fn response_len(&self) -> usize {
+        match self {
+            InputRequest::FullInput => Input::PACKED_SIZE,
+            InputRequest::Numpad => Numpad::PACKED_SIZE,
+            InputRequest::Keypad => Keypad::PACKED_SIZE,
+            InputRequest::Auxiliary => Auxiliary::PACKED_SIZE,
+            InputRequest::Analog => AnalogInputs::PACKED_SIZE,
+            InputRequest::DecodeOne |
+            InputRequest::DecodeTwo |
+            InputRequest::DecodeN => DecodeInstructions::PACKED_SIZE,
+            InputRequest::OtherOne |
+            InputRequest::OtherTwo |
+            InputRequest::OtherN => size_of::<OtherInput>(),
+            InputRequest::SetAddress => 1,
+            InputRequest::WriteConfig => 0,
+            InputRequest::ReadConfig => Config::PACKED_SIZE,
+            InputRequest::RawButtons => RawButtons::PACKED_SIZE,
+            InputRequest::BuildId => BUILD_ID_LEN,
+            InputRequest::ChangedSections => SectionChanges::PACKED_SIZE,
+            InputRequest::DebugFrame => DebugFrame::PACKED_SIZE,
+            InputRequest::AlertFrame => ChangeFrame::PACKED_SIZE,
+            InputRequest::CommandLog => CommandLog::PACKED_SIZE,
+            InputRequest::TimedAnalog => TimedAnalog::PACKED_SIZE,
+            InputRequest::ReportMode => 1,
+            InputRequest::ExtensionStatus => ExtensionStatus::PACKED_SIZE,
+            InputRequest::ThresholdEvents => ThresholdEvent::PACKED_SIZE,
+        }
+    }
 }
 
-impl From<u8> for InputRequest {
-    fn from(value: u8) -> Self {
+/// The length of the largest transfer between the main input module and a program module (the
+/// transport buffers on both sides are sized to this)
+pub const MAX_TRANSFER_LEN: usize = 248;
+
+/// The first byte of every response from an extension.  The main input module scans for it to
+/// find a response shifted by an extension that was not ready to answer (or a transfer that
+/// started mid-byte).
+pub const EXTENSION_SYNC_BYTE: u8 = 0xA5;
+
+/// The length of the header leading every response from an extension (the sync byte, then the
+/// length of the payload that follows)
+pub const EXTENSION_HEADER_LEN: usize = 2;
+
+/// The length of a full input response from an extension (the header, then the packed input
+/// followed by the CRC-8 of the packed input, so the main input module never unpacks a corrupt
+/// transfer)
+pub const EXTENSION_FULL_INPUT_LEN: usize = EXTENSION_HEADER_LEN + Input::PACKED_SIZE + 1;
+
+/// Every request (used to check the response lengths at compile time)
+const ALL_REQUESTS: [InputRequest; 24] = [
+    InputRequest::FullInput,
+    InputRequest::Numpad,
+    InputRequest::Keypad,
+    InputRequest::Auxiliary,
+    InputRequest::Analog,
+    InputRequest::DecodeOne,
+    InputRequest::OtherOne,
+    InputRequest::DecodeTwo,
+    InputRequest::OtherTwo,
+    InputRequest::SetAddress,
+    InputRequest::WriteConfig,
+    InputRequest::ReadConfig,
+    InputRequest::RawButtons,
+    InputRequest::OtherN,
+    InputRequest::DecodeN,
+    InputRequest::BuildId,
+    InputRequest::ChangedSections,
+    InputRequest::DebugFrame,
+    InputRequest::AlertFrame,
+    InputRequest::CommandLog,
+    InputRequest::TimedAnalog,
+    InputRequest::ReportMode,
+    InputRequest::ExtensionStatus,
+    InputRequest::ThresholdEvents,
+];
+
+// Fail the build (rather than panic at runtime) if a response outgrows the transport buffers.  When
+// adding an opcode, give it a length in `InputRequest::response_len` and add it to `ALL_REQUESTS`.
+const _: () = {
+    let mut i = 0;
+    while i < ALL_REQUESTS.len() {
+        assert!(ALL_REQUESTS[i].response_len() <= MAX_TRANSFER_LEN);
+        i += 1;
+    }
+    // The payload length of an extension response must fit in its header's length byte
+    assert!(MAX_TRANSFER_LEN <= u8::MAX as usize);
+};
+
+/// The single byte sent in response to a request with an unknown opcode so the requester's read
+/// still completes
+pub const UNKNOWN_REQUEST_RESPONSE: u8 = 0xFF;
+
+impl TryFrom<u8> for InputRequest {
+    /// The unknown opcode
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
         match value {
-            0 => InputRequest::FullInput,
-            1 => InputRequest::Numpad,
-            2 => InputRequest::Keypad,
-            3 => InputRequest::Auxiliary,
-            4 => InputRequest::Analog,
-            5 => InputRequest::DecodeOne,
-            6 => InputRequest::OtherOne,
-            7 => InputRequest::DecodeTwo,
-            8 => InputRequest::OtherTwo,
-            _ => InputRequest::SetAddress,
+            0 => Ok(InputRequest::FullInput),
+            1 => Ok(InputRequest::Numpad),
+            2 => Ok(InputRequest::Keypad),
+            3 => Ok(InputRequest::Auxiliary),
+            4 => Ok(InputRequest::Analog),
+            5 => Ok(InputRequest::DecodeOne),
+            6 => Ok(InputRequest::OtherOne),
+            7 => Ok(InputRequest::DecodeTwo),
+            8 => Ok(InputRequest::OtherTwo),
+            9 => Ok(InputRequest::SetAddress),
+            10 => Ok(InputRequest::WriteConfig),
+            11 => Ok(InputRequest::ReadConfig),
+            12 => Ok(InputRequest::RawButtons),
+            13 => Ok(InputRequest::OtherN),
+            14 => Ok(InputRequest::DecodeN),
+            15 => Ok(InputRequest::BuildId),
+            16 => Ok(InputRequest::ChangedSections),
+            17 => Ok(InputRequest::DebugFrame),
+            18 => Ok(InputRequest::AlertFrame),
+            19 => Ok(InputRequest::CommandLog),
+            20 => Ok(InputRequest::TimedAnalog),
+            21 => Ok(InputRequest::ReportMode),
+            22 => Ok(InputRequest::ExtensionStatus),
+            23 => Ok(InputRequest::ThresholdEvents),
+            _ => Err(value),
         }
     }
 }
@@ -186,18 +914,248 @@ pub struct Input {
     /// Analog input
     pub analog: AnalogInputs,
 
+    #[cfg(feature = "other-input")]
     #[builder(default = "[0u8; 24]")]
     /// Other Input 1
     pub other_input_one: OtherInput,
 
+    #[cfg(feature = "other-input")]
     #[builder(default = "[0u8; 24]")]
     /// Other Input 2
     pub other_input_two: OtherInput,
 }
 
+impl Input {
+    /// The length of the packed input.  The bytes are laid out as:
+    ///
+    /// | Bytes    | Section                  |
+    /// |----------|--------------------------|
+    /// | `0..2`   | numpad                   |
+    /// | `2..6`   | keypad                   |
+    /// | `6..10`  | auxiliary                |
+    /// | `10..22` | analog inputs            |
+    /// | `22..46` | other input 1            |
+    /// | `46..70` | other input 2            |
+    #[cfg(feature = "other-input")]
+    pub const PACKED_SIZE: usize = 70;
+
+    /// The length of the packed input (without the other inputs).  The bytes are laid out as the
+    /// numpad (`0..2`), the keypad (`2..6`), the auxiliary (`6..10`) then the analog inputs
+    /// (`10..22`).
+    #[cfg(not(feature = "other-input"))]
+    pub const PACKED_SIZE: usize = 22;
+
+    /// The characters of every pressed button.
+    ///
+    /// The order is part of the API: numpad digits (0-9), then keypad letters (a-z), then
+    /// auxiliary characters (in field order), regardless of the order the buttons were pressed in.
+    pub fn pressed_chars(&self) -> impl Iterator<Item = char> {
+        self.numpad.pressed_digits()
+            .chain(self.keypad.pressed_chars())
+            .chain(self.auxiliary.pressed_chars())
+    }
+
+    /// A compact summary of the pressed keys (i.e. `1 a ! enter`) that formats the same with
+    /// `defmt` and `core::fmt`
+    pub fn summary(&self) -> InputSummary<'_> {
+        InputSummary(self)
+    }
+
+    /// Render the compact summary of the pressed keys (see `summary`) into a text buffer
+    pub fn write_summary(&self, w: &mut impl core::fmt::Write) -> core::fmt::Result {
+        write!(w, "{}", self.summary())
+    }
+
+    /// Render the input as a compact ASCII log line (i.e. `keys=1 a enter analog=2048,0,0,0,0,0`)
+    /// into a fixed buffer for logging over plain UART, returning the filled part of the buffer.
+    ///
+    /// The line has no line ending and is truncated if it does not fit in the buffer.
+    pub fn to_log_line<'a>(&self, buf: &'a mut [u8]) -> &'a str {
+        use core::fmt::Write;
+
+        let mut line = LineWriter { buf, len: 0 };
+        let [a0, a1, a2, a3, a4, a5] = self.analog.channels();
+        // A full buffer only truncates the line
+        let _ = write!(line, "keys={} analog={},{},{},{},{},{}", self.summary(), a0, a1, a2, a3, a4, a5);
+
+        let LineWriter { buf, len } = line;
+        // Only whole ASCII characters are ever written
+        core::str::from_utf8(&buf[..len]).unwrap()
+    }
+
+    /// Zero every section (and analog channel or other input) the module does not support so
+    /// uninitialized data is never read as input
+    pub fn mask_to_capabilities(&mut self, caps: &Capabilities) {
+        if !caps.numpad {
+            self.numpad = Numpad::default();
+        }
+        if !caps.keypad {
+            self.keypad = Keypad::default();
+        }
+        if !caps.auxiliary {
+            self.auxiliary = Auxiliary::default();
+        }
+
+        for channel in (caps.analog_channels as usize)..6 {
+            if let Some(value) = self.analog.channel_mut(channel) {
+                *value = 0;
+            }
+        }
+
+        #[cfg(feature = "other-input")]
+        {
+            if caps.other_inputs < 2 {
+                self.other_input_two = [0u8; 24];
+            }
+            if caps.other_inputs < 1 {
+                self.other_input_one = [0u8; 24];
+            }
+        }
+    }
+
+    /// Check if every button pressed in this input is also pressed in `other` (only the numpad,
+    /// keypad and auxiliary are compared)
+    pub fn is_subset_of(&self, other: &Self) -> bool {
+        self.numpad.is_subset_of(&other.numpad) &&
+            self.keypad.is_subset_of(&other.keypad) &&
+            self.auxiliary.is_subset_of(&other.auxiliary)
+    }
+
+    /// Check if no buttons are pressed (the analog and other inputs are ignored)
+    pub fn is_empty(&self) -> bool {
+        DigitalInput(self) == DigitalInput(&Input::default())
+    }
+
+    /// Check if every button of `chord` is held in this input (other buttons may also be held)
+    pub fn holds_chord(&self, chord: &Input) -> bool {
+        chord.is_subset_of(self)
+    }
+
+    /// Build an input with the button of each character in `s` pressed.
+    ///
+    /// Digits press the numpad, letters press the keypad (an uppercase letter also presses shift)
+    /// and symbols press the auxiliary.  Any other character (e.g. whitespace) cannot be mapped
+    /// and the first such character is returned as the error.
+    pub fn from_str_keys(s: &str) -> Result<Self, char> {
+        let mut input = Self::default();
+        for c in s.chars() {
+            let button = if let Some(digit) = c.to_digit(10) {
+                input.numpad.digit_mut(digit as u8)
+            } else if c.is_ascii_uppercase() {
+                input.keypad.shift = true;
+                input.keypad.letter_mut(c.to_ascii_lowercase())
+            } else if c.is_ascii_lowercase() {
+                input.keypad.letter_mut(c)
+            } else {
+                input.auxiliary.char_mut(c)
+            };
+
+            *button.ok_or(c)? = true;
+        }
+
+        Ok(input)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+/// Compares only the buttons (numpad, keypad and auxiliary) of an input.
+///
+/// The analog inputs rarely compare equal across frames, so `DigitalInput(&a) == DigitalInput(&b)`
+/// is the canonical check for whether the buttons of two frames match.
+pub struct DigitalInput<'a>(pub &'a Input);
+
+impl PartialEq for DigitalInput<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.numpad == other.0.numpad &&
+            self.0.keypad == other.0.keypad &&
+            self.0.auxiliary == other.0.auxiliary
+    }
+}
+
+impl Eq for DigitalInput<'_> {}
+
+/// Writes text into a fixed byte buffer, truncating once the buffer is full
+struct LineWriter<'a> {
+    /// The buffer being filled
+    buf: &'a mut [u8],
+    /// The number of bytes written so far
+    len: usize,
+}
+
+impl core::fmt::Write for LineWriter<'_> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let available = self.buf.len() - self.len;
+        let written = s.len().min(available);
+        self.buf[self.len..self.len + written].copy_from_slice(&s.as_bytes()[..written]);
+        self.len += written;
+
+        if written < s.len() {
+            Err(core::fmt::Error)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// A key in the summary of an input
+enum SummaryKey {
+    /// A pressed character
+    Char(char),
+    /// A pressed key without a character (i.e. enter)
+    Named(&'static str),
+}
+
+/// A compact summary of the pressed keys of an input: the pressed characters (in the order of
+/// `Input::pressed_chars`) followed by enter and backspace, separated by spaces
+pub struct InputSummary<'a>(&'a Input);
+
+impl InputSummary<'_> {
+    /// The keys of the summary in order (shared by both formatters)
+    fn keys(&self) -> impl Iterator<Item = SummaryKey> {
+        let keypad = self.0.keypad;
+        self.0.pressed_chars()
+            .map(SummaryKey::Char)
+            .chain(keypad.enter.then_some(SummaryKey::Named("enter")))
+            .chain(keypad.backspace.then_some(SummaryKey::Named("backspace")))
+    }
+}
+
+impl core::fmt::Display for InputSummary<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for (i, key) in self.keys().enumerate() {
+            if i != 0 {
+                f.write_str(" ")?;
+            }
+            match key {
+                SummaryKey::Char(c) => core::fmt::Write::write_char(f, c)?,
+                SummaryKey::Named(name) => f.write_str(name)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Format for InputSummary<'_> {
+    fn format(&self, fmt: defmt::Formatter) {
+        for (i, key) in self.keys().enumerate() {
+            if i != 0 {
+                defmt::write!(fmt, " ");
+            }
+            match key {
+                SummaryKey::Char(c) => defmt::write!(fmt, "{=char}", c),
+                SummaryKey::Named(name) => defmt::write!(fmt, "{=str}", name),
+            }
+        }
+    }
+}
+
+impl PackedSize for Input {
+    const PACKED_LEN: usize = Self::PACKED_SIZE;
+}
+
 impl Pack for Input {
     fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
-        if buffer.len() < 71 {
+        if buffer.len() < Self::PACKED_SIZE {
             return Err(PackingError::InvalidBufferSize);
         }
 
@@ -205,15 +1163,18 @@ impl Pack for Input {
         self.keypad.pack(&mut buffer[2..6])?;
         self.auxiliary.pack(&mut buffer[6..10])?;
         self.analog.pack(&mut buffer[10..22])?;
-        buffer[22..46].copy_from_slice(&self.other_input_one);
-        buffer[46..70].copy_from_slice(&self.other_input_two);
+        #[cfg(feature = "other-input")]
+        {
+            buffer[22..46].copy_from_slice(&self.other_input_one);
+            buffer[46..70].copy_from_slice(&self.other_input_two);
+        }
         Ok(())
     }
 }
 
 impl Unpack for Input {
     fn unpack(buffer: &[u8]) -> Result<Self, PackingError> where Self: Sized {
-        if buffer.len() < 71 {
+        if buffer.len() < Self::PACKED_SIZE {
             return Err(PackingError::InvalidBufferSize);
         }
 
@@ -222,13 +1183,27 @@ impl Unpack for Input {
             keypad: Keypad::unpack(&buffer[2..6])?,
             auxiliary: Auxiliary::unpack(&buffer[6..10])?,
             analog: AnalogInputs::unpack(&buffer[10..22])?,
+            #[cfg(feature = "other-input")]
             other_input_one: buffer[22..46].try_into().unwrap(),
+            #[cfg(feature = "other-input")]
             other_input_two: buffer[46..70].try_into().unwrap(),
         })
     }
 }
 
-impl BitOr for Input {
+/// OR the raw bytes of two other inputs
+#[cfg(feature = "other-input")]
+fn bitor_other(lhs: OtherInput, rhs: OtherInput) -> OtherInput {
+    let mut merged = lhs;
+    for (byte, rhs_byte) in merged.iter_mut().zip(rhs) {
+        *byte |= rhs_byte;
+    }
+    merged
+}
+
+/// Merge two inputs: the buttons are ORed, the analog channels are merged per channel (see
+/// `AnalogInputs`'s `BitOr`) and the raw bytes of the other inputs are ORed
+impl BitOr for Input {
     type Output = Self;
 
     fn bitor(self, rhs: Self) -> Self::Output {
@@ -236,9 +1211,11 @@ impl BitOr for Input {
             numpad: self.numpad | rhs.numpad,
             keypad: self.keypad | rhs.keypad,
             auxiliary: self.auxiliary | rhs.auxiliary,
-            analog: self.analog,
-            other_input_one: self.other_input_one,
-            other_input_two: self.other_input_two,
+            analog: self.analog | rhs.analog,
+            #[cfg(feature = "other-input")]
+            other_input_one: bitor_other(self.other_input_one, rhs.other_input_one),
+            #[cfg(feature = "other-input")]
+            other_input_two: bitor_other(self.other_input_two, rhs.other_input_two),
         }
     }
 }
@@ -249,12 +1226,98 @@ impl BitOrAssign for Input {
     }
 }
 
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq)]
+/// An `Input` stored in its packed form (e.g. for input history) with accessors that unpack
+/// individual sections on demand
+pub struct PackedInput([u8; Input::PACKED_SIZE]);
+
+impl PackedInput {
+    /// The packed bytes of the input
+    pub fn as_bytes(&self) -> &[u8; Input::PACKED_SIZE] {
+        &self.0
+    }
+
+    /// Unpack the numpad input
+    pub fn numpad(&self) -> Numpad {
+        Numpad::unpack(&self.0[0..2]).unwrap()
+    }
+
+    /// Unpack the keypad input
+    pub fn keypad(&self) -> Keypad {
+        Keypad::unpack(&self.0[2..6]).unwrap()
+    }
+
+    /// Unpack the auxiliary input
+    pub fn auxiliary(&self) -> Auxiliary {
+        Auxiliary::unpack(&self.0[6..10]).unwrap()
+    }
+
+    /// Unpack the analog input
+    pub fn analog(&self) -> AnalogInputs {
+        AnalogInputs::unpack(&self.0[10..22]).unwrap()
+    }
+
+    /// Other Input 1
+    #[cfg(feature = "other-input")]
+    pub fn other_input_one(&self) -> &OtherInput {
+        self.0[22..46].try_into().unwrap()
+    }
+
+    /// Other Input 2
+    #[cfg(feature = "other-input")]
+    pub fn other_input_two(&self) -> &OtherInput {
+        self.0[46..70].try_into().unwrap()
+    }
+}
+
+impl From<Input> for PackedInput {
+    fn from(value: Input) -> Self {
+        let mut buffer = [0u8; Input::PACKED_SIZE];
+        value.pack(&mut buffer).unwrap();
+        Self(buffer)
+    }
+}
+
+impl TryFrom<PackedInput> for Input {
+    type Error = PackingError;
+
+    fn try_from(value: PackedInput) -> Result<Self, Self::Error> {
+        Ok(Self {
+            numpad: Numpad::unpack(&value.0[0..2])?,
+            keypad: Keypad::unpack(&value.0[2..6])?,
+            auxiliary: Auxiliary::unpack(&value.0[6..10])?,
+            analog: AnalogInputs::unpack(&value.0[10..22])?,
+            #[cfg(feature = "other-input")]
+            other_input_one: *value.other_input_one(),
+            #[cfg(feature = "other-input")]
+            other_input_two: *value.other_input_two(),
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    extern crate std;
+    use std::vec;
+    use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction};
+
+    use numpad::NumpadBuilder;
     use keypad::KeypadBuilder;
     use auxiliary::AuxiliaryBuilder;
+    use extension_status::DisableReason;
+    use threshold::{AnalogThreshold, ThresholdDirection};
+
+    #[test]
+    fn test_input_request_try_from() {
+        for request in ALL_REQUESTS {
+            assert_eq!(InputRequest::try_from(request as u8), Ok(request));
+        }
+
+        assert_eq!(InputRequest::try_from(0x7F), Err(0x7F));
+        assert_eq!(InputRequest::try_from(0xFF), Err(0xFF));
+    }
 
     #[test]
     fn test_pack_unpack_inputs() {
@@ -319,15 +1382,930 @@ mod tests {
             keypad,
             auxiliary,
             analog: analog_inputs,
+            #[cfg(feature = "other-input")]
             other_input_one: [0u8; 24],
+            #[cfg(feature = "other-input")]
             other_input_two: [255u8; 24],
         };
 
-        let mut buffer = [0u8; 71];
+        let mut buffer = [0u8; Input::PACKED_SIZE];
         inputs.clone().pack(&mut buffer).unwrap();
 
         let decoded_inputs = Input::unpack(&buffer).unwrap();
 
         assert_eq!(inputs, decoded_inputs)
     }
+
+    fn test_input() -> Input {
+        #[cfg_attr(not(feature = "other-input"), allow(unused_mut))]
+        let mut input = Input {
+            numpad: NumpadBuilder::default().one(true).nine(true).build().unwrap(),
+            keypad: KeypadBuilder::default().shift(true).q(true).build().unwrap(),
+            auxiliary: AuxiliaryBuilder::default().tilde(true).build().unwrap(),
+            analog: AnalogInputs {
+                a0: 0x1234,
+                a1: 0x2345,
+                a2: 0x3456,
+                a3: 0x4567,
+                a4: 0x5678,
+                a5: 0x6789,
+            },
+            #[cfg(feature = "other-input")]
+            other_input_one: [0u8; 24],
+            #[cfg(feature = "other-input")]
+            other_input_two: [0u8; 24],
+        };
+        #[cfg(feature = "other-input")]
+        {
+            input.other_input_one[3] = 0x42;
+            input.other_input_two[23] = 0x24;
+        }
+        input
+    }
+
+    #[test]
+    fn test_driver_tolerates_clock_stretch() {
+        let expectations = [
+            Transaction::write_read(0x42, vec![InputRequest::BuildId as u8], vec![0; 8])
+                .with_error(ErrorKind::Bus),
+            Transaction::write_read(0x42, vec![InputRequest::BuildId as u8], b"1a2b3c4d".to_vec()),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let mut driver = InputModuleDriver::new(0x42, i2c.clone());
+        assert_eq!(driver.get_build_id(), Ok(*b"1a2b3c4d"));
+
+        i2c.done();
+    }
+
+    #[test]
+    fn test_driver_input_tolerates_clock_stretch() {
+        let input = test_input();
+        let mut packed = [0u8; Input::PACKED_SIZE];
+        input.pack(&mut packed).unwrap();
+
+        let expectations = [
+            Transaction::write_read(0x42, vec![InputRequest::FullInput as u8], vec![0; Input::PACKED_SIZE])
+                .with_error(ErrorKind::Bus),
+            Transaction::write_read(0x42, vec![InputRequest::FullInput as u8], vec![0; Input::PACKED_SIZE])
+                .with_error(ErrorKind::Bus),
+            Transaction::write_read(0x42, vec![InputRequest::FullInput as u8], packed.to_vec()),
+            Transaction::write_read(0x42, vec![InputRequest::Numpad as u8], vec![0, 0])
+                .with_error(ErrorKind::Bus),
+            Transaction::write_read(0x42, vec![InputRequest::Numpad as u8], packed[0..2].to_vec()),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let mut driver = InputModuleDriver::new(0x42, i2c.clone());
+        assert_eq!(driver.get_input(), Ok(input));
+        assert_eq!(driver.get_numpad(), Ok(input.numpad));
+
+        i2c.done();
+    }
+
+    #[test]
+    fn test_driver_clock_stretch_timeout() {
+        let stretched = Transaction::write_read(0x42, vec![InputRequest::TimedAnalog as u8], vec![0; TimedAnalog::PACKED_SIZE])
+            .with_error(ErrorKind::Bus);
+        let expectations = [stretched.clone(), stretched.clone(), stretched];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let mut driver = InputModuleDriver::new(0x42, i2c.clone());
+        driver.set_stretch_retries(2);
+        assert_eq!(driver.get_timed_analog(), Err(DriverError::Timeout));
+
+        i2c.done();
+    }
+
+    #[test]
+    fn test_driver_consuming_requests_not_retried() {
+        // The module may have popped the event before the bus error, so resending the request
+        // could skip it: the error is returned and the next read gets the event
+        let expectations = [
+            Transaction::write_read(0x42, vec![InputRequest::ThresholdEvents as u8], vec![0x00])
+                .with_error(ErrorKind::Bus),
+            Transaction::write_read(0x42, vec![InputRequest::ThresholdEvents as u8], vec![0x82]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let mut driver = InputModuleDriver::new(0x42, i2c.clone());
+        assert_eq!(driver.get_threshold_event(), Err(DriverError::I2c(ErrorKind::Bus)));
+        assert_eq!(driver.get_threshold_event(), Ok(Some(ThresholdEvent { channel: 2, direction: ThresholdDirection::Falling })));
+
+        i2c.done();
+    }
+
+    #[test]
+    fn test_driver_latched_input_not_retried() {
+        // Reading the input clears the latched buttons, so a resent read could miss a press
+        let expectations = [
+            Transaction::write_read(0x42, vec![InputRequest::Numpad as u8], vec![0, 0])
+                .with_error(ErrorKind::Bus),
+            Transaction::write_read(0x42, vec![InputRequest::Analog as u8], vec![0; 12])
+                .with_error(ErrorKind::Bus),
+            Transaction::write_read(0x42, vec![InputRequest::Analog as u8], vec![0; 12]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let mut driver = InputModuleDriver::new(0x42, i2c.clone());
+        driver.set_mode_flags(MODE_LATCH_KEYS);
+        assert_eq!(driver.get_numpad(), Err(DriverError::I2c(ErrorKind::Bus)));
+        assert!(driver.get_analog().is_ok());
+
+        i2c.done();
+    }
+
+    #[test]
+    fn test_request_idempotent() {
+        assert!(InputRequest::DecodeN.is_idempotent(0));
+        assert!(InputRequest::ReadConfig.is_idempotent(MODE_LATCH_KEYS));
+        assert!(!InputRequest::ThresholdEvents.is_idempotent(0));
+        assert!(!InputRequest::AlertFrame.is_idempotent(0));
+        assert!(!InputRequest::ChangedSections.is_idempotent(0));
+        assert!(InputRequest::Keypad.is_idempotent(0));
+        assert!(!InputRequest::Keypad.is_idempotent(MODE_LATCH_KEYS));
+        assert!(!InputRequest::FullInput.is_idempotent(MODE_ACCUMULATE));
+        assert!(InputRequest::Analog.is_idempotent(MODE_LATCH_KEYS | MODE_ACCUMULATE));
+        assert!(retryable(&[InputRequest::FullInput as u8], 0));
+        assert!(!retryable(&[], 0));
+        assert!(!retryable(&[0xEE], 0));
+    }
+
+    #[test]
+    fn test_driver_other_errors_not_retried() {
+        let expectations = [
+            Transaction::write_read(0x42, vec![InputRequest::Keypad as u8], vec![0; 4])
+                .with_error(ErrorKind::NoAcknowledge(embedded_hal::i2c::NoAcknowledgeSource::Address)),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let mut driver = InputModuleDriver::new(0x42, i2c.clone());
+        assert_eq!(
+            driver.get_keypad(),
+            Err(DriverError::I2c(ErrorKind::NoAcknowledge(embedded_hal::i2c::NoAcknowledgeSource::Address))),
+        );
+
+        i2c.done();
+    }
+
+    #[test]
+    fn test_driver_get_build_id() {
+        let expectations = [
+            Transaction::write_read(0x42, vec![InputRequest::BuildId as u8], b"1a2b3c4d".to_vec()),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let mut driver = InputModuleDriver::new(0x42, i2c.clone());
+        assert_eq!(driver.get_build_id(), Ok(*b"1a2b3c4d"));
+
+        i2c.done();
+    }
+
+    #[test]
+    fn test_driver_get_changed_sections() {
+        let expectations = [
+            Transaction::write_read(0x42, vec![InputRequest::ChangedSections as u8], vec![SectionChanges::KEYPAD]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let mut driver = InputModuleDriver::new(0x42, i2c.clone());
+        let changes = driver.get_changed_sections().unwrap();
+        assert!(changes.contains(SectionChanges::KEYPAD));
+        assert!(!changes.contains(SectionChanges::ANALOG));
+
+        i2c.done();
+    }
+
+    #[test]
+    fn test_driver_get_debug_frame() {
+        let mut input = Input::default();
+        input.numpad.seven = true;
+        let mut response = [0u8; DebugFrame::PACKED_SIZE];
+        DebugFrame::new(&input).pack(&mut response).unwrap();
+        // Corrupt a bit of the packed input so the checksums disagree
+        response[DebugFrame::PACKED_SIZE - 1] ^= 0x80;
+
+        let expectations = [
+            Transaction::write_read(0x42, vec![InputRequest::DebugFrame as u8], response.to_vec()),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let mut driver = InputModuleDriver::new(0x42, i2c.clone());
+        let (frame, checksum) = driver.get_debug_frame().unwrap();
+        assert_eq!(frame.len as usize, Input::PACKED_SIZE);
+        assert_eq!(frame.bytes, response[2..]);
+        assert_ne!(frame.checksum, checksum);
+
+        i2c.done();
+    }
+
+    #[test]
+    fn test_driver_get_change_frame() {
+        let expectations = [
+            Transaction::write_read(
+                0x42,
+                vec![InputRequest::AlertFrame as u8],
+                vec![SectionChanges::NUMPAD, 0b0100_0000, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            ),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let mut driver = InputModuleDriver::new(0x42, i2c.clone());
+        let frame = driver.get_change_frame().unwrap();
+        assert!(frame.changes.contains(SectionChanges::NUMPAD));
+        assert!(frame.numpad.one);
+
+        i2c.done();
+    }
+
+    #[test]
+    fn test_driver_get_command_log() {
+        let mut response = vec![0u8; CommandLog::PACKED_SIZE];
+        response[0..5].copy_from_slice(&[2, InputRequest::Keypad as u8, 4, InputRequest::BuildId as u8, BUILD_ID_LEN as u8]);
+        let expectations = [
+            Transaction::write_read(0x42, vec![InputRequest::CommandLog as u8], response),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let mut driver = InputModuleDriver::new(0x42, i2c.clone());
+        let log = driver.get_command_log().unwrap();
+        assert_eq!(log.len(), 2);
+        assert!(log.iter().map(|entry| entry.opcode).eq([InputRequest::Keypad as u8, InputRequest::BuildId as u8]));
+
+        i2c.done();
+    }
+
+    #[test]
+    fn test_driver_get_timed_analog() {
+        let expectations = [
+            Transaction::write_read(
+                0x42,
+                vec![InputRequest::TimedAnalog as u8],
+                vec![0x10, 0x27, 0, 0, 0x00, 0x08, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            ),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let mut driver = InputModuleDriver::new(0x42, i2c.clone());
+        let timed = driver.get_timed_analog().unwrap();
+        assert_eq!(timed.timestamp_us, 10_000);
+        assert_eq!(timed.analog.a0, 2048);
+
+        i2c.done();
+    }
+
+    #[test]
+    fn test_driver_get_analog_calibrated() {
+        // a0 = 0, a1 = 1024, a2 = 2048, a3 = 4095, a4 = 3072, a5 = 2060
+        let expectations = [
+            Transaction::write_read(
+                0x42,
+                vec![InputRequest::Analog as u8],
+                vec![0x00, 0x00, 0x00, 0x04, 0x00, 0x08, 0xFF, 0x0F, 0x00, 0x0C, 0x0C, 0x08],
+            ),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let cal = Calibration { min: 0, center: 2048, max: 4095, deadzone: 16 };
+
+        let mut driver = InputModuleDriver::new(0x42, i2c.clone());
+        let normalized = driver.get_analog_calibrated(&cal).unwrap();
+        assert_eq!(normalized[0], -1.0);
+        assert!((normalized[1] + 0.5).abs() < 0.01);
+        assert_eq!(normalized[2], 0.0);
+        assert_eq!(normalized[3], 1.0);
+        assert!((normalized[4] - 0.5).abs() < 0.01);
+        // Within the deadzone
+        assert_eq!(normalized[5], 0.0);
+
+        i2c.done();
+    }
+
+    #[test]
+    fn test_driver_report_mode() {
+        let expectations = [
+            Transaction::write(0x42, vec![InputRequest::ReportMode as u8, ReportMode::DigitOnly as u8]),
+            Transaction::write_read(0x42, vec![InputRequest::ReportMode as u8], vec![ReportMode::DigitOnly as u8]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let mut driver = InputModuleDriver::new(0x42, i2c.clone());
+        driver.set_report_mode(ReportMode::DigitOnly).unwrap();
+        assert_eq!(driver.get_report_mode(), Ok(ReportMode::DigitOnly));
+
+        i2c.done();
+    }
+
+    #[test]
+    fn test_driver_get_extension_status() {
+        let expectations = [
+            Transaction::write_read(0x42, vec![InputRequest::ExtensionStatus as u8], vec![4, 2]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let mut driver = InputModuleDriver::new(0x42, i2c.clone());
+        assert_eq!(driver.get_extension_status(), Ok(ExtensionStatus {
+            ext1: Some(DisableReason::Unplugged),
+            ext2: Some(DisableReason::ChecksumErrors),
+        }));
+
+        i2c.done();
+    }
+
+    #[test]
+    fn test_driver_threshold_events() {
+        let mut config = ThresholdConfig::default();
+        config.channels[1] = Some(AnalogThreshold { level: 0x0800, hysteresis: 0x0040 });
+        let mut packed = vec![InputRequest::ThresholdEvents as u8];
+        packed.extend_from_slice(&[0xFF, 0xFF, 0, 0, 0x00, 0x08, 0x40, 0x00]);
+        packed.extend_from_slice(&[0xFF, 0xFF, 0, 0].repeat(4));
+
+        let expectations = [
+            Transaction::write(0x42, packed),
+            Transaction::write_read(0x42, vec![InputRequest::ThresholdEvents as u8], vec![0x81]),
+            Transaction::write_read(0x42, vec![InputRequest::ThresholdEvents as u8], vec![NO_THRESHOLD_EVENT]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let mut driver = InputModuleDriver::new(0x42, i2c.clone());
+        driver.set_thresholds(config).unwrap();
+        assert_eq!(driver.get_threshold_event(), Ok(Some(ThresholdEvent { channel: 1, direction: ThresholdDirection::Falling })));
+        assert_eq!(driver.get_threshold_event(), Ok(None));
+
+        i2c.done();
+    }
+
+    #[test]
+    fn test_driver_malformed_responses() {
+        // A response cut short on the bus reads the missing bytes as zeros
+        let mut packed_instructions = [0u8; 248];
+        DecodeInstructions::default().pack(&mut packed_instructions).unwrap();
+        let mut truncated = packed_instructions;
+        truncated[2..].fill(0);
+
+        let expectations = [
+            Transaction::write_read(0x42, vec![InputRequest::DecodeN as u8, 0], truncated.to_vec()),
+            Transaction::write_read(0x42, vec![InputRequest::ThresholdEvents as u8], vec![0x0A]),
+            Transaction::write_read(0x42, vec![InputRequest::DecodeN as u8, 0], packed_instructions.to_vec()),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let mut driver = InputModuleDriver::new(0x42, i2c.clone());
+        assert_eq!(driver.get_decode_one(), Err(DriverError::Packing(PackingError::InvalidEncoding)));
+        assert_eq!(driver.get_threshold_event(), Err(DriverError::Packing(PackingError::InvalidEncoding)));
+        // The driver keeps working after a malformed response
+        assert_eq!(driver.get_decode_one(), Ok(DecodeInstructions::default()));
+
+        i2c.done();
+    }
+
+    #[cfg(feature = "async")]
+    /// The blocking i2c mock behind the async i2c trait
+    struct AsyncI2cMock(I2cMock);
+
+    #[cfg(feature = "async")]
+    impl embedded_hal::i2c::ErrorType for AsyncI2cMock {
+        type Error = embedded_hal::i2c::ErrorKind;
+    }
+
+    #[cfg(feature = "async")]
+    impl embedded_hal_async::i2c::I2c for AsyncI2cMock {
+        async fn transaction(&mut self, address: u8, operations: &mut [embedded_hal::i2c::Operation<'_>]) -> Result<(), Self::Error> {
+            I2c::transaction(&mut self.0, address, operations)
+        }
+
+        async fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+            I2c::write(&mut self.0, address, bytes)
+        }
+
+        async fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), Self::Error> {
+            I2c::write_read(&mut self.0, address, bytes, buffer)
+        }
+    }
+
+    #[cfg(feature = "async")]
+    /// Poll a future to completion (the mock never pends)
+    fn block_on<F: core::future::Future>(future: F) -> F::Output {
+        let mut future = core::pin::pin!(future);
+        let mut cx = core::task::Context::from_waker(core::task::Waker::noop());
+        loop {
+            if let core::task::Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn test_async_driver_getters() {
+        let input = test_input();
+        let mut packed = [0u8; Input::PACKED_SIZE];
+        input.pack(&mut packed).unwrap();
+        let mut packed_instructions = [0u8; 248];
+        DecodeInstructions::default().pack(&mut packed_instructions).unwrap();
+
+        let expectations = [
+            Transaction::write_read(0x42, vec![InputRequest::FullInput as u8], packed.to_vec()),
+            Transaction::write_read(0x42, vec![InputRequest::Numpad as u8], packed[0..2].to_vec()),
+            Transaction::write_read(0x42, vec![InputRequest::Analog as u8], packed[10..22].to_vec()),
+            Transaction::write_read(0x42, vec![InputRequest::DecodeN as u8, 1], packed_instructions.to_vec()),
+            Transaction::write(0x42, vec![InputRequest::SetAddress as u8, 0x43]),
+            Transaction::write_read(0x43, vec![InputRequest::OtherN as u8, 0], vec![0x5A; 24]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let mut driver = AsyncInputModuleDriver::new(0x42, AsyncI2cMock(i2c.clone()));
+        block_on(async {
+            assert_eq!(driver.get_input().await, Ok(input));
+            assert_eq!(driver.get_numpad().await, Ok(input.numpad));
+            assert_eq!(driver.get_analog().await, Ok(input.analog));
+            assert_eq!(driver.get_decode_two().await, Ok(DecodeInstructions::default()));
+            driver.set_address(0x43).await.unwrap();
+            assert_eq!(driver.get_other_one().await, Ok([0x5A; 24]));
+        });
+
+        i2c.done();
+    }
+
+    #[test]
+    fn test_driver_get_everything() {
+        let input = test_input();
+        let mut packed = [0u8; Input::PACKED_SIZE];
+        input.pack(&mut packed).unwrap();
+        let mut decode_two = DecodeInstructions::default();
+        decode_two.fields[0] = *b"dial\0\0\0\0\0\0";
+        let mut packed_one = [0u8; DecodeInstructions::PACKED_SIZE];
+        DecodeInstructions::default().pack(&mut packed_one).unwrap();
+        let mut packed_two = [0u8; DecodeInstructions::PACKED_SIZE];
+        decode_two.pack(&mut packed_two).unwrap();
+
+        let expectations = [
+            Transaction::write_read(0x42, vec![InputRequest::FullInput as u8], packed.to_vec()),
+            Transaction::write_read(0x42, vec![InputRequest::DecodeN as u8, 0], packed_one.to_vec()),
+            Transaction::write_read(0x42, vec![InputRequest::DecodeN as u8, 1], packed_two.to_vec()),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let mut driver = InputModuleDriver::new(0x42, i2c.clone());
+        assert_eq!(driver.get_everything(), Ok((input, DecodeInstructions::default(), decode_two)));
+
+        i2c.done();
+    }
+
+    #[test]
+    fn test_driver_probe() {
+        let expectations = [
+            Transaction::write(0x42, vec![]),
+            Transaction::write(0x42, vec![])
+                .with_error(ErrorKind::NoAcknowledge(embedded_hal::i2c::NoAcknowledgeSource::Address)),
+            Transaction::write(0x42, vec![]).with_error(ErrorKind::ArbitrationLoss),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let mut driver = InputModuleDriver::new(0x42, i2c.clone());
+        assert_eq!(driver.probe(), Ok(true));
+        assert_eq!(driver.probe(), Ok(false));
+        assert_eq!(driver.probe(), Err(DriverError::I2c(ErrorKind::ArbitrationLoss)));
+
+        i2c.done();
+    }
+
+    /// A valid config moving the module to `address`
+    fn driver_config(address: u8) -> Config {
+        Config {
+            address,
+            poll_rate_ms: 10,
+            calibration: [Calibration { min: 0, center: 2048, max: 4095, deadzone: 16 }; 6],
+            mode_flags: 0,
+            latch_timeout_ms: 1_000,
+            report_mode: ReportMode::Both as u8,
+            max_keys: 0,
+        }
+    }
+
+    #[test]
+    fn test_driver_write_config_moves_address() {
+        let config = driver_config(0x43);
+        let mut packed = vec![InputRequest::WriteConfig as u8];
+        packed.extend_from_slice(&[0u8; CONFIG_LEN]);
+        config.pack(&mut packed[1..]).unwrap();
+
+        let expectations = [
+            Transaction::write(0x42, packed),
+            Transaction::write_read(0x43, vec![InputRequest::BuildId as u8], b"1a2b3c4d".to_vec()),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let mut driver = InputModuleDriver::new(0x42, i2c.clone());
+        driver.write_config(config).unwrap();
+        assert_eq!(driver.get_build_id(), Ok(*b"1a2b3c4d"));
+
+        i2c.done();
+    }
+
+    #[test]
+    fn test_driver_write_config_rejected() {
+        // The module would reject the config and stay on its old address, so it is never written
+        let mut config = driver_config(0x43);
+        config.calibration[2].min = 4000;
+
+        let expectations = [
+            Transaction::write_read(0x42, vec![InputRequest::BuildId as u8], b"1a2b3c4d".to_vec()),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let mut driver = InputModuleDriver::new(0x42, i2c.clone());
+        assert_eq!(driver.write_config(config), Err(DriverError::Config(ConfigError::InvalidCalibration(2))));
+        assert_eq!(driver.get_build_id(), Ok(*b"1a2b3c4d"));
+
+        i2c.done();
+    }
+
+    #[test]
+    fn test_other_request_legacy_aliases() {
+        assert_eq!(InputRequest::OtherOne.other_request(7), InputRequest::OtherN.other_request(0));
+        assert_eq!(InputRequest::OtherTwo.other_request(7), InputRequest::OtherN.other_request(1));
+        assert_eq!(InputRequest::DecodeOne.other_request(7), InputRequest::DecodeN.other_request(0));
+        assert_eq!(InputRequest::DecodeTwo.other_request(7), InputRequest::DecodeN.other_request(1));
+        assert_eq!(InputRequest::OtherN.other_request(5), Some(OtherRequest::Other(5)));
+        assert_eq!(InputRequest::Numpad.other_request(0), None);
+    }
+
+    #[test]
+    fn test_driver_legacy_other_matches_indexed() {
+        let mut other_input = [0u8; 24];
+        other_input[0] = 0x12;
+        other_input[23] = 0x34;
+
+        let expectations = [
+            Transaction::write_read(0x42, vec![InputRequest::OtherN as u8, 0], other_input.to_vec()),
+            Transaction::write_read(0x42, vec![InputRequest::OtherN as u8, 0], other_input.to_vec()),
+            Transaction::write_read(0x42, vec![InputRequest::OtherN as u8, 1], other_input.to_vec()),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let mut driver = InputModuleDriver::new(0x42, i2c.clone());
+        let legacy = driver.get_other_one().unwrap();
+        let indexed = driver.get_other(0).unwrap();
+        assert_eq!(legacy, indexed);
+        assert_eq!(driver.get_other_two().unwrap(), other_input);
+
+        i2c.done();
+    }
+
+    #[test]
+    fn test_driver_get_decoded_one_vec() {
+        let mut decode_instructions = DecodeInstructions::default();
+        decode_instructions.data_sizes[0] = other::DataSize::Two;
+        decode_instructions.data_types[0] = other::DataType::Signed;
+        decode_instructions.fields[0] = *b"x_position";
+        decode_instructions.fields[1] = *b"button\0\0\0\0";
+        let mut packed_instructions = [0u8; 248];
+        decode_instructions.pack(&mut packed_instructions).unwrap();
+
+        let mut other_input = [0u8; 24];
+        other_input[0..2].copy_from_slice(&(-300i16).to_le_bytes());
+        other_input[2] = 1;
+
+        let expectations = [
+            Transaction::write_read(0x42, vec![InputRequest::DecodeN as u8, 0], packed_instructions.to_vec()),
+            Transaction::write_read(0x42, vec![InputRequest::OtherN as u8, 0], other_input.to_vec()),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let mut driver = InputModuleDriver::new(0x42, i2c.clone());
+        let decoded = driver.get_decoded_one_vec().unwrap();
+        assert_eq!(decoded.as_slice(), &[
+            (DecodedValue::I16(-300), *b"x_position"),
+            (DecodedValue::U8(1), *b"button\0\0\0\0"),
+        ]);
+
+        i2c.done();
+    }
+
+    #[test]
+    fn test_driver_validate_decode() {
+        let mut decode_instructions = DecodeInstructions { data_sizes: [other::DataSize::Eight; 24], ..Default::default() };
+        decode_instructions.fields[3] = *b"dropped\0\0\0";
+        let mut packed_instructions = [0u8; 248];
+        decode_instructions.pack(&mut packed_instructions).unwrap();
+
+        let expectations = [
+            Transaction::write_read(0x42, vec![InputRequest::DecodeN as u8, 1], packed_instructions.to_vec()),
+            Transaction::write_read(0x42, vec![InputRequest::DecodeN as u8, 1], packed_instructions.to_vec()),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let mut driver = InputModuleDriver::new(0x42, i2c.clone());
+        assert!(driver.get_decode_two().is_ok());
+        driver.set_validate_decode(true);
+        assert_eq!(driver.get_decode_two(), Err(DriverError::Decode(DecodeError::OutOfBounds)));
+
+        i2c.done();
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn test_async_driver_validate_decode() {
+        let mut decode_instructions = DecodeInstructions { data_sizes: [other::DataSize::Eight; 24], ..Default::default() };
+        decode_instructions.fields[3] = *b"dropped\0\0\0";
+        let mut packed_instructions = [0u8; 248];
+        decode_instructions.pack(&mut packed_instructions).unwrap();
+
+        let expectations = [
+            Transaction::write_read(0x42, vec![InputRequest::DecodeN as u8, 1], packed_instructions.to_vec()),
+            Transaction::write_read(0x42, vec![InputRequest::DecodeN as u8, 1], packed_instructions.to_vec()),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let mut driver = AsyncInputModuleDriver::new(0x42, AsyncI2cMock(i2c.clone()));
+        block_on(async {
+            assert!(driver.get_decode_two().await.is_ok());
+            driver.set_validate_decode(true);
+            assert_eq!(driver.get_decode_two().await, Err(DriverError::Decode(DecodeError::OutOfBounds)));
+        });
+
+        i2c.done();
+    }
+
+    #[test]
+    fn test_pressed_chars_order() {
+        let input = Input {
+            numpad: NumpadBuilder::default().seven(true).two(true).build().unwrap(),
+            keypad: KeypadBuilder::default().z(true).enter(true).c(true).build().unwrap(),
+            auxiliary: AuxiliaryBuilder::default().question(true).tilde(true).exclamation(true).build().unwrap(),
+            ..Default::default()
+        };
+
+        assert!(input.pressed_chars().eq(['2', '7', 'c', 'z', '!', '~', '?']));
+
+        let shifted = Input {
+            keypad: KeypadBuilder::default().shift(true).q(true).build().unwrap(),
+            ..input
+        };
+        assert!(shifted.pressed_chars().eq(['2', '7', 'Q', '!', '~', '?']));
+    }
+
+    #[test]
+    fn test_mask_to_capabilities_two_channels() {
+        let mut input = test_input();
+        input.mask_to_capabilities(&Capabilities { analog_channels: 2, ..Capabilities::ALL });
+
+        assert_eq!(input.analog, AnalogInputs { a0: 0x1234, a1: 0x2345, ..Default::default() });
+        assert_eq!(input.numpad, test_input().numpad);
+        #[cfg(feature = "other-input")]
+        assert_eq!(input.other_input_two, test_input().other_input_two);
+    }
+
+    #[test]
+    fn test_mask_to_capabilities_sections() {
+        let mut input = test_input();
+        input.mask_to_capabilities(&Capabilities {
+            numpad: true,
+            keypad: false,
+            auxiliary: false,
+            analog_channels: 0,
+            other_inputs: 1,
+        });
+
+        assert_eq!(input, Input {
+            numpad: test_input().numpad,
+            #[cfg(feature = "other-input")]
+            other_input_one: test_input().other_input_one,
+            ..Default::default()
+        });
+
+        let mut input = test_input();
+        input.mask_to_capabilities(&Capabilities::ALL);
+        assert_eq!(input, test_input());
+    }
+
+    #[test]
+    fn test_write_summary() {
+        let mut input = Input::from_str_keys("a1!").unwrap();
+        input.keypad.enter = true;
+
+        let mut summary: heapless::String<32> = heapless::String::new();
+        input.write_summary(&mut summary).unwrap();
+        assert_eq!(summary, "1 a ! enter");
+
+        let mut summary: heapless::String<32> = heapless::String::new();
+        Input::default().write_summary(&mut summary).unwrap();
+        assert_eq!(summary, "");
+    }
+
+    #[test]
+    fn test_to_log_line() {
+        let mut input = Input::from_str_keys("7b?").unwrap();
+        input.keypad.enter = true;
+        input.analog.a0 = 2048;
+        input.analog.a5 = 4095;
+
+        let mut buf = [0u8; 64];
+        assert_eq!(input.to_log_line(&mut buf), "keys=7 b ? enter analog=2048,0,0,0,0,4095");
+        assert_eq!(Input::default().to_log_line(&mut buf), "keys= analog=0,0,0,0,0,0");
+
+        let mut short = [0u8; 12];
+        assert_eq!(input.to_log_line(&mut short), "keys=7 b ? e");
+    }
+
+    #[test]
+    fn test_from_str_keys() {
+        let input = Input::from_str_keys("a1!").unwrap();
+        assert_eq!(input, Input {
+            numpad: NumpadBuilder::default().one(true).build().unwrap(),
+            keypad: KeypadBuilder::default().a(true).build().unwrap(),
+            auxiliary: AuxiliaryBuilder::default().exclamation(true).build().unwrap(),
+            ..Default::default()
+        });
+
+        let shifted = Input::from_str_keys("Q\\").unwrap();
+        assert!(shifted.pressed_chars().eq(['Q', '\\']));
+    }
+
+    #[test]
+    fn test_digital_input_ignores_analog() {
+        let mut a = Input::from_str_keys("4k!").unwrap();
+        let mut b = a;
+        a.analog.a0 = 100;
+        b.analog.a0 = 101;
+        #[cfg(feature = "other-input")]
+        {
+            b.other_input_one[0] = 0xFF;
+        }
+
+        assert_ne!(a, b);
+        assert_eq!(DigitalInput(&a), DigitalInput(&b));
+
+        b.keypad.k = false;
+        assert_ne!(DigitalInput(&a), DigitalInput(&b));
+    }
+
+    #[test]
+    fn test_input_is_empty() {
+        let mut input = Input::default();
+        input.analog.a5 = 2048;
+        assert!(input.is_empty());
+
+        input.auxiliary = Input::from_str_keys("@").unwrap().auxiliary;
+        assert!(!input.is_empty());
+    }
+
+    /// A frame with every button pressed, every analog channel set and other input filled
+    fn fully_populated_input() -> Input {
+        Input {
+            numpad: Numpad::from([true; 10]),
+            keypad: Keypad::from([true; 29]),
+            auxiliary: Auxiliary::from([true; 32]),
+            analog: AnalogInputs { a0: 0xFFF, a1: 0xFFF, a2: 0xFFF, a3: 0xFFF, a4: 0xFFF, a5: 0xFFF },
+            #[cfg(feature = "other-input")]
+            other_input_one: [0xFF; 24],
+            #[cfg(feature = "other-input")]
+            other_input_two: [0xFF; 24],
+        }
+    }
+
+    #[test]
+    fn test_numpad_keypad_disjoint_bytes() {
+        let numpad_only = Input { numpad: Numpad::from([true; 10]), ..Default::default() };
+        let keypad_only = Input { keypad: Keypad::from([true; 29]), ..Default::default() };
+
+        let mut numpad_bytes = [0u8; Input::PACKED_SIZE];
+        numpad_only.pack(&mut numpad_bytes).unwrap();
+        let mut keypad_bytes = [0u8; Input::PACKED_SIZE];
+        keypad_only.pack(&mut keypad_bytes).unwrap();
+
+        // The numpad is packed in bytes 0..2 and the keypad in bytes 2..6
+        assert!(numpad_bytes[2..].iter().all(|&byte| byte == 0));
+        assert!(keypad_bytes[..2].iter().all(|&byte| byte == 0) && keypad_bytes[6..].iter().all(|&byte| byte == 0));
+        assert!(numpad_bytes.iter().zip(keypad_bytes).all(|(numpad, keypad)| numpad & keypad == 0));
+    }
+
+    #[test]
+    fn test_numpad_keypad_independent() {
+        let mut buffer = [0u8; Input::PACKED_SIZE];
+
+        // Toggling any digit of a fully populated frame never touches the keypad (and the reverse)
+        for digit in 0..10 {
+            let mut input = fully_populated_input();
+            *input.numpad.digit_mut(digit).unwrap() = false;
+            input.pack(&mut buffer).unwrap();
+            let unpacked = Input::unpack(&buffer).unwrap();
+            assert_eq!(unpacked.keypad, fully_populated_input().keypad);
+            assert_eq!(unpacked, input);
+        }
+
+        for letter in 'a'..='z' {
+            let mut input = fully_populated_input();
+            *input.keypad.letter_mut(letter).unwrap() = false;
+            input.pack(&mut buffer).unwrap();
+            let unpacked = Input::unpack(&buffer).unwrap();
+            assert_eq!(unpacked.numpad, fully_populated_input().numpad);
+            assert_eq!(unpacked, input);
+        }
+    }
+
+    #[test]
+    fn test_input_bitor_keeps_both_analog() {
+        let mut left = Input::from_str_keys("1a").unwrap();
+        left.analog.a0 = 100;
+        left.analog.a2 = analog::ANALOG_DISCONNECTED;
+        let mut right = Input::from_str_keys("2b").unwrap();
+        right.analog.a0 = 50;
+        right.analog.a2 = 300;
+        right.analog.a4 = 4095;
+
+        let merged = left | right;
+        assert_eq!(merged.analog, AnalogInputs { a0: 100, a2: 300, a4: 4095, ..Default::default() });
+        assert_eq!(DigitalInput(&merged), DigitalInput(&Input::from_str_keys("12ab").unwrap()));
+        assert_eq!((right | left).analog, merged.analog);
+    }
+
+    #[test]
+    #[cfg(feature = "other-input")]
+    fn test_input_bitor_ors_other_input() {
+        let mut left = Input::default();
+        left.other_input_one[0] = 0b1010_0000;
+        left.other_input_two[23] = 0x0F;
+        let mut right = Input::default();
+        right.other_input_one[0] = 0b0000_0101;
+        right.other_input_one[5] = 0x80;
+
+        let merged = left | right;
+        assert_eq!(merged.other_input_one[0], 0b1010_0101);
+        assert_eq!(merged.other_input_one[5], 0x80);
+        assert_eq!(merged.other_input_two[23], 0x0F);
+    }
+
+    #[test]
+    fn test_from_str_keys_unmappable() {
+        assert_eq!(Input::from_str_keys("a b"), Err(' '));
+        assert_eq!(Input::from_str_keys("é"), Err('é'));
+    }
+
+    #[test]
+    fn test_packed_input_conversions() {
+        let input = test_input();
+
+        let packed = PackedInput::from(input);
+        let unpacked: Input = packed.try_into().unwrap();
+
+        assert_eq!(input, unpacked);
+    }
+
+    #[test]
+    fn test_packed_input_accessors() {
+        let input = test_input();
+        let packed = PackedInput::from(input);
+
+        assert_eq!(packed.numpad(), input.numpad);
+        assert_eq!(packed.keypad(), input.keypad);
+        assert_eq!(packed.auxiliary(), input.auxiliary);
+        assert_eq!(packed.analog(), input.analog);
+        #[cfg(feature = "other-input")]
+        {
+            assert_eq!(packed.other_input_one(), &input.other_input_one);
+            assert_eq!(packed.other_input_two(), &input.other_input_two);
+        }
+
+        let mut buffer = [0u8; Input::PACKED_SIZE];
+        input.pack(&mut buffer).unwrap();
+        assert_eq!(packed.as_bytes(), &buffer);
+    }
+
+    #[test]
+    #[cfg(feature = "other-input")]
+    fn test_packed_size_with_other_input() {
+        assert_eq!(Input::PACKED_SIZE, 70);
+        assert_eq!(crate::protocol::FULL_FRAME_LEN, 70);
+        assert_eq!(EXTENSION_FULL_INPUT_LEN, 73);
+
+        let mut buffer = [0u8; 70];
+        assert_eq!(test_input().pack(&mut buffer[..69]), Err(PackingError::InvalidBufferSize));
+        test_input().pack(&mut buffer).unwrap();
+        assert_eq!(buffer[22..46], test_input().other_input_one);
+        assert_eq!(Input::unpack(&buffer), Ok(test_input()));
+    }
+
+    #[test]
+    #[cfg(not(feature = "other-input"))]
+    fn test_packed_size_without_other_input() {
+        assert_eq!(Input::PACKED_SIZE, 22);
+        assert_eq!(crate::protocol::FULL_FRAME_LEN, 22);
+        assert_eq!(EXTENSION_FULL_INPUT_LEN, 25);
+
+        let mut buffer = [0u8; 22];
+        assert_eq!(test_input().pack(&mut buffer[..21]), Err(PackingError::InvalidBufferSize));
+        test_input().pack(&mut buffer).unwrap();
+        assert_eq!(Input::unpack(&buffer), Ok(test_input()));
+    }
+
+    #[test]
+    fn test_pack_fills_every_byte() {
+        // Every byte of the buffer is written, whatever it held before
+        let mut zeros = [0x00u8; Input::PACKED_SIZE];
+        let mut ones = [0xFFu8; Input::PACKED_SIZE];
+        test_input().pack(&mut zeros).unwrap();
+        test_input().pack(&mut ones).unwrap();
+        assert_eq!(zeros, ones);
+    }
 }
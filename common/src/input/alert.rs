@@ -0,0 +1,163 @@
+//!
+//! SMBus-Style Change Alerts
+//!
+//! Rather than polling, a program module can wait for the main input module to assert its
+//! attention line (active low, like SMBALERT#).  The line is asserted once any section of the input
+//! changes and stays asserted until the program reads the change frame with the `AlertFrame`
+//! request, which clears the alert.  Every change since the last read is reported, even if the
+//! input changed back before the read.
+//!
+
+use defmt::Format;
+use crate::packing::{Pack, PackingError, Unpack};
+
+use super::{auxiliary::Auxiliary, changes::SectionChanges, keypad::Keypad, numpad::Numpad, Input};
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq, Default)]
+/// The compact frame read after an alert: the sections that changed since the last read followed
+/// by the current buttons (the analog and other inputs are requested separately when flagged)
+pub struct ChangeFrame {
+    /// The sections that changed since the last read
+    pub changes: SectionChanges,
+    /// The current numpad
+    pub numpad: Numpad,
+    /// The current keypad
+    pub keypad: Keypad,
+    /// The current auxiliary
+    pub auxiliary: Auxiliary,
+}
+
+impl ChangeFrame {
+    /// The length of the packed change frame
+    pub const PACKED_SIZE: usize = SectionChanges::PACKED_SIZE + Numpad::PACKED_SIZE +
+        Keypad::PACKED_SIZE + Auxiliary::PACKED_SIZE;
+}
+
+impl Pack for ChangeFrame {
+    fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
+        if buffer.len() < Self::PACKED_SIZE {
+            return Err(PackingError::InvalidBufferSize);
+        }
+
+        self.changes.pack(&mut buffer[0..1])?;
+        self.numpad.pack(&mut buffer[1..3])?;
+        self.keypad.pack(&mut buffer[3..7])?;
+        self.auxiliary.pack(&mut buffer[7..11])?;
+        Ok(())
+    }
+}
+
+impl Unpack for ChangeFrame {
+    fn unpack(buffer: &[u8]) -> Result<Self, PackingError> where Self: Sized {
+        if buffer.len() < Self::PACKED_SIZE {
+            return Err(PackingError::InvalidBufferSize);
+        }
+
+        Ok(Self {
+            changes: SectionChanges::unpack(&buffer[0..1])?,
+            numpad: Numpad::unpack(&buffer[1..3])?,
+            keypad: Keypad::unpack(&buffer[3..7])?,
+            auxiliary: Auxiliary::unpack(&buffer[7..11])?,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq, Default)]
+/// The module side of the alert: tracks the changes since the last read and whether the attention
+/// line should be asserted
+pub struct ChangeAlert {
+    /// The input at the last update
+    last: Input,
+    /// The sections that changed since the last read
+    pending: SectionChanges,
+}
+
+impl ChangeAlert {
+    /// Feed the next input frame and get whether the attention line should be asserted
+    pub fn update(&mut self, input: &Input) -> bool {
+        self.pending |= SectionChanges::between(&self.last, input);
+        self.last = *input;
+        self.is_asserted()
+    }
+
+    /// Flag that keys were dropped by the simultaneous key limit (asserting the alert until read)
+    pub fn flag_key_overflow(&mut self) {
+        self.pending |= SectionChanges::from_bits(SectionChanges::KEY_OVERFLOW);
+    }
+
+    /// Check if the alert is waiting to be read
+    pub fn is_asserted(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Read the change frame, clearing the alert
+    pub fn read(&mut self) -> ChangeFrame {
+        let frame = ChangeFrame {
+            changes: self.pending,
+            numpad: self.last.numpad,
+            keypad: self.last.keypad,
+            auxiliary: self.last.auxiliary,
+        };
+        self.pending = SectionChanges::default();
+        frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alert_set_until_read() {
+        let mut alert = ChangeAlert::default();
+        assert!(!alert.update(&Input::default()));
+
+        let pressed = Input::from_str_keys("5").unwrap();
+        assert!(alert.update(&pressed));
+        assert!(alert.update(&pressed));
+
+        let frame = alert.read();
+        assert_eq!(frame.changes.bits(), SectionChanges::NUMPAD);
+        assert!(frame.numpad.five);
+        assert!(!alert.is_asserted());
+        assert!(!alert.update(&pressed));
+    }
+
+    #[test]
+    fn test_alert_reports_reverted_change() {
+        let mut alert = ChangeAlert::default();
+        alert.update(&Input::from_str_keys("q").unwrap());
+        alert.update(&Input::default());
+
+        let frame = alert.read();
+        assert_eq!(frame.changes.bits(), SectionChanges::KEYPAD);
+        assert_eq!(frame.keypad, Keypad::default());
+        assert_eq!(alert.read().changes, SectionChanges::default());
+    }
+
+    #[test]
+    fn test_alert_key_overflow() {
+        let mut alert = ChangeAlert::default();
+        alert.update(&Input::default());
+        alert.flag_key_overflow();
+        assert!(alert.is_asserted());
+
+        assert_eq!(alert.read().changes.bits(), SectionChanges::KEY_OVERFLOW);
+        assert!(!alert.is_asserted());
+    }
+
+    #[test]
+    fn test_pack_unpack_change_frame() {
+        let mut alert = ChangeAlert::default();
+        let mut input = Input::from_str_keys("7z?").unwrap();
+        input.analog.a1 = 12;
+        alert.update(&input);
+
+        let frame = alert.read();
+        let mut buffer = [0u8; ChangeFrame::PACKED_SIZE];
+        frame.pack(&mut buffer).unwrap();
+
+        assert_eq!(buffer[0], SectionChanges::NUMPAD | SectionChanges::KEYPAD | SectionChanges::AUXILIARY | SectionChanges::ANALOG);
+        assert_eq!(ChangeFrame::unpack(&buffer), Ok(frame));
+    }
+}
@@ -2,9 +2,10 @@
 //! Analog Inputs
 //! 
 
+use core::ops::{BitOr, Sub};
 use derive_builder::Builder;
 use defmt::Format;
-use crate::packing::{Pack, PackingError, Unpack};
+use crate::packing::{Pack, PackedSize, PackingError, Unpack};
 
 #[derive(Clone, Copy, Debug, Format, PartialEq, Eq, Default, Builder)]
 #[builder(build_fn(error(validation_error = false)))]
@@ -35,9 +36,100 @@ pub struct AnalogInputs {
     pub a5: u16,
 }
 
+/// The largest reading of the 12-bit ADCs used by the input modules
+pub const ADC_MAX: u16 = 4095;
+
+/// The reserved value reporting an analog channel has nothing connected (the 12-bit ADCs can never
+/// produce it)
+pub const ANALOG_DISCONNECTED: u16 = u16::MAX;
+
+/// Readings within this many counts of either rail are treated as a floating (disconnected) pin
+pub const DISCONNECTED_RAIL_MARGIN: u16 = 8;
+
+/// Check if a raw reading is pinned within `margin` counts of either rail.
+///
+/// Joystick potentiometers stop short of the rails so a connected stick never reads this, while
+/// an unplugged stick leaves the ADC pin floating at a rail.
+pub fn reads_disconnected(raw: u16, margin: u16) -> bool {
+    raw <= margin || raw >= ADC_MAX.saturating_sub(margin)
+}
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq)]
+/// Error from building analog inputs with checked channel ranges
+pub enum AnalogRangeError {
+    /// The given channel (0..6) is above the allowed maximum
+    OutOfRange(usize),
+}
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq, Default)]
+/// The signed change of each analog channel between two readings (`current - previous`)
+pub struct AnalogDelta {
+    /// The change of the first analog input
+    pub a0: i32,
+    /// The change of the second analog input
+    pub a1: i32,
+    /// The change of the third analog input
+    pub a2: i32,
+    /// The change of the fourth analog input
+    pub a3: i32,
+    /// The change of the fifth analog input
+    pub a4: i32,
+    /// The change of the sixth analog input
+    pub a5: i32,
+}
+
+impl Sub for AnalogInputs {
+    type Output = AnalogDelta;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        AnalogDelta {
+            a0: self.a0 as i32 - rhs.a0 as i32,
+            a1: self.a1 as i32 - rhs.a1 as i32,
+            a2: self.a2 as i32 - rhs.a2 as i32,
+            a3: self.a3 as i32 - rhs.a3 as i32,
+            a4: self.a4 as i32 - rhs.a4 as i32,
+            a5: self.a5 as i32 - rhs.a5 as i32,
+        }
+    }
+}
+
+impl AnalogInputsBuilder {
+    /// Build the analog inputs, rejecting any channel above `max` (i.e. `ADC_MAX`).
+    ///
+    /// Unlike `build`, this catches producers reporting values the ADC cannot produce.
+    pub fn build_checked(&self, max: u16) -> Result<AnalogInputs, AnalogRangeError> {
+        // Every field has a default so building cannot fail
+        let inputs = self.build().unwrap();
+
+        match inputs.channels().iter().position(|channel| *channel > max) {
+            Some(channel) => Err(AnalogRangeError::OutOfRange(channel)),
+            None => Ok(inputs),
+        }
+    }
+}
+
+/// Merge the analog inputs of two sources channel by channel: each channel takes the larger value,
+/// except that a channel reporting `ANALOG_DISCONNECTED` on one side takes the other side's value
+/// (so an unplugged joystick never hides a connected one)
+impl BitOr for AnalogInputs {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        let rhs = rhs.channels();
+        self.map(|channel, value| match (value, rhs[channel]) {
+            (ANALOG_DISCONNECTED, other) | (other, ANALOG_DISCONNECTED) => other,
+            (value, other) => value.max(other),
+        })
+    }
+}
+
+impl PackedSize for AnalogInputs {
+    const PACKED_LEN: usize = Self::PACKED_SIZE;
+}
+
 impl Pack for AnalogInputs {
     fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
-        if buffer.len() < 12 {
+        if buffer.len() < Self::PACKED_SIZE {
             return Err(PackingError::InvalidBufferSize);
         }
 
@@ -54,7 +146,7 @@ impl Pack for AnalogInputs {
 
 impl Unpack for AnalogInputs {
     fn unpack(buffer: &[u8]) -> Result<Self, PackingError> where Self: Sized {
-        if buffer.len() < 12 {
+        if buffer.len() < Self::PACKED_SIZE {
             return Err(PackingError::InvalidBufferSize);
         }
 
@@ -69,10 +161,450 @@ impl Unpack for AnalogInputs {
     }
 }
 
+/// Fixed-point representation of 1.0 in Q15
+pub const Q15_ONE: u16 = 0x7FFF;
+
+/// atan(2^-i) in degrees scaled by 256 for the CORDIC iterations
+const CORDIC_ANGLES: [i32; 14] = [11520, 6801, 3593, 1824, 916, 458, 229, 115, 57, 29, 14, 7, 4, 2];
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq)]
+/// Calibration information used to convert raw analog readings into signed values
+pub struct Calibration {
+    /// The smallest raw reading the channel produces
+    pub min: u16,
+    /// The raw reading of the channel at rest
+    pub center: u16,
+    /// The largest raw reading the channel produces
+    pub max: u16,
+    /// The distance (in raw counts) from center that is still considered centered
+    pub deadzone: u16,
+}
+
+/// The default deadzone (in raw counts) around the center of a channel
+pub const DEFAULT_DEADZONE: u16 = 16;
+
+impl Default for Calibration {
+    /// Calibration for a 12-bit ADC centered at mid-scale
+    fn default() -> Self {
+        Self {
+            min: 0,
+            center: 2048,
+            max: ADC_MAX,
+            deadzone: DEFAULT_DEADZONE,
+        }
+    }
+}
+
+impl Calibration {
+    /// Convert a raw reading into a value in -1.0..=1.0 (see `normalized_q15`)
+    pub fn normalized(&self, raw: u16) -> f32 {
+        self.normalized_q15(raw) as f32 / Q15_ONE as f32
+    }
+
+    /// Convert a raw reading into a signed Q15 value (-32767..=32767) where 0 is the center and
+    /// +/-32767 are the rails.  Readings within the deadzone are reported as 0 and readings past
+    /// the rails are clamped.
+    pub fn normalized_q15(&self, raw: u16) -> i16 {
+        let (offset, span, sign) = if raw >= self.center {
+            (raw - self.center, self.max.saturating_sub(self.center), 1)
+        } else {
+            (self.center - raw, self.center.saturating_sub(self.min), -1)
+        };
+
+        if offset <= self.deadzone {
+            return 0;
+        }
+
+        let offset = (offset - self.deadzone) as u32;
+        let span = span.saturating_sub(self.deadzone).max(1) as u32;
+        let magnitude = (offset * Q15_ONE as u32 / span).min(Q15_ONE as u32) as i16;
+        sign * magnitude
+    }
+
+    /// Convert a raw reading into a percentage (0..=100) of the calibrated range.
+    ///
+    /// The percentage is referenced to the minimum (0 at `min`, 100 at `max`) rather than the
+    /// center, so a centered channel reads about 50.  The deadzone is not applied, readings past
+    /// the rails are clamped and the result is rounded to the nearest percent (halves round up).
+    pub fn percent(&self, raw: u16) -> u8 {
+        let max = self.max.max(self.min);
+        let offset = (raw.clamp(self.min, max) - self.min) as u32;
+        let span = (max - self.min).max(1) as u32;
+        ((offset * 100 + span / 2) / span) as u8
+    }
+}
+
+impl Pack for Calibration {
+    fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
+        if buffer.len() < 8 {
+            return Err(PackingError::InvalidBufferSize);
+        }
+
+        buffer[0..2].copy_from_slice(&self.min.to_le_bytes());
+        buffer[2..4].copy_from_slice(&self.center.to_le_bytes());
+        buffer[4..6].copy_from_slice(&self.max.to_le_bytes());
+        buffer[6..8].copy_from_slice(&self.deadzone.to_le_bytes());
+
+        Ok(())
+    }
+}
+
+impl Unpack for Calibration {
+    fn unpack(buffer: &[u8]) -> Result<Self, PackingError> where Self: Sized {
+        if buffer.len() < 8 {
+            return Err(PackingError::InvalidBufferSize);
+        }
+
+        Ok(Self {
+            min: u16::from_le_bytes(buffer[0..2].try_into().unwrap()),
+            center: u16::from_le_bytes(buffer[2..4].try_into().unwrap()),
+            max: u16::from_le_bytes(buffer[4..6].try_into().unwrap()),
+            deadzone: u16::from_le_bytes(buffer[6..8].try_into().unwrap()),
+        })
+    }
+}
+
+impl AnalogInputs {
+    /// The length of the packed analog inputs
+    pub const PACKED_SIZE: usize = 12;
+
+    /// Get the analog channels as an array ordered a0..a5
+    pub fn channels(&self) -> [u16; 6] {
+        [self.a0, self.a1, self.a2, self.a3, self.a4, self.a5]
+    }
+
+    /// The value of an analog channel (`None` if `channel` is not 0..6)
+    pub fn channel_mut(&mut self, channel: usize) -> Option<&mut u16> {
+        match channel {
+            0 => Some(&mut self.a0),
+            1 => Some(&mut self.a1),
+            2 => Some(&mut self.a2),
+            3 => Some(&mut self.a3),
+            4 => Some(&mut self.a4),
+            5 => Some(&mut self.a5),
+            _ => None,
+        }
+    }
+
+    /// Check if a channel has something connected (`false` if it reports `ANALOG_DISCONNECTED` or
+    /// `channel` is not 0..6)
+    pub fn is_connected(&self, channel: usize) -> bool {
+        self.channels().get(channel).is_some_and(|value| *value != ANALOG_DISCONNECTED)
+    }
+
+    /// A bitmask of the channels reporting `ANALOG_DISCONNECTED` (bit 0 is a0)
+    pub fn disconnected_mask(&self) -> u8 {
+        self.channels()
+            .iter()
+            .enumerate()
+            .filter(|(_, value)| **value == ANALOG_DISCONNECTED)
+            .fold(0, |mask, (channel, _)| mask | (1 << channel))
+    }
+
+    /// Apply `f` to every channel, passing the channel index (0..6) and value (i.e. a per-channel
+    /// lookup curve)
+    pub fn map(self, f: impl Fn(usize, u16) -> u16) -> AnalogInputs {
+        let mut mapped = self;
+        for (channel, value) in self.channels().into_iter().enumerate() {
+            // `channels` only yields indices 0..6
+            *mapped.channel_mut(channel).unwrap() = f(channel, value);
+        }
+        mapped
+    }
+
+    /// Check if any channel moved more than `threshold` counts since `prev` so ADC jitter does not
+    /// count as a change
+    pub fn changed_beyond(&self, prev: &Self, threshold: u16) -> bool {
+        self.channels()
+            .into_iter()
+            .zip(prev.channels())
+            .any(|(current, previous)| current.abs_diff(previous) > threshold)
+    }
+
+    /// Convert a pair of joystick channels into polar form.
+    ///
+    /// Returns the magnitude in Q15 (0..=32767 representing 0..=1, diagonals are clamped to 1) and
+    /// the angle in whole degrees (0..360) where 0 is +x and 90 is +y.  The angle is computed with
+    /// an integer CORDIC and is accurate to within one degree.  A centered stick reports an angle
+    /// of 0.
+    ///
+    /// # Panics
+    /// If `x_ch` or `y_ch` is not a channel index (0..6)
+    pub fn polar(&self, cal: &Calibration, x_ch: usize, y_ch: usize) -> (u16, u16) {
+        let channels = self.channels();
+        let x = cal.normalized_q15(channels[x_ch]) as i32;
+        let y = cal.normalized_q15(channels[y_ch]) as i32;
+
+        let magnitude = isqrt((x * x + y * y) as u32).min(Q15_ONE as u32) as u16;
+        if magnitude == 0 {
+            return (0, 0);
+        }
+
+        (magnitude, atan2_degrees(y, x))
+    }
+
+    /// Convert every channel into a percentage (0..=100) of the calibrated range (see
+    /// `Calibration::percent`)
+    pub fn percent(&self, cal: &Calibration) -> [u8; 6] {
+        self.channels().map(|raw| cal.percent(raw))
+    }
+}
+
+/// Integer square root (floor)
+fn isqrt(value: u32) -> u32 {
+    if value < 2 {
+        return value;
+    }
+
+    let mut estimate = value;
+    let mut next = estimate.div_ceil(2);
+    while next < estimate {
+        estimate = next;
+        next = (estimate + value / estimate) / 2;
+    }
+    estimate
+}
+
+/// Compute atan2(y, x) in whole degrees (0..360) using a vectoring CORDIC
+fn atan2_degrees(mut y: i32, mut x: i32) -> u16 {
+    // Rotate into the right half-plane so the CORDIC converges
+    let mut angle = 0i32;
+    if x < 0 {
+        x = -x;
+        y = -y;
+        angle = 180 * 256;
+    }
+
+    for (i, step) in CORDIC_ANGLES.iter().enumerate() {
+        let (dx, dy) = (y >> i, x >> i);
+        if y > 0 {
+            x += dx;
+            y -= dy;
+            angle += step;
+        } else {
+            x -= dx;
+            y += dy;
+            angle -= step;
+        }
+    }
+
+    let degrees = (angle + 128).div_euclid(256);
+    degrees.rem_euclid(360) as u16
+}
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq, Default)]
+/// The analog inputs with the time they were sampled, so a program can compute rates (i.e.
+/// joystick velocity) even when the poll rate varies
+pub struct TimedAnalog {
+    /// The time the analog inputs were sampled in microseconds of the module's clock.  The
+    /// timestamp wraps around every 2^32 us (about 71.6 minutes), so only differences between
+    /// timestamps are meaningful (see `elapsed_us_since`).
+    pub timestamp_us: u32,
+    /// The analog inputs
+    pub analog: AnalogInputs,
+}
+
+impl TimedAnalog {
+    /// The length of the packed timed analog inputs (the timestamp followed by the analog inputs)
+    pub const PACKED_SIZE: usize = 4 + AnalogInputs::PACKED_SIZE;
+
+    /// The time between an earlier sample and this sample in microseconds (correct across a
+    /// wraparound as long as the samples are less than 2^32 us apart)
+    pub fn elapsed_us_since(&self, earlier: &Self) -> u32 {
+        self.timestamp_us.wrapping_sub(earlier.timestamp_us)
+    }
+}
+
+impl Pack for TimedAnalog {
+    fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
+        if buffer.len() < Self::PACKED_SIZE {
+            return Err(PackingError::InvalidBufferSize);
+        }
+
+        buffer[0..4].copy_from_slice(&self.timestamp_us.to_le_bytes());
+        self.analog.pack(&mut buffer[4..Self::PACKED_SIZE])
+    }
+}
+
+impl Unpack for TimedAnalog {
+    fn unpack(buffer: &[u8]) -> Result<Self, PackingError> where Self: Sized {
+        if buffer.len() < Self::PACKED_SIZE {
+            return Err(PackingError::InvalidBufferSize);
+        }
+
+        Ok(Self {
+            timestamp_us: u32::from_le_bytes(buffer[0..4].try_into().unwrap()),
+            analog: AnalogInputs::unpack(&buffer[4..Self::PACKED_SIZE])?,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const CALIBRATION: Calibration = Calibration {
+        min: 0,
+        center: 2048,
+        max: 4095,
+        deadzone: 0,
+    };
+
+    #[test]
+    fn test_default_calibration_normalized() {
+        let calibration = Calibration::default();
+
+        assert_eq!(calibration.normalized(0), -1.0);
+        assert_eq!(calibration.normalized(2048), 0.0);
+        assert_eq!(calibration.normalized(2048 - DEFAULT_DEADZONE), 0.0);
+        assert_eq!(calibration.normalized(2048 + DEFAULT_DEADZONE), 0.0);
+        assert_eq!(calibration.normalized(ADC_MAX), 1.0);
+
+        let mut last = -1.0;
+        for raw in (0..=ADC_MAX).step_by(64) {
+            let value = calibration.normalized(raw);
+            assert!((-1.0..=1.0).contains(&value));
+            assert!(value >= last);
+            last = value;
+        }
+    }
+
+    #[test]
+    fn test_percent() {
+        let inputs = AnalogInputs { a0: 2048, a1: 0, a2: 4095, a3: 1024, ..Default::default() };
+        assert_eq!(inputs.percent(&CALIBRATION), [50, 0, 100, 25, 0, 0]);
+    }
+
+    #[test]
+    fn test_percent_offset_calibration() {
+        let cal = Calibration { min: 100, center: 2000, max: 3900, deadzone: 0 };
+        let inputs = AnalogInputs { a0: 2000, a1: 100, a2: 3900, a3: 50, a4: 4095, a5: 119 };
+
+        // Readings past the rails are clamped and 19 counts (0.5%) rounds up
+        assert_eq!(inputs.percent(&cal), [50, 0, 100, 0, 100, 1]);
+    }
+
+    #[test]
+    fn test_polar_center() {
+        let inputs = AnalogInputs {
+            a0: 2048,
+            a1: 2048,
+            ..Default::default()
+        };
+
+        assert_eq!(inputs.polar(&CALIBRATION, 0, 1), (0, 0));
+    }
+
+    #[test]
+    fn test_polar_cardinal_directions() {
+        let right = AnalogInputs { a0: 4095, a1: 2048, ..Default::default() };
+        let up = AnalogInputs { a0: 2048, a1: 4095, ..Default::default() };
+        let left = AnalogInputs { a0: 0, a1: 2048, ..Default::default() };
+        let down = AnalogInputs { a0: 2048, a1: 0, ..Default::default() };
+
+        assert_eq!(right.polar(&CALIBRATION, 0, 1), (Q15_ONE, 0));
+        assert_eq!(up.polar(&CALIBRATION, 0, 1), (Q15_ONE, 90));
+        assert_eq!(left.polar(&CALIBRATION, 0, 1), (Q15_ONE, 180));
+        assert_eq!(down.polar(&CALIBRATION, 0, 1), (Q15_ONE, 270));
+    }
+
+    #[test]
+    fn test_polar_diagonal() {
+        let inputs = AnalogInputs { a2: 4095, a3: 4095, ..Default::default() };
+        let (magnitude, angle) = inputs.polar(&CALIBRATION, 2, 3);
+        assert_eq!(magnitude, Q15_ONE);
+        assert_eq!(angle, 45);
+    }
+
+    #[test]
+    fn test_sub_analog_inputs() {
+        let previous = AnalogInputs { a0: 100, a1: 2048, a2: 7, ..Default::default() };
+        let current = AnalogInputs { a0: 150, a1: 2000, a2: 7, ..Default::default() };
+
+        assert_eq!(current - previous, AnalogDelta { a0: 50, a1: -48, ..Default::default() });
+    }
+
+    #[test]
+    fn test_sub_analog_inputs_full_range() {
+        let low = AnalogInputs::default();
+        let high = AnalogInputs { a0: u16::MAX, a5: u16::MAX, ..Default::default() };
+
+        assert_eq!(high - low, AnalogDelta { a0: 65535, a5: 65535, ..Default::default() });
+        assert_eq!(low - high, AnalogDelta { a0: -65535, a5: -65535, ..Default::default() });
+    }
+
+    #[test]
+    fn test_changed_beyond_jitter() {
+        let prev = AnalogInputs { a0: 2048, a3: 100, ..Default::default() };
+        let current = AnalogInputs { a0: 2051, a3: 97, ..Default::default() };
+
+        assert!(!current.changed_beyond(&prev, 3));
+        assert!(!current.changed_beyond(&current, 0));
+    }
+
+    #[test]
+    fn test_changed_beyond_move() {
+        let prev = AnalogInputs { a0: 2048, a5: 4000, ..Default::default() };
+        let current = AnalogInputs { a0: 2050, a5: 3900, ..Default::default() };
+
+        assert!(current.changed_beyond(&prev, 3));
+        assert!(prev.changed_beyond(&current, 99));
+        assert!(!prev.changed_beyond(&current, 100));
+    }
+
+    #[test]
+    fn test_reads_disconnected() {
+        assert!(reads_disconnected(0, DISCONNECTED_RAIL_MARGIN));
+        assert!(reads_disconnected(DISCONNECTED_RAIL_MARGIN, DISCONNECTED_RAIL_MARGIN));
+        assert!(reads_disconnected(ADC_MAX, DISCONNECTED_RAIL_MARGIN));
+        assert!(reads_disconnected(ADC_MAX - DISCONNECTED_RAIL_MARGIN, DISCONNECTED_RAIL_MARGIN));
+        assert!(!reads_disconnected(DISCONNECTED_RAIL_MARGIN + 1, DISCONNECTED_RAIL_MARGIN));
+        assert!(!reads_disconnected(2048, DISCONNECTED_RAIL_MARGIN));
+        assert!(!reads_disconnected(ADC_MAX - DISCONNECTED_RAIL_MARGIN - 1, DISCONNECTED_RAIL_MARGIN));
+    }
+
+    #[test]
+    fn test_disconnected_channels() {
+        let inputs = AnalogInputs { a0: ANALOG_DISCONNECTED, a1: 0, a4: ANALOG_DISCONNECTED, ..Default::default() };
+
+        assert!(!inputs.is_connected(0));
+        assert!(inputs.is_connected(1));
+        assert!(!inputs.is_connected(6));
+        assert_eq!(inputs.disconnected_mask(), 0b01_0001);
+        assert_eq!(AnalogInputs::default().disconnected_mask(), 0);
+    }
+
+    #[test]
+    fn test_map_doubles_channels() {
+        let inputs = AnalogInputs { a0: 1, a1: 2, a2: 3, a3: 4, a4: 5, a5: 2000 };
+        let doubled = inputs.map(|_, value| value * 2);
+
+        assert_eq!(doubled, AnalogInputs { a0: 2, a1: 4, a2: 6, a3: 8, a4: 10, a5: 4000 });
+        assert_eq!(inputs.map(|channel, _| channel as u16).channels(), [0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_build_checked_in_range() {
+        let inputs = AnalogInputsBuilder::default()
+            .a0(0)
+            .a3(ADC_MAX)
+            .build_checked(ADC_MAX);
+
+        assert_eq!(inputs, Ok(AnalogInputs { a3: ADC_MAX, ..Default::default() }));
+    }
+
+    #[test]
+    fn test_build_checked_over_range() {
+        let builder = AnalogInputsBuilder::default()
+            .a1(100)
+            .a4(ADC_MAX + 1)
+            .clone();
+
+        assert_eq!(builder.build_checked(ADC_MAX), Err(AnalogRangeError::OutOfRange(4)));
+        assert_eq!(builder.build_checked(u16::MAX).unwrap().a4, ADC_MAX + 1);
+        assert_eq!(builder.build().unwrap().a4, ADC_MAX + 1);
+    }
+
     #[test]
     fn test_pack_analog_inputs() {
         let analog_inputs = AnalogInputs {
@@ -128,4 +660,36 @@ mod tests {
             AnalogInputs::unpack(&buffer).unwrap(),
         );
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_timed_analog_elapsed() {
+        let earlier = TimedAnalog { timestamp_us: 1_000, analog: AnalogInputs::default() };
+        let later = TimedAnalog { timestamp_us: 11_000, analog: AnalogInputs { a0: 500, ..Default::default() } };
+        assert_eq!(later.elapsed_us_since(&earlier), 10_000);
+
+        // The velocity of a0 in counts per second
+        let velocity = (later.analog.a0 as i32 - earlier.analog.a0 as i32) as i64 * 1_000_000 /
+            later.elapsed_us_since(&earlier) as i64;
+        assert_eq!(velocity, 50_000);
+    }
+
+    #[test]
+    fn test_timed_analog_elapsed_wraparound() {
+        let earlier = TimedAnalog { timestamp_us: u32::MAX - 499, analog: AnalogInputs::default() };
+        let later = TimedAnalog { timestamp_us: 9_500, analog: AnalogInputs::default() };
+        assert_eq!(later.elapsed_us_since(&earlier), 10_000);
+    }
+
+    #[test]
+    fn test_pack_unpack_timed_analog() {
+        let timed = TimedAnalog {
+            timestamp_us: 0x0102_0304,
+            analog: AnalogInputs { a0: 0x1234, a5: 0x0FFF, ..Default::default() },
+        };
+
+        let mut buffer = [0u8; TimedAnalog::PACKED_SIZE];
+        timed.pack(&mut buffer).unwrap();
+        assert_eq!(buffer[0..4], [0x04, 0x03, 0x02, 0x01]);
+        assert_eq!(TimedAnalog::unpack(&buffer), Ok(timed));
+    }
+}
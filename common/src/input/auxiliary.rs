@@ -6,7 +6,7 @@ use core::ops::{BitOr, BitOrAssign};
 
 use derive_builder::Builder;
 use defmt::Format;
-use crate::packing::{Pack, PackingError, Unpack};
+use crate::packing::{Pack, PackedSize, PackingError, Unpack};
 
 #[derive(Clone, Copy, Debug, Format, PartialEq, Eq, Default, Builder)]
 #[builder(build_fn(error(validation_error = false)))]
@@ -141,9 +141,205 @@ pub struct Auxiliary {
     pub question: bool,
 }
 
+impl Auxiliary {
+    /// The length of the packed auxiliary
+    pub const PACKED_SIZE: usize = 4;
+
+    /// Check if no button is pressed (by checking the packed bytes are all zero)
+    pub fn is_empty(&self) -> bool {
+        let mut buffer = [0u8; 4];
+        self.pack(&mut buffer).unwrap();
+        buffer == [0u8; 4]
+    }
+
+    /// Check if every button pressed in this auxiliary is also pressed in `other` (by comparing the
+    /// packed bytes)
+    pub fn is_subset_of(&self, other: &Self) -> bool {
+        let mut buffer = [0u8; 4];
+        let mut other_buffer = [0u8; 4];
+        self.pack(&mut buffer).unwrap();
+        other.pack(&mut other_buffer).unwrap();
+        buffer.iter().zip(other_buffer).all(|(byte, other_byte)| byte & !other_byte == 0)
+    }
+
+    /// The state of each character labeled by the character (ordered by field)
+    pub fn labeled_keys(&self) -> [(&'static str, bool); 32] {
+        [
+            ("!", self.exclamation),
+            ("@", self.at),
+            ("#", self.hash),
+            ("$", self.dollar),
+            ("%", self.percent),
+            ("^", self.caret),
+            ("&", self.and),
+            ("*", self.star),
+            ("(", self.left_paren),
+            (")", self.right_paren),
+            ("-", self.minus),
+            ("_", self.underscore),
+            ("+", self.plus),
+            ("=", self.equal),
+            ("`", self.backtick),
+            ("~", self.tilde),
+            ("[", self.left_square),
+            ("]", self.right_square),
+            ("{", self.left_curly),
+            ("}", self.right_curly),
+            ("\\", self.backslash),
+            ("|", self.pipe),
+            (";", self.semicolon),
+            (":", self.colon),
+            ("'", self.single_quote),
+            ("\"", self.double_quote),
+            (",", self.comma),
+            (".", self.period),
+            ("<", self.less_than),
+            (">", self.greater_than),
+            ("/", self.forwardslash),
+            ("?", self.question),
+        ]
+    }
+
+    /// The state of a character button (`None` if `c` is not an auxiliary character)
+    pub fn char_mut(&mut self, c: char) -> Option<&mut bool> {
+        match c {
+            '!' => Some(&mut self.exclamation),
+            '@' => Some(&mut self.at),
+            '#' => Some(&mut self.hash),
+            '$' => Some(&mut self.dollar),
+            '%' => Some(&mut self.percent),
+            '^' => Some(&mut self.caret),
+            '&' => Some(&mut self.and),
+            '*' => Some(&mut self.star),
+            '(' => Some(&mut self.left_paren),
+            ')' => Some(&mut self.right_paren),
+            '-' => Some(&mut self.minus),
+            '_' => Some(&mut self.underscore),
+            '+' => Some(&mut self.plus),
+            '=' => Some(&mut self.equal),
+            '`' => Some(&mut self.backtick),
+            '~' => Some(&mut self.tilde),
+            '[' => Some(&mut self.left_square),
+            ']' => Some(&mut self.right_square),
+            '{' => Some(&mut self.left_curly),
+            '}' => Some(&mut self.right_curly),
+            '\\' => Some(&mut self.backslash),
+            '|' => Some(&mut self.pipe),
+            ';' => Some(&mut self.semicolon),
+            ':' => Some(&mut self.colon),
+            '\'' => Some(&mut self.single_quote),
+            '"' => Some(&mut self.double_quote),
+            ',' => Some(&mut self.comma),
+            '.' => Some(&mut self.period),
+            '<' => Some(&mut self.less_than),
+            '>' => Some(&mut self.greater_than),
+            '/' => Some(&mut self.forwardslash),
+            '?' => Some(&mut self.question),
+            _ => None,
+        }
+    }
+
+    /// The pressed characters.
+    ///
+    /// The characters are always yielded in field order (`!` through `?`, matching the packed
+    /// layout), regardless of the order they were pressed in.
+    pub fn pressed_chars(&self) -> impl Iterator<Item = char> {
+        self.labeled_keys()
+            .into_iter()
+            .filter(|(_, pressed)| *pressed)
+            .map(|(label, _)| label.chars().next().unwrap())
+    }
+}
+
+impl From<[bool; 32]> for Auxiliary {
+    /// Build the auxiliary from the state of each button (ordered the field order, `!` to `?`)
+    fn from(value: [bool; 32]) -> Self {
+        let [
+            exclamation,
+            at,
+            hash,
+            dollar,
+            percent,
+            caret,
+            and,
+            star,
+            left_paren,
+            right_paren,
+            minus,
+            underscore,
+            plus,
+            equal,
+            backtick,
+            tilde,
+            left_square,
+            right_square,
+            left_curly,
+            right_curly,
+            backslash,
+            pipe,
+            semicolon,
+            colon,
+            single_quote,
+            double_quote,
+            comma,
+            period,
+            less_than,
+            greater_than,
+            forwardslash,
+            question,
+        ] = value;
+
+        Self {
+            exclamation,
+            at,
+            hash,
+            dollar,
+            percent,
+            caret,
+            and,
+            star,
+            left_paren,
+            right_paren,
+            minus,
+            underscore,
+            plus,
+            equal,
+            backtick,
+            tilde,
+            left_square,
+            right_square,
+            left_curly,
+            right_curly,
+            backslash,
+            pipe,
+            semicolon,
+            colon,
+            single_quote,
+            double_quote,
+            comma,
+            period,
+            less_than,
+            greater_than,
+            forwardslash,
+            question,
+        }
+    }
+}
+
+impl From<Auxiliary> for [bool; 32] {
+    /// The state of each button (ordered the field order, `!` to `?`, matching `labeled_keys`)
+    fn from(value: Auxiliary) -> Self {
+        value.labeled_keys().map(|(_, pressed)| pressed)
+    }
+}
+
+impl PackedSize for Auxiliary {
+    const PACKED_LEN: usize = Self::PACKED_SIZE;
+}
+
 impl Pack for Auxiliary {
     fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
-        if buffer.len() < 4 {
+        if buffer.len() < Self::PACKED_SIZE {
             return Err(PackingError::InvalidBufferSize);
         }
 
@@ -186,7 +382,7 @@ impl Pack for Auxiliary {
 
 impl Unpack for Auxiliary {
     fn unpack(buffer: &[u8]) -> Result<Self, PackingError> where Self: Sized {
-        if buffer.len() < 4 {
+        if buffer.len() < Self::PACKED_SIZE {
             return Err(PackingError::InvalidBufferSize);
         }
 
@@ -367,6 +563,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_auxiliary_array_round_trip() {
+        for index in 0..32 {
+            let mut buttons = [false; 32];
+            buttons[index] = true;
+
+            let auxiliary = Auxiliary::from(buttons);
+            assert_eq!(auxiliary.labeled_keys().iter().position(|(_, pressed)| *pressed), Some(index));
+            assert_eq!(<[bool; 32]>::from(auxiliary), buttons);
+        }
+
+        let auxiliary = Auxiliary::from([true; 32]);
+        assert!(auxiliary.at && auxiliary.question);
+    }
+
+    #[test]
+    fn test_auxiliary_is_subset_of() {
+        let one = AuxiliaryBuilder::default().question(true).build().unwrap();
+        let mut two = one;
+        two.at = true;
+
+        assert!(Auxiliary::default().is_subset_of(&one));
+        assert!(one.is_subset_of(&two));
+        assert!(!two.is_subset_of(&one));
+    }
+
+    #[test]
+    fn test_auxiliary_is_empty() {
+        assert!(Auxiliary::default().is_empty());
+        assert!(!AuxiliaryBuilder::default().question(true).build().unwrap().is_empty());
+    }
+
     #[test]
     fn test_bitor_auxiliary() {
         let auxiliary1 = AuxiliaryBuilder::create_empty()
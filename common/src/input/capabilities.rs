@@ -0,0 +1,109 @@
+//!
+//! Input Module Capabilities
+//!
+
+use defmt::Format;
+use crate::packing::{Pack, PackingError, Unpack};
+
+/// The most analog channels an input module can declare
+pub const MAX_ANALOG_CHANNELS: u8 = 6;
+
+/// The most other inputs an input module can declare
+pub const MAX_OTHER_INPUTS: u8 = 2;
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq)]
+/// The sections of the input an input module actually fills in (packed into a single byte)
+pub struct Capabilities {
+    /// The numpad is supported
+    pub numpad: bool,
+    /// The keypad is supported
+    pub keypad: bool,
+    /// The auxiliary is supported
+    pub auxiliary: bool,
+    /// The number of analog channels supported (a0 upwards, at most `MAX_ANALOG_CHANNELS`)
+    pub analog_channels: u8,
+    /// The number of other inputs supported (at most `MAX_OTHER_INPUTS`)
+    pub other_inputs: u8,
+}
+
+impl Capabilities {
+    /// The length of the packed capabilities
+    pub const PACKED_SIZE: usize = 1;
+
+    /// Every section of the input is supported
+    pub const ALL: Self = Self {
+        numpad: true,
+        keypad: true,
+        auxiliary: true,
+        analog_channels: MAX_ANALOG_CHANNELS,
+        other_inputs: MAX_OTHER_INPUTS,
+    };
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl Pack for Capabilities {
+    fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
+        if buffer.len() < Self::PACKED_SIZE {
+            return Err(PackingError::InvalidBufferSize);
+        }
+
+        buffer[0] = ((self.other_inputs.min(MAX_OTHER_INPUTS)) << 6) |
+            ((self.auxiliary as u8) << 5) |
+            ((self.keypad as u8) << 4) |
+            ((self.numpad as u8) << 3) |
+            self.analog_channels.min(MAX_ANALOG_CHANNELS);
+        Ok(())
+    }
+}
+
+impl Unpack for Capabilities {
+    fn unpack(buffer: &[u8]) -> Result<Self, PackingError> where Self: Sized {
+        if buffer.len() < Self::PACKED_SIZE {
+            return Err(PackingError::InvalidBufferSize);
+        }
+
+        Ok(Self {
+            numpad: buffer[0] & (1 << 3) != 0,
+            keypad: buffer[0] & (1 << 4) != 0,
+            auxiliary: buffer[0] & (1 << 5) != 0,
+            analog_channels: (buffer[0] & 0b111).min(MAX_ANALOG_CHANNELS),
+            other_inputs: (buffer[0] >> 6).min(MAX_OTHER_INPUTS),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_unpack_capabilities() {
+        let capabilities = Capabilities {
+            numpad: true,
+            keypad: false,
+            auxiliary: true,
+            analog_channels: 2,
+            other_inputs: 1,
+        };
+
+        let mut buffer = [0u8; 1];
+        capabilities.pack(&mut buffer).unwrap();
+
+        assert_eq!(buffer[0], 0b0110_1010);
+        assert_eq!(Capabilities::unpack(&buffer), Ok(capabilities));
+    }
+
+    #[test]
+    fn test_unpack_capabilities_clamped() {
+        let capabilities = Capabilities::unpack(&[0b1100_0111]).unwrap();
+
+        assert_eq!(capabilities.analog_channels, MAX_ANALOG_CHANNELS);
+        assert_eq!(capabilities.other_inputs, MAX_OTHER_INPUTS);
+        assert_eq!(Capabilities::unpack(&[]), Err(PackingError::InvalidBufferSize));
+    }
+}
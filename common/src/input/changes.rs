@@ -0,0 +1,141 @@
+//!
+//! Input Sections Changed Between Frames
+//!
+
+use core::ops::{BitOr, BitOrAssign};
+use defmt::Format;
+use crate::packing::{Pack, PackingError, Unpack};
+
+use super::Input;
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq, Default)]
+/// The sections of the input that changed between two frames (packed as a single byte bitmask)
+pub struct SectionChanges(u8);
+
+impl SectionChanges {
+    /// The length of the packed section changes
+    pub const PACKED_SIZE: usize = 1;
+
+    /// The numpad changed
+    pub const NUMPAD: u8 = 1 << 0;
+    /// The keypad changed
+    pub const KEYPAD: u8 = 1 << 1;
+    /// The auxiliary changed
+    pub const AUXILIARY: u8 = 1 << 2;
+    /// The analog inputs changed
+    pub const ANALOG: u8 = 1 << 3;
+    /// The first other input changed
+    pub const OTHER_ONE: u8 = 1 << 4;
+    /// The second other input changed
+    pub const OTHER_TWO: u8 = 1 << 5;
+    /// Not a section: a frame held more keys than the simultaneous key limit so keys were dropped
+    /// (see `Config::max_keys`)
+    pub const KEY_OVERFLOW: u8 = 1 << 7;
+
+    /// Create the section changes from a raw bitmask
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    /// Find the sections that changed between the previous and current frame
+    pub fn between(previous: &Input, current: &Input) -> Self {
+        let sections = [
+            (Self::NUMPAD, previous.numpad != current.numpad),
+            (Self::KEYPAD, previous.keypad != current.keypad),
+            (Self::AUXILIARY, previous.auxiliary != current.auxiliary),
+            (Self::ANALOG, previous.analog != current.analog),
+            #[cfg(feature = "other-input")]
+            (Self::OTHER_ONE, previous.other_input_one != current.other_input_one),
+            #[cfg(feature = "other-input")]
+            (Self::OTHER_TWO, previous.other_input_two != current.other_input_two),
+        ];
+
+        Self(sections.iter()
+            .filter(|(_, changed)| *changed)
+            .fold(0, |mask, (section, _)| mask | section))
+    }
+
+    /// The raw bitmask
+    pub fn bits(&self) -> u8 {
+        self.0
+    }
+
+    /// Check if a section (i.e. `SectionChanges::KEYPAD`) changed
+    pub fn contains(&self, section: u8) -> bool {
+        self.0 & section != 0
+    }
+
+    /// True if no section changed
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl BitOr for SectionChanges {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for SectionChanges {
+    fn bitor_assign(&mut self, rhs: Self) {
+        *self = *self | rhs;
+    }
+}
+
+impl Pack for SectionChanges {
+    fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
+        if buffer.len() < Self::PACKED_SIZE {
+            return Err(PackingError::InvalidBufferSize);
+        }
+
+        buffer[0] = self.0;
+        Ok(())
+    }
+}
+
+impl Unpack for SectionChanges {
+    fn unpack(buffer: &[u8]) -> Result<Self, PackingError> where Self: Sized {
+        if buffer.len() < Self::PACKED_SIZE {
+            return Err(PackingError::InvalidBufferSize);
+        }
+
+        Ok(Self(buffer[0]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_section_changes_one_section() {
+        let previous = Input::default();
+        let mut current = Input::default();
+        current.keypad.q = true;
+
+        let changes = SectionChanges::between(&previous, &current);
+        assert_eq!(changes.bits(), SectionChanges::KEYPAD);
+        assert!(changes.contains(SectionChanges::KEYPAD));
+        assert!(!changes.contains(SectionChanges::NUMPAD));
+
+        let mut buffer = [0u8; SectionChanges::PACKED_SIZE];
+        changes.pack(&mut buffer).unwrap();
+        assert_eq!(SectionChanges::unpack(&buffer), Ok(changes));
+    }
+
+    #[test]
+    #[cfg(feature = "other-input")]
+    fn test_section_changes_analog_and_other() {
+        let previous = Input::default();
+        let mut current = Input::default();
+        current.analog.a4 = 1;
+        current.other_input_two[23] = 1;
+
+        let changes = SectionChanges::between(&previous, &current);
+        assert_eq!(changes.bits(), SectionChanges::ANALOG | SectionChanges::OTHER_TWO);
+        assert!(SectionChanges::between(&current, &current).is_empty());
+    }
+}
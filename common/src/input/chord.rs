@@ -0,0 +1,112 @@
+//!
+//! Key Chord Detection
+//!
+
+use crate::time::{Clock, TimerDuration, TimerInstant};
+
+use super::Input;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Fires once a chord (a set of buttons held together, i.e. a reset combo) has been held for a
+/// duration.  It will not fire again until the chord is released.
+pub struct ChordDetector {
+    /// The buttons that make up the chord
+    chord: Input,
+    /// The time the chord must be held before firing
+    hold: TimerDuration,
+    /// The time the chord was first held (if it is currently held)
+    held_since: Option<TimerInstant>,
+    /// The chord fired since it was last held
+    fired: bool,
+}
+
+impl ChordDetector {
+    /// Create a new chord detector that fires once the chord has been held for `hold`
+    pub fn new(chord: Input, hold: TimerDuration) -> Self {
+        Self {
+            chord,
+            hold,
+            held_since: None,
+            fired: false,
+        }
+    }
+
+    /// Feed the next input frame and get whether the chord fired
+    pub fn update<C: Clock>(&mut self, clock: &C, input: &Input) -> bool {
+        if !input.holds_chord(&self.chord) {
+            self.held_since = None;
+            self.fired = false;
+            return false;
+        }
+
+        let now = clock.now();
+        let held_since = *self.held_since.get_or_insert(now);
+        if self.fired || now - held_since < self.hold {
+            return false;
+        }
+
+        self.fired = true;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::cell::Cell;
+    use fugit::ExtU64;
+
+    /// A clock that only moves when advanced
+    struct FakeClock(Cell<TimerInstant>);
+
+    impl Clock for FakeClock {
+        fn now(&self) -> TimerInstant {
+            self.0.get()
+        }
+    }
+
+    fn reset_chord() -> Input {
+        let mut chord = Input::default();
+        chord.keypad.shift = true;
+        chord.keypad.r = true;
+        chord
+    }
+
+    #[test]
+    fn test_holds_chord() {
+        let mut input = reset_chord();
+        input.numpad.one = true;
+
+        assert!(input.holds_chord(&reset_chord()));
+        assert!(!reset_chord().holds_chord(&input));
+        assert!(Input::default().holds_chord(&Input::default()));
+    }
+
+    #[test]
+    fn test_chord_fires_once_after_hold() {
+        let clock = FakeClock(Cell::new(TimerInstant::from_ticks(0)));
+        let mut detector = ChordDetector::new(reset_chord(), 500.millis());
+
+        assert!(!detector.update(&clock, &reset_chord()));
+        clock.0.set(clock.0.get() + 499.millis());
+        assert!(!detector.update(&clock, &reset_chord()));
+        clock.0.set(clock.0.get() + 1.millis());
+        assert!(detector.update(&clock, &reset_chord()));
+        clock.0.set(clock.0.get() + 1000.millis());
+        assert!(!detector.update(&clock, &reset_chord()));
+    }
+
+    #[test]
+    fn test_chord_refires_after_release() {
+        let clock = FakeClock(Cell::new(TimerInstant::from_ticks(0)));
+        let mut detector = ChordDetector::new(reset_chord(), 0.millis());
+        let mut partial = reset_chord();
+        partial.keypad.r = false;
+
+        assert!(detector.update(&clock, &reset_chord()));
+        assert!(!detector.update(&clock, &reset_chord()));
+        assert!(!detector.update(&clock, &partial));
+        assert!(detector.update(&clock, &reset_chord()));
+    }
+}
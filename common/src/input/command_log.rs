@@ -0,0 +1,131 @@
+//!
+//! Log of the Latest Requests for Post-Mortem Debugging
+//!
+
+use defmt::Format;
+use heapless::Deque;
+use crate::packing::{Pack, PackingError, Unpack};
+
+use super::InputRequest;
+
+/// The number of requests kept in the command log
+pub const COMMAND_LOG_LEN: usize = 16;
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq)]
+/// A request received by the module
+pub struct CommandEntry {
+    /// The raw opcode of the request (which may not be a known request)
+    pub opcode: u8,
+    /// The length of the response to the request
+    pub response_len: u8,
+}
+
+#[derive(Clone, Debug, Default)]
+/// A ring buffer of the latest requests (the oldest request is dropped once the log is full)
+pub struct CommandLog {
+    entries: Deque<CommandEntry, COMMAND_LOG_LEN>,
+}
+
+impl CommandLog {
+    /// The length of the packed log (the number of entries followed by each entry, oldest first)
+    pub const PACKED_SIZE: usize = 1 + 2 * COMMAND_LOG_LEN;
+
+    /// Create a new empty command log
+    pub const fn new() -> Self {
+        Self {
+            entries: Deque::new(),
+        }
+    }
+
+    /// Record a received opcode (unknown opcodes are answered with a single byte)
+    pub fn record(&mut self, opcode: u8) {
+        let response_len = InputRequest::try_from(opcode).map_or(1, |request| request.response_len());
+        if self.entries.is_full() {
+            self.entries.pop_front();
+        }
+        // There is always room after dropping the oldest entry
+        let _ = self.entries.push_back(CommandEntry { opcode, response_len: response_len as u8 });
+    }
+
+    /// The logged requests, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &CommandEntry> {
+        self.entries.iter()
+    }
+
+    /// The number of logged requests
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if no requests have been logged
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl Pack for &CommandLog {
+    fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
+        if buffer.len() < CommandLog::PACKED_SIZE {
+            return Err(PackingError::InvalidBufferSize);
+        }
+
+        buffer[0..CommandLog::PACKED_SIZE].fill(0);
+        buffer[0] = self.entries.len() as u8;
+        for (chunk, entry) in buffer[1..CommandLog::PACKED_SIZE].chunks_mut(2).zip(self.entries.iter()) {
+            chunk[0] = entry.opcode;
+            chunk[1] = entry.response_len;
+        }
+        Ok(())
+    }
+}
+
+impl Unpack for CommandLog {
+    fn unpack(buffer: &[u8]) -> Result<Self, PackingError> where Self: Sized {
+        if buffer.len() < Self::PACKED_SIZE {
+            return Err(PackingError::InvalidBufferSize);
+        }
+
+        // A corrupt length is capped at the capacity of the log
+        let mut log = Self::default();
+        for chunk in buffer[1..Self::PACKED_SIZE].chunks(2).take(buffer[0] as usize) {
+            let _ = log.entries.push_back(CommandEntry { opcode: chunk[0], response_len: chunk[1] });
+        }
+        Ok(log)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_log_wraparound() {
+        let mut log = CommandLog::default();
+        for opcode in 0..(COMMAND_LOG_LEN as u8 + 3) {
+            log.record(opcode);
+        }
+
+        assert_eq!(log.len(), COMMAND_LOG_LEN);
+        assert_eq!(log.iter().next().unwrap().opcode, 3);
+        assert_eq!(log.iter().last().unwrap().opcode, COMMAND_LOG_LEN as u8 + 2);
+    }
+
+    #[test]
+    fn test_pack_unpack_command_log() {
+        let mut log = CommandLog::default();
+        log.record(InputRequest::Numpad as u8);
+        log.record(0xEE);
+        log.record(InputRequest::FullInput as u8);
+
+        let mut buffer = [0xFFu8; CommandLog::PACKED_SIZE];
+        (&log).pack(&mut buffer).unwrap();
+        assert_eq!(buffer[0..7], [3, InputRequest::Numpad as u8, 2, 0xEE, 1, InputRequest::FullInput as u8, InputRequest::FullInput.response_len() as u8]);
+        assert!(buffer[7..].iter().all(|&byte| byte == 0));
+
+        let unpacked = CommandLog::unpack(&buffer).unwrap();
+        assert!(unpacked.iter().eq(log.iter()));
+
+        buffer[0] = 0xFF;
+        assert_eq!(CommandLog::unpack(&buffer).unwrap().len(), COMMAND_LOG_LEN);
+    }
+}
@@ -0,0 +1,299 @@
+//!
+//! Persistent Input Module Configuration
+//!
+
+use defmt::Format;
+use crate::packing::{Pack, PackingError, Unpack};
+use super::analog::Calibration;
+
+/// The packed length of a config
+pub const CONFIG_LEN: usize = 56;
+
+/// The layout version of the packed config, stored with it in flash and bumped whenever a field is
+/// added so a config stored by an earlier firmware is migrated rather than misread
+pub const CONFIG_LAYOUT_VERSION: u8 = 1;
+
+/// The lowest non-reserved 7-bit i2c address
+pub const MIN_I2C_ADDRESS: u8 = 0x08;
+/// The highest non-reserved 7-bit i2c address
+pub const MAX_I2C_ADDRESS: u8 = 0x77;
+
+/// The slowest allowed poll rate (in milliseconds)
+pub const MAX_POLL_RATE_MS: u16 = 1_000;
+
+/// Mode flag for a board without local buttons (i.e. one that only aggregates extensions)
+pub const MODE_NO_LOCAL_BUTTONS: u8 = 1 << 0;
+
+/// Mode flag for latching pressed buttons until the program reads the input (so a press shorter
+/// than the poll interval of the program is never missed)
+pub const MODE_LATCH_KEYS: u8 = 1 << 1;
+
+/// Mode flag for accumulating the buttons pressed in every frame until the program reads the input
+/// (like `MODE_LATCH_KEYS` without the latch timeout, so the release timing is lost).  With both
+/// flags set the latch timeout applies.
+pub const MODE_ACCUMULATE: u8 = 1 << 2;
+
+/// The mode flags currently understood by the input modules (unknown flags are rejected)
+pub const KNOWN_MODE_FLAGS: u8 = MODE_NO_LOCAL_BUTTONS | MODE_LATCH_KEYS | MODE_ACCUMULATE;
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq, Default)]
+/// How the buttons shared by a digit and multi-tap letters (2-9) are reported
+pub enum ReportMode {
+    /// Report both the digit and the selected letter
+    #[default]
+    Both = 0,
+    /// Only report the digit
+    DigitOnly = 1,
+    /// Only report the selected letter
+    LetterOnly = 2,
+}
+
+impl ReportMode {
+    /// Check if the digit of a shared button is reported
+    pub fn reports_digits(&self) -> bool {
+        *self != ReportMode::LetterOnly
+    }
+
+    /// Check if the selected letter of a shared button is reported
+    pub fn reports_letters(&self) -> bool {
+        *self != ReportMode::DigitOnly
+    }
+}
+
+impl TryFrom<u8> for ReportMode {
+    /// The unknown report mode
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(ReportMode::Both),
+            1 => Ok(ReportMode::DigitOnly),
+            2 => Ok(ReportMode::LetterOnly),
+            _ => Err(value),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq)]
+/// Error from validating a config
+pub enum ConfigError {
+    /// The i2c address is reserved or not a 7-bit address
+    InvalidAddress,
+    /// The poll rate is zero or slower than `MAX_POLL_RATE_MS`
+    InvalidPollRate,
+    /// The calibration for the given channel is not ordered min <= center <= max
+    InvalidCalibration(usize),
+    /// The mode flags contain a flag that is not understood
+    InvalidModeFlags,
+    /// The latch timeout is zero
+    InvalidLatchTimeout,
+    /// The report mode is not a `ReportMode`
+    InvalidReportMode,
+}
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq)]
+/// The full configuration of an input module, written and read as one block so provisioning is atomic
+pub struct Config {
+    /// The i2c address of the input module
+    pub address: u8,
+    /// The time between input updates (in milliseconds)
+    pub poll_rate_ms: u16,
+    /// The calibration of each analog channel (a0..a5)
+    pub calibration: [Calibration; 6],
+    /// Mode flags for the input module
+    pub mode_flags: u8,
+    /// The time after which a latched button the program never read is cleared (in milliseconds,
+    /// so a program that stops polling never leaves a phantom press, see `MODE_LATCH_KEYS`)
+    pub latch_timeout_ms: u16,
+    /// How the buttons shared by a digit and letters are reported (a `ReportMode`)
+    pub report_mode: u8,
+    /// The most keys reported in a single frame (0 for no limit).  The lowest-priority keys of a
+    /// frame over the limit are dropped and the overflow is flagged with
+    /// `SectionChanges::KEY_OVERFLOW`.
+    pub max_keys: u8,
+}
+
+impl Config {
+    /// The length of the packed config
+    pub const PACKED_SIZE: usize = CONFIG_LEN;
+
+    /// Check if the board has local buttons that should be scanned
+    pub fn has_local_buttons(&self) -> bool {
+        self.mode_flags & MODE_NO_LOCAL_BUTTONS == 0
+    }
+
+    /// Check if pressed buttons are latched until the program reads the input
+    pub fn latches_keys(&self) -> bool {
+        self.mode_flags & MODE_LATCH_KEYS != 0
+    }
+
+    /// Check if the buttons pressed in every frame are accumulated until the program reads the input
+    pub fn accumulates_input(&self) -> bool {
+        self.mode_flags & MODE_ACCUMULATE != 0
+    }
+
+    /// How the buttons shared by a digit and letters are reported (`ReportMode::Both` if the
+    /// report mode is invalid)
+    pub fn reporting(&self) -> ReportMode {
+        ReportMode::try_from(self.report_mode).unwrap_or_default()
+    }
+
+    /// Check that every field of the config is valid
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if !(MIN_I2C_ADDRESS..=MAX_I2C_ADDRESS).contains(&self.address) {
+            return Err(ConfigError::InvalidAddress);
+        }
+
+        if self.poll_rate_ms == 0 || self.poll_rate_ms > MAX_POLL_RATE_MS {
+            return Err(ConfigError::InvalidPollRate);
+        }
+
+        for (i, calibration) in self.calibration.iter().enumerate() {
+            if calibration.min > calibration.center || calibration.center > calibration.max {
+                return Err(ConfigError::InvalidCalibration(i));
+            }
+        }
+
+        if self.mode_flags & !KNOWN_MODE_FLAGS != 0 {
+            return Err(ConfigError::InvalidModeFlags);
+        }
+
+        if self.latch_timeout_ms == 0 {
+            return Err(ConfigError::InvalidLatchTimeout);
+        }
+
+        if ReportMode::try_from(self.report_mode).is_err() {
+            return Err(ConfigError::InvalidReportMode);
+        }
+
+        Ok(())
+    }
+}
+
+impl Pack for Config {
+    fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
+        if buffer.len() < CONFIG_LEN {
+            return Err(PackingError::InvalidBufferSize);
+        }
+
+        buffer[0] = self.address;
+        buffer[1..3].copy_from_slice(&self.poll_rate_ms.to_le_bytes());
+        buffer[3] = self.mode_flags;
+        for (i, calibration) in self.calibration.iter().enumerate() {
+            calibration.pack(&mut buffer[(4+(i*8))..(4+((i+1)*8))])?;
+        }
+        buffer[52..54].copy_from_slice(&self.latch_timeout_ms.to_le_bytes());
+        buffer[54] = self.report_mode;
+        buffer[55] = self.max_keys;
+
+        Ok(())
+    }
+}
+
+impl Unpack for Config {
+    fn unpack(buffer: &[u8]) -> Result<Self, PackingError> where Self: Sized {
+        if buffer.len() < CONFIG_LEN {
+            return Err(PackingError::InvalidBufferSize);
+        }
+
+        let mut calibration = [Calibration { min: 0, center: 0, max: 0, deadzone: 0 }; 6];
+        for (i, channel) in calibration.iter_mut().enumerate() {
+            *channel = Calibration::unpack(&buffer[(4+(i*8))..(4+((i+1)*8))])?;
+        }
+
+        Ok(Self {
+            address: buffer[0],
+            poll_rate_ms: u16::from_le_bytes(buffer[1..3].try_into().unwrap()),
+            mode_flags: buffer[3],
+            calibration,
+            latch_timeout_ms: u16::from_le_bytes(buffer[52..54].try_into().unwrap()),
+            report_mode: buffer[54],
+            max_keys: buffer[55],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config() -> Config {
+        let mut calibration = [Calibration { min: 0, center: 2048, max: 4095, deadzone: 16 }; 6];
+        calibration[1] = Calibration { min: 100, center: 1900, max: 3900, deadzone: 32 };
+
+        Config {
+            address: 0x42,
+            poll_rate_ms: 10,
+            calibration,
+            mode_flags: 0,
+            latch_timeout_ms: 1_000,
+            report_mode: ReportMode::LetterOnly as u8,
+            max_keys: 6,
+        }
+    }
+
+    #[test]
+    fn test_pack_unpack_config() {
+        let config = test_config();
+
+        let mut buffer = [0u8; CONFIG_LEN];
+        config.pack(&mut buffer).unwrap();
+
+        assert_eq!(buffer[0], 0x42);
+        assert_eq!(buffer[1..3], [10, 0]);
+        assert_eq!(buffer[52..54], [0xE8, 0x03]);
+        assert_eq!(buffer[54], ReportMode::LetterOnly as u8);
+        assert_eq!(buffer[55], 6);
+        assert_eq!(config, Config::unpack(&buffer).unwrap());
+    }
+
+    #[test]
+    fn test_pack_config_buffer_too_small() {
+        let mut buffer = [0u8; CONFIG_LEN - 1];
+        assert_eq!(test_config().pack(&mut buffer), Err(PackingError::InvalidBufferSize));
+        assert_eq!(Config::unpack(&buffer), Err(PackingError::InvalidBufferSize));
+    }
+
+    #[test]
+    fn test_validate_config() {
+        assert_eq!(test_config().validate(), Ok(()));
+
+        let mut config = test_config();
+        config.address = 0x03;
+        assert_eq!(config.validate(), Err(ConfigError::InvalidAddress));
+
+        let mut config = test_config();
+        config.poll_rate_ms = 0;
+        assert_eq!(config.validate(), Err(ConfigError::InvalidPollRate));
+
+        let mut config = test_config();
+        config.calibration[4].center = 5000;
+        assert_eq!(config.validate(), Err(ConfigError::InvalidCalibration(4)));
+
+        let mut config = test_config();
+        config.mode_flags = 0x80;
+        assert_eq!(config.validate(), Err(ConfigError::InvalidModeFlags));
+
+        let mut config = test_config();
+        config.mode_flags = MODE_NO_LOCAL_BUTTONS | MODE_LATCH_KEYS | MODE_ACCUMULATE;
+        assert_eq!(config.validate(), Ok(()));
+
+        let mut config = test_config();
+        config.latch_timeout_ms = 0;
+        assert_eq!(config.validate(), Err(ConfigError::InvalidLatchTimeout));
+
+        let mut config = test_config();
+        config.report_mode = 3;
+        assert_eq!(config.validate(), Err(ConfigError::InvalidReportMode));
+        assert_eq!(config.reporting(), ReportMode::Both);
+    }
+
+    #[test]
+    fn test_has_local_buttons() {
+        let mut config = test_config();
+        assert!(config.has_local_buttons());
+
+        config.mode_flags |= MODE_NO_LOCAL_BUTTONS;
+        assert!(!config.has_local_buttons());
+    }
+}
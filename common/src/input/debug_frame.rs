@@ -0,0 +1,110 @@
+//!
+//! Diagnostic Dump of the Packed Input
+//!
+
+use defmt::Format;
+use crate::{crc::crc8, packing::{Pack, PackingError, Unpack}};
+
+use super::Input;
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq)]
+/// The exact packed input bytes behind a small header (length and CRC-8) so a program can confirm
+/// the framing of a transfer without a logic analyzer
+pub struct DebugFrame {
+    /// The number of packed input bytes the module sent
+    pub len: u8,
+    /// The CRC-8 of the packed input computed by the module
+    pub checksum: u8,
+    /// The packed input
+    pub bytes: [u8; Input::PACKED_SIZE],
+}
+
+impl DebugFrame {
+    /// The length of the packed debug frame (the header followed by the packed input)
+    pub const PACKED_SIZE: usize = Input::PACKED_SIZE + 2;
+
+    /// Pack an input into a debug frame
+    pub fn new(input: &Input) -> Self {
+        let mut bytes = [0u8; Input::PACKED_SIZE];
+        input.pack(&mut bytes).unwrap();
+
+        Self {
+            len: Input::PACKED_SIZE as u8,
+            checksum: crc8(&bytes),
+            bytes,
+        }
+    }
+
+    /// The CRC-8 of the received packed input (compare with `checksum`)
+    pub fn computed_checksum(&self) -> u8 {
+        crc8(&self.bytes)
+    }
+
+    /// Check if the header matches the received packed input
+    pub fn is_valid(&self) -> bool {
+        self.len as usize == Input::PACKED_SIZE && self.checksum == self.computed_checksum()
+    }
+}
+
+impl Pack for DebugFrame {
+    fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
+        if buffer.len() < Self::PACKED_SIZE {
+            return Err(PackingError::InvalidBufferSize);
+        }
+
+        buffer[0] = self.len;
+        buffer[1] = self.checksum;
+        buffer[2..Self::PACKED_SIZE].copy_from_slice(&self.bytes);
+        Ok(())
+    }
+}
+
+impl Unpack for DebugFrame {
+    fn unpack(buffer: &[u8]) -> Result<Self, PackingError> where Self: Sized {
+        if buffer.len() < Self::PACKED_SIZE {
+            return Err(PackingError::InvalidBufferSize);
+        }
+
+        Ok(Self {
+            len: buffer[0],
+            checksum: buffer[1],
+            bytes: buffer[2..Self::PACKED_SIZE].try_into().unwrap(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_unpack_debug_frame() {
+        let mut input = Input::default();
+        input.keypad.z = true;
+        input.analog.a2 = 0x0ABC;
+
+        let frame = DebugFrame::new(&input);
+        let mut buffer = [0u8; DebugFrame::PACKED_SIZE];
+        frame.pack(&mut buffer).unwrap();
+
+        assert_eq!(buffer[0] as usize, Input::PACKED_SIZE);
+        assert_eq!(buffer[1], crc8(&buffer[2..]));
+
+        let unpacked = DebugFrame::unpack(&buffer).unwrap();
+        assert_eq!(unpacked, frame);
+        assert!(unpacked.is_valid());
+        assert_eq!(Input::unpack(&unpacked.bytes), Ok(input));
+    }
+
+    #[test]
+    fn test_debug_frame_corrupt() {
+        let mut buffer = [0u8; DebugFrame::PACKED_SIZE];
+        DebugFrame::new(&Input::default()).pack(&mut buffer).unwrap();
+        buffer[10] ^= 0x01;
+
+        let frame = DebugFrame::unpack(&buffer).unwrap();
+        assert!(!frame.is_valid());
+        assert_ne!(frame.checksum, frame.computed_checksum());
+        assert_eq!(DebugFrame::unpack(&buffer[1..]), Err(PackingError::InvalidBufferSize));
+    }
+}
@@ -0,0 +1,140 @@
+//!
+//! Directions of a D-Pad or Joystick
+//!
+
+use defmt::Format;
+
+use super::analog::{AnalogInputs, Calibration, Q15_ONE};
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq)]
+/// Opposing directions of a d-pad that were pushed together (a fault or miscalibration)
+pub enum DPadConflict {
+    /// Up and down were pushed together
+    Vertical,
+    /// Left and right were pushed together
+    Horizontal,
+    /// Up and down, and left and right were pushed together
+    Both,
+}
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq, Default)]
+/// How hard each direction of a d-pad is pushed (0 when released, up to `Q15_ONE` for a joystick
+/// at the rail or a pressed button)
+pub struct DPad {
+    /// The push up
+    pub up: u16,
+    /// The push down
+    pub down: u16,
+    /// The push left
+    pub left: u16,
+    /// The push right
+    pub right: u16,
+}
+
+impl DPad {
+    /// Read four direction buttons as a d-pad
+    pub fn from_buttons(up: bool, down: bool, left: bool, right: bool) -> Self {
+        let push = |pressed: bool| if pressed { Q15_ONE } else { 0 };
+        Self { up: push(up), down: push(down), left: push(left), right: push(right) }
+    }
+
+    /// Read a pair of joystick channels as a d-pad (+x is right and +y is up, see
+    /// `Calibration::normalized_q15`)
+    ///
+    /// # Panics
+    /// If `x_ch` or `y_ch` is not a channel index (0..6)
+    pub fn from_joystick(analog: &AnalogInputs, cal: &Calibration, x_ch: usize, y_ch: usize) -> Self {
+        let channels = analog.channels();
+        let x = cal.normalized_q15(channels[x_ch]);
+        let y = cal.normalized_q15(channels[y_ch]);
+
+        Self {
+            up: y.max(0) as u16,
+            down: y.min(0).unsigned_abs(),
+            left: x.min(0).unsigned_abs(),
+            right: x.max(0) as u16,
+        }
+    }
+
+    /// Combine with another d-pad (i.e. the joysticks of two extensions), keeping the harder push
+    /// of each direction
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            up: self.up.max(other.up),
+            down: self.down.max(other.down),
+            left: self.left.max(other.left),
+            right: self.right.max(other.right),
+        }
+    }
+
+    /// The opposing directions that are both pushed (`None` if the d-pad is consistent)
+    pub fn conflict(&self) -> Option<DPadConflict> {
+        match (self.up != 0 && self.down != 0, self.left != 0 && self.right != 0) {
+            (true, true) => Some(DPadConflict::Both),
+            (true, false) => Some(DPadConflict::Vertical),
+            (false, true) => Some(DPadConflict::Horizontal),
+            (false, false) => None,
+        }
+    }
+
+    /// Resolve opposing directions by keeping the harder push of each axis (releasing both when
+    /// they are pushed equally hard), returning the consistent d-pad and the conflict it had
+    pub fn resolve(self) -> (Self, Option<DPadConflict>) {
+        let (up, down) = resolve_axis(self.up, self.down);
+        let (left, right) = resolve_axis(self.left, self.right);
+        (Self { up, down, left, right }, self.conflict())
+    }
+}
+
+/// Keep the harder of two opposing pushes (releasing both when they are equal)
+fn resolve_axis(a: u16, b: u16) -> (u16, u16) {
+    match a.cmp(&b) {
+        core::cmp::Ordering::Greater => (a, 0),
+        core::cmp::Ordering::Less => (0, b),
+        core::cmp::Ordering::Equal => (0, 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CALIBRATION: Calibration = Calibration { min: 0, center: 2048, max: 4095, deadzone: 0 };
+
+    #[test]
+    fn test_dpad_from_joystick() {
+        let analog = AnalogInputs { a0: 0, a1: 4095, ..Default::default() };
+        let dpad = DPad::from_joystick(&analog, &CALIBRATION, 0, 1);
+
+        assert_eq!(dpad, DPad { up: Q15_ONE, left: Q15_ONE, ..Default::default() });
+        assert_eq!(dpad.conflict(), None);
+    }
+
+    #[test]
+    fn test_dpad_merged_conflict_resolved() {
+        let stick_one = AnalogInputs { a0: 0, a1: 2048, ..Default::default() };
+        let stick_two = AnalogInputs { a0: 3072, a1: 2048, ..Default::default() };
+        let dpad = DPad::from_joystick(&stick_one, &CALIBRATION, 0, 1)
+            .merge(DPad::from_joystick(&stick_two, &CALIBRATION, 0, 1));
+        assert_eq!(dpad.conflict(), Some(DPadConflict::Horizontal));
+
+        // The harder push left wins
+        let (resolved, conflict) = dpad.resolve();
+        assert_eq!(resolved, DPad { left: Q15_ONE, ..Default::default() });
+        assert_eq!(conflict, Some(DPadConflict::Horizontal));
+        assert_eq!(resolved.conflict(), None);
+    }
+
+    #[test]
+    fn test_dpad_buttons_conflict_released() {
+        let dpad = DPad::from_buttons(true, true, true, true);
+        assert_eq!(dpad.conflict(), Some(DPadConflict::Both));
+
+        // Equally hard pushes cannot be resolved so both are released
+        assert_eq!(dpad.resolve(), (DPad::default(), Some(DPadConflict::Both)));
+        assert_eq!(DPad::from_buttons(true, true, false, true).resolve(), (
+            DPad { right: Q15_ONE, ..Default::default() },
+            Some(DPadConflict::Vertical),
+        ));
+    }
+}
@@ -0,0 +1,117 @@
+//!
+//! Connection Status of the Extensions
+//!
+//! The main input module disables an extension rather than trusting it when the extension is
+//! unplugged or its transfers keep failing.  The status records why each extension is disabled so
+//! a program can tell a faulty extension from one that was unplugged.
+//!
+
+use defmt::Format;
+
+use crate::packing::{Pack, PackingError, Unpack};
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq)]
+/// Why the main input module disabled an extension
+pub enum DisableReason {
+    /// The spi transfer with the extension failed
+    SpiError = 1,
+    /// Every re-read of the extension's full input failed its checksum
+    ChecksumErrors = 2,
+    /// The extension never answered with a complete response (it was not ready or is hung)
+    Timeout = 3,
+    /// The extension is not plugged in
+    Unplugged = 4,
+    /// A reason this library does not know (reported by newer firmware)
+    Unknown = 0xFF,
+}
+
+impl TryFrom<u8> for DisableReason {
+    /// The unknown reason
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(DisableReason::SpiError),
+            2 => Ok(DisableReason::ChecksumErrors),
+            3 => Ok(DisableReason::Timeout),
+            4 => Ok(DisableReason::Unplugged),
+            0xFF => Ok(DisableReason::Unknown),
+            _ => Err(value),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq, Default)]
+/// Why each extension is disabled (`None` while the extension is enabled)
+pub struct ExtensionStatus {
+    /// Why extension 1 is disabled
+    pub ext1: Option<DisableReason>,
+    /// Why extension 2 is disabled
+    pub ext2: Option<DisableReason>,
+}
+
+impl ExtensionStatus {
+    /// The length of the packed status
+    pub const PACKED_SIZE: usize = 2;
+}
+
+/// Pack the reason an extension is disabled (0 while it is enabled)
+fn pack_reason(reason: Option<DisableReason>) -> u8 {
+    reason.map_or(0, |reason| reason as u8)
+}
+
+/// Unpack the reason an extension is disabled
+fn unpack_reason(byte: u8) -> Option<DisableReason> {
+    match byte {
+        0 => None,
+        byte => Some(DisableReason::try_from(byte).unwrap_or(DisableReason::Unknown)),
+    }
+}
+
+impl Pack for ExtensionStatus {
+    fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
+        if buffer.len() < Self::PACKED_SIZE {
+            return Err(PackingError::InvalidBufferSize);
+        }
+
+        buffer[0] = pack_reason(self.ext1);
+        buffer[1] = pack_reason(self.ext2);
+        Ok(())
+    }
+}
+
+impl Unpack for ExtensionStatus {
+    fn unpack(buffer: &[u8]) -> Result<Self, PackingError> where Self: Sized {
+        if buffer.len() < Self::PACKED_SIZE {
+            return Err(PackingError::InvalidBufferSize);
+        }
+
+        Ok(Self {
+            ext1: unpack_reason(buffer[0]),
+            ext2: unpack_reason(buffer[1]),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_unpack_extension_status() {
+        let status = ExtensionStatus { ext1: None, ext2: Some(DisableReason::ChecksumErrors) };
+        let mut buffer = [0u8; ExtensionStatus::PACKED_SIZE];
+        status.pack(&mut buffer).unwrap();
+
+        assert_eq!(buffer, [0, 2]);
+        assert_eq!(ExtensionStatus::unpack(&buffer), Ok(status));
+    }
+
+    #[test]
+    fn test_unpack_unknown_reason() {
+        assert_eq!(ExtensionStatus::unpack(&[0x09, 4]), Ok(ExtensionStatus {
+            ext1: Some(DisableReason::Unknown),
+            ext2: Some(DisableReason::Unplugged),
+        }));
+    }
+}
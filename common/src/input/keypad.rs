@@ -6,7 +6,7 @@ use core::ops::{BitOr, BitOrAssign};
 
 use derive_builder::Builder;
 use defmt::Format;
-use crate::packing::{Pack, PackingError, Unpack};
+use crate::packing::{Pack, PackedSize, PackingError, Unpack};
 
 #[derive(Clone, Copy, Debug, Format, PartialEq, Eq, Default, Builder)]
 #[builder(build_fn(error(validation_error = false)))]
@@ -129,9 +129,239 @@ pub struct Keypad {
     pub z: bool,
 }
 
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq)]
+/// A single key of the keypad (shift is a modifier rather than a key)
+pub enum KeypadKey {
+    /// The enter button
+    Enter,
+    /// The backspace button
+    Backspace,
+    /// A letter button (a-z)
+    Letter(char),
+}
+
+impl KeypadKey {
+    /// The ascii character typed by the key: a newline for enter, a backspace (`'\u{8}'`) for
+    /// backspace and the letter (uppercase with `shift`) for a letter
+    pub fn to_char(self, shift: bool) -> char {
+        match self {
+            KeypadKey::Enter => '\n',
+            KeypadKey::Backspace => '\u{8}',
+            KeypadKey::Letter(letter) if shift => letter.to_ascii_uppercase(),
+            KeypadKey::Letter(letter) => letter,
+        }
+    }
+}
+
+impl Keypad {
+    /// The length of the packed keypad
+    pub const PACKED_SIZE: usize = 4;
+
+    /// Check if no button is pressed (by checking the packed bytes are all zero)
+    pub fn is_empty(&self) -> bool {
+        let mut buffer = [0u8; 4];
+        self.pack(&mut buffer).unwrap();
+        buffer == [0u8; 4]
+    }
+
+    /// Check if every button pressed in this keypad is also pressed in `other` (by comparing the
+    /// packed bytes)
+    pub fn is_subset_of(&self, other: &Self) -> bool {
+        let mut buffer = [0u8; 4];
+        let mut other_buffer = [0u8; 4];
+        self.pack(&mut buffer).unwrap();
+        other.pack(&mut other_buffer).unwrap();
+        buffer.iter().zip(other_buffer).all(|(byte, other_byte)| byte & !other_byte == 0)
+    }
+
+    /// The state of each button labeled by its name (ordered shift, enter, backspace, a-z)
+    pub fn labeled_keys(&self) -> [(&'static str, bool); 29] {
+        [
+            ("shift", self.shift),
+            ("enter", self.enter),
+            ("backspace", self.backspace),
+            ("a", self.a),
+            ("b", self.b),
+            ("c", self.c),
+            ("d", self.d),
+            ("e", self.e),
+            ("f", self.f),
+            ("g", self.g),
+            ("h", self.h),
+            ("i", self.i),
+            ("j", self.j),
+            ("k", self.k),
+            ("l", self.l),
+            ("m", self.m),
+            ("n", self.n),
+            ("o", self.o),
+            ("p", self.p),
+            ("q", self.q),
+            ("r", self.r),
+            ("s", self.s),
+            ("t", self.t),
+            ("u", self.u),
+            ("v", self.v),
+            ("w", self.w),
+            ("x", self.x),
+            ("y", self.y),
+            ("z", self.z),
+        ]
+    }
+
+    /// The state of a letter button (`None` if `letter` is not a-z)
+    pub fn letter_mut(&mut self, letter: char) -> Option<&mut bool> {
+        match letter {
+            'a' => Some(&mut self.a),
+            'b' => Some(&mut self.b),
+            'c' => Some(&mut self.c),
+            'd' => Some(&mut self.d),
+            'e' => Some(&mut self.e),
+            'f' => Some(&mut self.f),
+            'g' => Some(&mut self.g),
+            'h' => Some(&mut self.h),
+            'i' => Some(&mut self.i),
+            'j' => Some(&mut self.j),
+            'k' => Some(&mut self.k),
+            'l' => Some(&mut self.l),
+            'm' => Some(&mut self.m),
+            'n' => Some(&mut self.n),
+            'o' => Some(&mut self.o),
+            'p' => Some(&mut self.p),
+            'q' => Some(&mut self.q),
+            'r' => Some(&mut self.r),
+            's' => Some(&mut self.s),
+            't' => Some(&mut self.t),
+            'u' => Some(&mut self.u),
+            'v' => Some(&mut self.v),
+            'w' => Some(&mut self.w),
+            'x' => Some(&mut self.x),
+            'y' => Some(&mut self.y),
+            'z' => Some(&mut self.z),
+            _ => None,
+        }
+    }
+
+    /// The characters of the pressed letter buttons (uppercase while shift is pressed).
+    ///
+    /// The characters are always yielded in alphabetical order (a-z), regardless of the order
+    /// they were pressed in.  Shift, enter, and backspace do not produce characters.
+    pub fn pressed_chars(&self) -> impl Iterator<Item = char> {
+        let shift = self.shift;
+        self.labeled_keys()
+            .into_iter()
+            .skip(3)
+            .filter_map(move |(label, pressed)| {
+                let c = label.chars().next().unwrap();
+                pressed.then(|| if shift { c.to_ascii_uppercase() } else { c })
+            })
+    }
+
+    /// The pressed key with the highest priority, for handling one key at a time (i.e. menu
+    /// navigation).  Enter has the highest priority, then backspace, then the letters in
+    /// alphabetical order.  Shift is ignored.
+    pub fn first_pressed(&self) -> Option<KeypadKey> {
+        self.labeled_keys()
+            .into_iter()
+            .skip(1)
+            .find(|(_, pressed)| *pressed)
+            .map(|(label, _)| match label {
+                "enter" => KeypadKey::Enter,
+                "backspace" => KeypadKey::Backspace,
+                letter => KeypadKey::Letter(letter.chars().next().unwrap()),
+            })
+    }
+
+    /// The character typed by the first pressed key (see `first_pressed` and `KeypadKey::to_char`),
+    /// for feeding a text field one key at a time
+    pub fn first_char(&self) -> Option<char> {
+        self.first_pressed().map(|key| key.to_char(self.shift))
+    }
+}
+
+impl From<[bool; 29]> for Keypad {
+    /// Build the keypad from the state of each button (ordered shift, enter, backspace, a-z)
+    fn from(value: [bool; 29]) -> Self {
+        let [
+            shift,
+            enter,
+            backspace,
+            a,
+            b,
+            c,
+            d,
+            e,
+            f,
+            g,
+            h,
+            i,
+            j,
+            k,
+            l,
+            m,
+            n,
+            o,
+            p,
+            q,
+            r,
+            s,
+            t,
+            u,
+            v,
+            w,
+            x,
+            y,
+            z,
+        ] = value;
+
+        Self {
+            shift,
+            enter,
+            backspace,
+            a,
+            b,
+            c,
+            d,
+            e,
+            f,
+            g,
+            h,
+            i,
+            j,
+            k,
+            l,
+            m,
+            n,
+            o,
+            p,
+            q,
+            r,
+            s,
+            t,
+            u,
+            v,
+            w,
+            x,
+            y,
+            z,
+        }
+    }
+}
+
+impl From<Keypad> for [bool; 29] {
+    /// The state of each button (ordered shift, enter, backspace, a-z, matching `labeled_keys`)
+    fn from(value: Keypad) -> Self {
+        value.labeled_keys().map(|(_, pressed)| pressed)
+    }
+}
+
+impl PackedSize for Keypad {
+    const PACKED_LEN: usize = Self::PACKED_SIZE;
+}
+
 impl Pack for Keypad {
     fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
-        if buffer.len() < 4 {
+        if buffer.len() < Self::PACKED_SIZE {
             return Err(PackingError::InvalidBufferSize);
         }
 
@@ -173,7 +403,7 @@ impl Unpack for Keypad {
     where
         Self: Sized,
     {
-        if buffer.len() < 4 {
+        if buffer.len() < Self::PACKED_SIZE {
             return Err(PackingError::InvalidBufferSize);
         }
 
@@ -218,24 +448,24 @@ impl BitOr for Keypad {
         Self {
             shift: self.shift || rhs.shift,
             a: self.a || rhs.a,
-            b: self.a || rhs.b,
-            c: self.a || rhs.c,
-            d: self.a || rhs.d,
-            e: self.a || rhs.e,
-            f: self.a || rhs.f,
-            g: self.a || rhs.g,
-            h: self.a || rhs.h,
-            i: self.a || rhs.i,
-            j: self.a || rhs.j,
-            k: self.a || rhs.k,
-            l: self.a || rhs.l,
-            m: self.a || rhs.m,
-            n: self.a || rhs.n,
-            o: self.a || rhs.o,
-            p: self.a || rhs.p,
-            q: self.a || rhs.q,
-            r: self.a || rhs.r,
-            s: self.a || rhs.s,
+            b: self.b || rhs.b,
+            c: self.c || rhs.c,
+            d: self.d || rhs.d,
+            e: self.e || rhs.e,
+            f: self.f || rhs.f,
+            g: self.g || rhs.g,
+            h: self.h || rhs.h,
+            i: self.i || rhs.i,
+            j: self.j || rhs.j,
+            k: self.k || rhs.k,
+            l: self.l || rhs.l,
+            m: self.m || rhs.m,
+            n: self.n || rhs.n,
+            o: self.o || rhs.o,
+            p: self.p || rhs.p,
+            q: self.q || rhs.q,
+            r: self.r || rhs.r,
+            s: self.s || rhs.s,
             t: self.t || rhs.t,
             u: self.u || rhs.u,
             v: self.v || rhs.v,
@@ -335,6 +565,83 @@ mod tests {
         assert_eq!(keypad, Keypad::unpack(&buffer).unwrap(),);
     }
 
+    #[test]
+    fn test_keypad_array_round_trip() {
+        for index in 0..29 {
+            let mut buttons = [false; 29];
+            buttons[index] = true;
+
+            let keypad = Keypad::from(buttons);
+            assert_eq!(keypad.labeled_keys().iter().position(|(_, pressed)| *pressed), Some(index));
+            assert_eq!(<[bool; 29]>::from(keypad), buttons);
+        }
+
+        let keypad = Keypad::from([true; 29]);
+        assert!(keypad.enter && keypad.z);
+    }
+
+    #[test]
+    fn test_keypad_first_pressed() {
+        let mut keypad = KeypadBuilder::default().shift(true).m(true).z(true).build().unwrap();
+        assert_eq!(keypad.first_pressed(), Some(KeypadKey::Letter('m')));
+
+        keypad.backspace = true;
+        assert_eq!(keypad.first_pressed(), Some(KeypadKey::Backspace));
+        keypad.enter = true;
+        assert_eq!(keypad.first_pressed(), Some(KeypadKey::Enter));
+    }
+
+    #[test]
+    fn test_keypad_first_pressed_empty() {
+        assert_eq!(Keypad::default().first_pressed(), None);
+
+        // Shift alone is not a key
+        let shift = KeypadBuilder::default().shift(true).build().unwrap();
+        assert_eq!(shift.first_pressed(), None);
+    }
+
+    #[test]
+    fn test_keypad_first_char_shift() {
+        let mut keypad = KeypadBuilder::default().q(true).build().unwrap();
+        assert_eq!(keypad.first_char(), Some('q'));
+        keypad.shift = true;
+        assert_eq!(keypad.first_char(), Some('Q'));
+        assert_eq!(Keypad::default().first_char(), None);
+    }
+
+    #[test]
+    fn test_keypad_first_char_multiple_presses() {
+        let mut keypad = KeypadBuilder::default().shift(true).x(true).c(true).build().unwrap();
+        assert_eq!(keypad.first_char(), Some('C'));
+        assert!(keypad.pressed_chars().eq(['C', 'X']));
+
+        // Shift does not change enter or backspace
+        keypad.backspace = true;
+        assert_eq!(keypad.first_char(), Some('\u{8}'));
+        keypad.enter = true;
+        assert_eq!(keypad.first_char(), Some('\n'));
+        keypad.shift = false;
+        assert_eq!(keypad.first_char(), Some('\n'));
+        assert!(keypad.pressed_chars().eq(['c', 'x']));
+    }
+
+    #[test]
+    fn test_keypad_is_subset_of() {
+        let one = KeypadBuilder::default().z(true).build().unwrap();
+        let mut two = one;
+        two.a = true;
+
+        assert!(Keypad::default().is_subset_of(&one));
+        assert!(one.is_subset_of(&two));
+        assert!(!two.is_subset_of(&one));
+    }
+
+    #[test]
+    fn test_keypad_is_empty() {
+        assert!(Keypad::default().is_empty());
+        assert!(!KeypadBuilder::default().z(true).build().unwrap().is_empty());
+    }
+
     #[test]
     fn test_bitor_keybad() {
         let keypad1 = KeypadBuilder::create_empty()
@@ -357,4 +664,30 @@ mod tests {
         assert!(keypad.f);
         assert!(keypad.z);
     }
+
+    #[test]
+    fn test_bitor_keypad_a_does_not_bleed() {
+        let left = KeypadBuilder::default().a(true).build().unwrap();
+        let right = KeypadBuilder::default().m(true).z(true).build().unwrap();
+
+        let expected = KeypadBuilder::default().a(true).m(true).z(true).build().unwrap();
+        assert_eq!(left | right, expected);
+        assert_eq!(right | left, expected);
+    }
+
+    #[test]
+    fn test_bitor_keypad_every_key_independent() {
+        for left_index in 0..29 {
+            for right_index in 0..29 {
+                let mut left = [false; 29];
+                left[left_index] = true;
+                let mut right = [false; 29];
+                right[right_index] = true;
+                let mut expected = left;
+                expected[right_index] = true;
+
+                assert_eq!(<[bool; 29]>::from(Keypad::from(left) | Keypad::from(right)), expected);
+            }
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,104 @@
+//!
+//! Mapping of a controller's physical inputs onto Input fields
+//!
+
+use defmt::Format;
+use super::Input;
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq)]
+/// The input field a physical button is reported as
+pub enum ButtonTarget {
+    /// A numpad digit (0-9)
+    Digit(u8),
+    /// A keypad letter (a-z)
+    Letter(char),
+    /// The keypad shift
+    Shift,
+    /// The keypad enter
+    Enter,
+    /// The keypad backspace
+    Backspace,
+}
+
+impl ButtonTarget {
+    /// The field of the input this target refers to (`None` for an invalid digit or letter)
+    fn field<'a>(&self, input: &'a mut Input) -> Option<&'a mut bool> {
+        match *self {
+            ButtonTarget::Digit(digit) => input.numpad.digit_mut(digit),
+            ButtonTarget::Letter(letter) => input.keypad.letter_mut(letter),
+            ButtonTarget::Shift => Some(&mut input.keypad.shift),
+            ButtonTarget::Enter => Some(&mut input.keypad.enter),
+            ButtonTarget::Backspace => Some(&mut input.keypad.backspace),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq)]
+/// Maps the `B` buttons and `A` analog readings of a controller onto the fields of an input so
+/// controller variants can be remapped without changing how the pins are read
+pub struct InputMapping<const B: usize, const A: usize> {
+    /// The target of each button
+    pub buttons: [ButtonTarget; B],
+    /// The analog channel (0..6) of each analog reading
+    pub analog: [usize; A],
+}
+
+impl<const B: usize, const A: usize> InputMapping<B, A> {
+    /// Write the button states and analog readings into their mapped fields of the input.
+    ///
+    /// Fields that are not mapped are left untouched and invalid targets are ignored.
+    pub fn apply(&self, input: &mut Input, buttons: [bool; B], analog: [u16; A]) {
+        for (target, pressed) in self.buttons.iter().zip(buttons) {
+            if let Some(field) = target.field(input) {
+                *field = pressed;
+            }
+        }
+
+        for (channel, value) in self.analog.iter().zip(analog) {
+            if let Some(field) = input.analog.channel_mut(*channel) {
+                *field = value;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_mapping() {
+        let mapping = InputMapping {
+            buttons: [ButtonTarget::Letter('a'), ButtonTarget::Letter('b')],
+            analog: [0, 1],
+        };
+
+        let mut input = Input::default();
+        mapping.apply(&mut input, [true, false], [100, 200]);
+
+        assert!(input.keypad.a);
+        assert!(!input.keypad.b);
+        assert_eq!(input.analog.a0, 100);
+        assert_eq!(input.analog.a1, 200);
+    }
+
+    #[test]
+    fn test_apply_alternate_mapping() {
+        let mapping = InputMapping {
+            buttons: [ButtonTarget::Enter, ButtonTarget::Digit(5), ButtonTarget::Letter('?')],
+            analog: [4, 2],
+        };
+
+        let mut input = Input::default();
+        input.keypad.a = true;
+        mapping.apply(&mut input, [true, true, true], [1234, 4321]);
+
+        assert!(input.keypad.enter);
+        assert!(input.numpad.five);
+        assert!(input.keypad.a);
+        assert!(input.pressed_chars().eq(['5', 'a']));
+        assert_eq!(input.analog.a4, 1234);
+        assert_eq!(input.analog.a2, 4321);
+        assert_eq!(input.analog.a0, 0);
+    }
+}
@@ -6,7 +6,7 @@ use core::ops::{BitOr, BitOrAssign};
 
 use derive_builder::Builder;
 use defmt::Format;
-use crate::packing::{Pack, PackingError, Unpack};
+use crate::packing::{Pack, PackedSize, PackingError, Unpack};
 
 #[derive(Clone, Copy, Debug, Format, PartialEq, Eq, Default, Builder)]
 #[builder(build_fn(error(validation_error = false)))]
@@ -53,9 +53,117 @@ pub struct Numpad {
     pub nine: bool,
 }
 
+impl Numpad {
+    /// The length of the packed numpad
+    pub const PACKED_SIZE: usize = 2;
+
+    /// Check if no button is pressed (by checking the packed bytes are all zero)
+    pub fn is_empty(&self) -> bool {
+        let mut buffer = [0u8; 2];
+        self.pack(&mut buffer).unwrap();
+        buffer == [0u8; 2]
+    }
+
+    /// Check if every button pressed in this numpad is also pressed in `other` (by comparing the
+    /// packed bytes)
+    pub fn is_subset_of(&self, other: &Self) -> bool {
+        let mut buffer = [0u8; 2];
+        let mut other_buffer = [0u8; 2];
+        self.pack(&mut buffer).unwrap();
+        other.pack(&mut other_buffer).unwrap();
+        buffer.iter().zip(other_buffer).all(|(byte, other_byte)| byte & !other_byte == 0)
+    }
+
+    /// The state of each button labeled by its digit (ordered 0-9)
+    pub fn labeled_keys(&self) -> [(&'static str, bool); 10] {
+        [
+            ("0", self.zero),
+            ("1", self.one),
+            ("2", self.two),
+            ("3", self.three),
+            ("4", self.four),
+            ("5", self.five),
+            ("6", self.six),
+            ("7", self.seven),
+            ("8", self.eight),
+            ("9", self.nine),
+        ]
+    }
+
+    /// The state of a digit button (`None` if `digit` is not 0-9)
+    pub fn digit_mut(&mut self, digit: u8) -> Option<&mut bool> {
+        match digit {
+            0 => Some(&mut self.zero),
+            1 => Some(&mut self.one),
+            2 => Some(&mut self.two),
+            3 => Some(&mut self.three),
+            4 => Some(&mut self.four),
+            5 => Some(&mut self.five),
+            6 => Some(&mut self.six),
+            7 => Some(&mut self.seven),
+            8 => Some(&mut self.eight),
+            9 => Some(&mut self.nine),
+            _ => None,
+        }
+    }
+
+    /// The digits of the pressed buttons.
+    ///
+    /// The digits are always yielded in ascending order (0-9), regardless of the order they were
+    /// pressed in.
+    pub fn pressed_digits(&self) -> impl Iterator<Item = char> {
+        self.labeled_keys()
+            .into_iter()
+            .filter(|(_, pressed)| *pressed)
+            .map(|(label, _)| label.chars().next().unwrap())
+    }
+}
+
+impl From<[bool; 10]> for Numpad {
+    /// Build the numpad from the state of each button (ordered 0-9)
+    fn from(value: [bool; 10]) -> Self {
+        let [
+            zero,
+            one,
+            two,
+            three,
+            four,
+            five,
+            six,
+            seven,
+            eight,
+            nine,
+        ] = value;
+
+        Self {
+            zero,
+            one,
+            two,
+            three,
+            four,
+            five,
+            six,
+            seven,
+            eight,
+            nine,
+        }
+    }
+}
+
+impl From<Numpad> for [bool; 10] {
+    /// The state of each button (ordered 0-9, matching `labeled_keys`)
+    fn from(value: Numpad) -> Self {
+        value.labeled_keys().map(|(_, pressed)| pressed)
+    }
+}
+
+impl PackedSize for Numpad {
+    const PACKED_LEN: usize = Self::PACKED_SIZE;
+}
+
 impl Pack for Numpad {
     fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
-        if buffer.len() < 2 {
+        if buffer.len() < Self::PACKED_SIZE {
             return Err(PackingError::InvalidBufferSize);
         }
 
@@ -77,7 +185,7 @@ impl Unpack for Numpad {
     where
         Self: Sized,
     {
-        if buffer.len() < 2 {
+        if buffer.len() < Self::PACKED_SIZE {
             return Err(PackingError::InvalidBufferSize);
         }
 
@@ -185,6 +293,50 @@ mod tests {
         assert_eq!(numpad, Numpad::unpack(&buffer).unwrap());
     }
 
+    #[test]
+    fn test_pressed_digits_order() {
+        let numpad = NumpadBuilder::create_empty()
+            .nine(true)
+            .zero(true)
+            .four(true)
+            .build()
+            .unwrap();
+
+        assert!(numpad.pressed_digits().eq(['0', '4', '9']));
+    }
+
+    #[test]
+    fn test_numpad_array_round_trip() {
+        for index in 0..10 {
+            let mut buttons = [false; 10];
+            buttons[index] = true;
+
+            let numpad = Numpad::from(buttons);
+            assert_eq!(numpad.labeled_keys().iter().position(|(_, pressed)| *pressed), Some(index));
+            assert_eq!(<[bool; 10]>::from(numpad), buttons);
+        }
+
+        let numpad = Numpad::from([true; 10]);
+        assert!(numpad.one && numpad.nine);
+    }
+
+    #[test]
+    fn test_numpad_is_subset_of() {
+        let one = NumpadBuilder::default().nine(true).build().unwrap();
+        let mut two = one;
+        two.zero = true;
+
+        assert!(Numpad::default().is_subset_of(&one));
+        assert!(one.is_subset_of(&two));
+        assert!(!two.is_subset_of(&one));
+    }
+
+    #[test]
+    fn test_numpad_is_empty() {
+        assert!(Numpad::default().is_empty());
+        assert!(!NumpadBuilder::default().nine(true).build().unwrap().is_empty());
+    }
+
     #[test]
     fn test_bitor_numpad() {
         let numpad1 = NumpadBuilder::create_empty()
@@ -3,7 +3,8 @@
 //! 
 
 use defmt::Format;
-use crate::packing::{Pack, PackingError, Unpack};
+use heapless::{String, Vec};
+use crate::packing::{Pack, PackedSize, PackingError, Unpack};
 
 /// The data storage for other inputs
 /// 
@@ -34,13 +35,65 @@ pub enum DataType {
     Floating,
 }
 
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// The unique id of an input module.  Zero is reserved on the wire for a module that has not been
+/// assigned an id, so a `ModuleId` is never zero.
+pub struct ModuleId(u16);
+
+impl ModuleId {
+    /// The wire value of a module without an assigned id
+    pub const UNASSIGNED: u16 = 0;
+
+    /// Create a module id (`None` for the reserved `UNASSIGNED` value)
+    pub const fn new(id: u16) -> Option<Self> {
+        if id == Self::UNASSIGNED {
+            None
+        } else {
+            Some(Self(id))
+        }
+    }
+
+    /// The raw id
+    pub const fn get(&self) -> u16 {
+        self.0
+    }
+}
+
+impl TryFrom<u16> for ModuleId {
+    /// The reserved id
+    type Error = u16;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        Self::new(value).ok_or(value)
+    }
+}
+
+impl From<ModuleId> for u16 {
+    fn from(value: ModuleId) -> Self {
+        value.0
+    }
+}
+
 #[derive(Clone, Copy, Debug, Format, PartialEq, Eq)]
 /// For other input, all buffers must be 24 bytes in length.  Within this buffer, the
 /// data can be decoded in any way.  Specifically, in this case, the data will be decoded 
 /// with respect to these instructions
+///
+/// The data sizes are packed self-delimiting (a size is a run of zero bits ended by a one) and the
+/// data types are packed with the fewest bits their field's size allows, so packing only keeps:
+/// - the data sizes until they fill the 24 byte other input, later sizes unpack as `DataSize::One`
+///   (a last size that overflows the other input may unpack as `DataSize::Eight`)
+/// - the data type of every field that fits in the other input (one and two byte floating point
+///   fields cannot be decoded and unpack as `DataType::Unsigned`), later types unpack as
+///   `DataType::Unsigned`
+/// - the module id and every field name byte for byte
+///
+/// Packing unpacked instructions gives the same bytes (pack, unpack, pack is byte identical), but
+/// unpacking bytes with filler bits set after the last size or type and packing again clears them.
+/// Use `canonical_pack` for a byte form that only depends on how the instructions decode.
 pub struct DecodeInstructions {
-    /// The unique id of the input module
-    pub module_id: u16,
+    /// The unique id of the input module (`None` if the module has not been assigned an id)
+    pub module_id: Option<ModuleId>,
     /// The size of each piece of data
     pub data_sizes: [DataSize; 24],
     /// The data type of each piece of data
@@ -52,7 +105,7 @@ pub struct DecodeInstructions {
 impl Default for DecodeInstructions {
     fn default() -> Self {
         Self {
-            module_id: 0,
+            module_id: None,
             data_sizes: [DataSize::One; 24],
             data_types: [DataType::Unsigned; 24],
             fields: [[0u8; 10]; 24],
@@ -60,6 +113,101 @@ impl Default for DecodeInstructions {
     }
 }
 
+impl DecodeInstructions {
+    /// The length of the packed decode instructions
+    pub const PACKED_SIZE: usize = 248;
+
+    /// The number of fields that fit in the 24 byte other input (fields past this are padding)
+    pub fn field_count(&self) -> usize {
+        fitting_fields(&self.data_sizes)
+    }
+
+    /// The number of fields the module declares: every field up to and including the last named
+    /// field (unnamed fields before it are still declared).  Trailing unnamed fields are defaults
+    /// and are not declared.
+    pub fn declared_field_count(&self) -> usize {
+        let field_count = self.field_count();
+        self.fields[..field_count]
+            .iter()
+            .rposition(|field| !trim_name(field).is_empty())
+            .map_or(0, |last| last + 1)
+    }
+
+    /// Check that every named field fits in the 24 byte other input.  A named field past the fields
+    /// that fit (i.e. twelve 8 byte fields) is never decoded, so instructions that fail this check
+    /// would silently drop fields.
+    pub fn validate(&self) -> Result<(), DecodeError> {
+        let field_count = self.field_count();
+        if self.fields[field_count..].iter().any(|field| !trim_name(field).is_empty()) {
+            return Err(DecodeError::OutOfBounds);
+        }
+        Ok(())
+    }
+
+    /// Check if two sets of decode instructions decode other input identically.
+    ///
+    /// Only the module id and the fields that fit in the other input are compared and field names
+    /// are compared after `trim_name`, so differences in padding are ignored.
+    pub fn semantically_eq(&self, other: &Self) -> bool {
+        let field_count = self.field_count();
+
+        self.module_id == other.module_id &&
+            field_count == other.field_count() &&
+            (0..field_count).all(|i| {
+                self.data_sizes[i] == other.data_sizes[i] &&
+                    self.data_types[i] == other.data_types[i] &&
+                    trim_name(&self.fields[i]) == trim_name(&other.fields[i])
+            })
+    }
+
+    /// Check if an extension has been reconfigured (i.e. cached decode instructions are stale)
+    pub fn differs_semantically(&self, other: &Self) -> bool {
+        !self.semantically_eq(other)
+    }
+
+    /// The instructions with everything `semantically_eq` ignores set to a fixed value: the names
+    /// are trimmed and zero padded, the sizes, types and names past the fields that fit are
+    /// defaults, and a size that overflows the other input is `DataSize::Eight`
+    fn canonical(&self) -> Self {
+        let field_count = self.field_count();
+        let mut canonical = Self { module_id: self.module_id, ..Self::default() };
+
+        for i in 0..field_count {
+            canonical.data_sizes[i] = self.data_sizes[i];
+            canonical.data_types[i] = self.data_types[i];
+            let name = trim_name(&self.fields[i]);
+            canonical.fields[i][..name.len()].copy_from_slice(name);
+        }
+
+        // The size after the last field must still overflow the other input or the field count
+        // would change
+        let used: usize = self.data_sizes[..field_count].iter().map(|data_size| *data_size as usize).sum();
+        if field_count < 24 && used < 24 {
+            canonical.data_sizes[field_count] = DataSize::Eight;
+        }
+
+        canonical
+    }
+
+    /// Pack the instructions into a deterministic byte form: instructions that are
+    /// `semantically_eq` always pack to identical bytes (so the bytes can be cached or compared)
+    pub fn canonical_pack(&self, buffer: &mut [u8]) -> Result<(), PackingError> {
+        self.canonical().pack(buffer)
+    }
+}
+
+/// The number of fields with the data sizes that fit in the 24 byte other input
+fn fitting_fields(data_sizes: &[DataSize; 24]) -> usize {
+    let mut cumulative_length = 0;
+    data_sizes
+        .iter()
+        .take_while(|data_size| {
+            cumulative_length += **data_size as usize;
+            cumulative_length <= 24
+        })
+        .count()
+}
+
 impl Pack for [DataSize; 24] {
     fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
         if buffer.len() < 3 {
@@ -140,6 +288,15 @@ impl Unpack for [DataSize; 24] {
                 bit_index += 1;
                 cumulative_length += 1;
                 data_sizes_index += 1;
+            } else if bit_index > 20 && data >> bit_index == 0 {
+                // A size's bit never takes more bits than the size takes bytes, so only a last size
+                // that overflows the other input can be cut off by the end of the 24 bits
+                data_sizes[data_sizes_index] = DataSize::Eight;
+                break;
+            } else {
+                // A run of four zero bits is not the start of any size (i.e. an erased or truncated
+                // buffer), so the sizes can never be decoded
+                return Err(PackingError::InvalidEncoding);
             }
         }
 
@@ -209,6 +366,13 @@ impl Unpack for [DataType; 24] {
                 data_types[data_types_index] = DataType::Unsigned;
                 bit_index += 1;
                 data_types_index += 1;
+            } else if bit_index > 21 && data >> bit_index == 0 {
+                // Only a last type that overflows the 24 bits can be cut off, and it cannot be told
+                // apart from the types that did not fit
+                break;
+            } else {
+                // A run of three zero bits is not the start of any type
+                return Err(PackingError::InvalidEncoding);
             }
         }
 
@@ -216,16 +380,85 @@ impl Unpack for [DataType; 24] {
     }
 }
 
+/// Pack the data type of every field that fits in the other input with the fewest bits its size
+/// allows.  One and two byte fields cannot be floating point, so their type is a single bit (1 for
+/// unsigned, 0 for signed), and four and eight byte fields use the self-delimiting codes of
+/// `[DataType; 24]`.  A type never takes more bits than its field takes bytes, so every type fits in
+/// the 24 bits.
+fn pack_field_types(data_sizes: &[DataSize; 24], data_types: &[DataType; 24], buffer: &mut [u8]) {
+    let mut value = 0u32;
+    let mut bit_index = 0;
+    for (data_size, data_type) in data_sizes.iter().zip(data_types).take(fitting_fields(data_sizes)) {
+        match (data_size, data_type) {
+            (DataSize::One | DataSize::Two, DataType::Signed) => {
+                bit_index += 1;
+            },
+            // One and two byte floating point fields cannot be decoded so they pack as unsigned
+            (DataSize::One | DataSize::Two, _) | (_, DataType::Unsigned) => {
+                value |= 1 << bit_index;
+                bit_index += 1;
+            },
+            (_, DataType::Signed) => {
+                value |= 1 << (bit_index + 1);
+                bit_index += 2;
+            },
+            (_, DataType::Floating) => {
+                value |= 1 << (bit_index + 2);
+                bit_index += 3;
+            },
+        }
+    }
+
+    buffer[0..3].copy_from_slice(&value.to_le_bytes()[0..3]);
+}
+
+/// Unpack the data types packed by `pack_field_types` (the types past the fields that fit in the
+/// other input are `DataType::Unsigned`)
+fn unpack_field_types(data_sizes: &[DataSize; 24], buffer: &[u8]) -> [DataType; 24] {
+    let value = u32::from_le_bytes([buffer[0], buffer[1], buffer[2], 0]);
+    let is_set = |bit_index: usize| value & (1 << bit_index) != 0;
+
+    let mut data_types = [DataType::Unsigned; 24];
+    let mut bit_index = 0;
+    for (data_size, data_type) in data_sizes.iter().zip(data_types.iter_mut()).take(fitting_fields(data_sizes)) {
+        *data_type = match data_size {
+            DataSize::One | DataSize::Two => {
+                bit_index += 1;
+                if is_set(bit_index - 1) { DataType::Unsigned } else { DataType::Signed }
+            },
+            _ if is_set(bit_index) => {
+                bit_index += 1;
+                DataType::Unsigned
+            },
+            _ if is_set(bit_index + 1) => {
+                bit_index += 2;
+                DataType::Signed
+            },
+            _ => {
+                bit_index += 3;
+                DataType::Floating
+            },
+        };
+    }
+
+    data_types
+}
+
+impl PackedSize for DecodeInstructions {
+    const PACKED_LEN: usize = Self::PACKED_SIZE;
+}
+
 impl Pack for DecodeInstructions {
     fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
-        if buffer.len() < 248 {
+        if buffer.len() < Self::PACKED_SIZE {
             return Err(PackingError::InvalidBufferSize);
         }
 
-        buffer[0..2].copy_from_slice(&self.module_id.to_le_bytes());
+        let module_id = self.module_id.map_or(ModuleId::UNASSIGNED, u16::from);
+        buffer[0..2].copy_from_slice(&module_id.to_le_bytes());
 
         self.data_sizes.pack(&mut buffer[2..5])?;
-        self.data_types.pack(&mut buffer[5..8])?;
+        pack_field_types(&self.data_sizes, &self.data_types, &mut buffer[5..8]);
 
         for (i, field) in self.fields.iter().enumerate() {
             buffer[(8+(i*10))..(8+((i+1)*10))].copy_from_slice(field);
@@ -237,14 +470,16 @@ impl Pack for DecodeInstructions {
 
 impl Unpack for DecodeInstructions {
     fn unpack(buffer: &[u8]) -> Result<Self, PackingError> where Self: Sized {
-        if buffer.len() < 8 {
+        // Every field is read, so a short buffer is rejected rather than partially read (a missing
+        // field name could not be told apart from an unused one)
+        if buffer.len() < Self::PACKED_SIZE {
             return Err(PackingError::InvalidBufferSize);
         }
 
-        let module_id = u16::from_le_bytes(buffer[0..2].try_into().unwrap());
+        let module_id = ModuleId::new(u16::from_le_bytes(buffer[0..2].try_into().unwrap()));
 
         let data_sizes = <[DataSize; 24]>::unpack(&buffer[2..5])?;
-        let data_types = <[DataType; 24]>::unpack(&buffer[5..8])?;
+        let data_types = unpack_field_types(&data_sizes, &buffer[5..8]);
 
         let mut fields = [[0u8; 10]; 24];
         for i in 0..24 {
@@ -283,6 +518,173 @@ pub enum DecodedInput<'a> {
     F32{ value: f32, name: &'a[u8; 10]},
     /// An f64
     F64{ value: f64, name: &'a[u8; 10]},
+    /// The raw bytes of a field with a size and type that cannot be decoded (only returned by
+    /// `decode_lenient`)
+    Raw{ bytes: &'a [u8], name: &'a[u8; 10]},
+}
+
+impl<'a> DecodedInput<'a> {
+    /// The full 10 byte field name (including any padding)
+    pub fn raw_name(&self) -> &'a [u8; 10] {
+        match *self {
+            DecodedInput::U8 { name, .. } |
+            DecodedInput::U16 { name, .. } |
+            DecodedInput::U32 { name, .. } |
+            DecodedInput::U64 { name, .. } |
+            DecodedInput::I8 { name, .. } |
+            DecodedInput::I16 { name, .. } |
+            DecodedInput::I32 { name, .. } |
+            DecodedInput::I64 { name, .. } |
+            DecodedInput::F32 { name, .. } |
+            DecodedInput::F64 { name, .. } |
+            DecodedInput::Raw { name, .. } => name,
+        }
+    }
+
+    /// The field name trimmed by `trim_name`
+    pub fn name(&self) -> &'a [u8] {
+        trim_name(self.raw_name())
+    }
+
+    /// The trimmed field name as a str (`None` if the name holds a non-ascii byte)
+    pub fn name_str(&self) -> Option<&'a str> {
+        let name = self.name();
+        // Ascii is always valid utf-8
+        name.is_ascii().then(|| core::str::from_utf8(name).unwrap())
+    }
+}
+
+/// Formats as `name = value`, with any non-ascii byte of the name escaped and raw bytes in hex
+impl core::fmt::Display for DecodedInput<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} = ", self.name().escape_ascii())?;
+        match *self {
+            DecodedInput::U8 { value, .. } => write!(f, "{}", value),
+            DecodedInput::U16 { value, .. } => write!(f, "{}", value),
+            DecodedInput::U32 { value, .. } => write!(f, "{}", value),
+            DecodedInput::U64 { value, .. } => write!(f, "{}", value),
+            DecodedInput::I8 { value, .. } => write!(f, "{}", value),
+            DecodedInput::I16 { value, .. } => write!(f, "{}", value),
+            DecodedInput::I32 { value, .. } => write!(f, "{}", value),
+            DecodedInput::I64 { value, .. } => write!(f, "{}", value),
+            DecodedInput::F32 { value, .. } => write!(f, "{}", value),
+            DecodedInput::F64 { value, .. } => write!(f, "{}", value),
+            DecodedInput::Raw { bytes, .. } => write!(f, "{:02x?}", bytes),
+        }
+    }
+}
+
+/// An owned field name (ascii, padded as sent by the module)
+pub type FieldName = [u8; 10];
+
+#[derive(Clone, Copy, Debug, Format, PartialEq)]
+/// Decoded value from other input that does not borrow the decode instructions
+pub enum DecodedValue {
+    /// A u8
+    U8(u8),
+    /// A u16
+    U16(u16),
+    /// A u32
+    U32(u32),
+    /// A u64
+    U64(u64),
+    /// An i8
+    I8(i8),
+    /// An i16
+    I16(i16),
+    /// An i32
+    I32(i32),
+    /// An i64
+    I64(i64),
+    /// An f32
+    F32(f32),
+    /// An f64
+    F64(f64),
+    /// The raw bytes of an undecodable field (only the first `len` bytes are used)
+    Raw { bytes: [u8; 8], len: u8 },
+}
+
+impl DecodedValue {
+    /// The value as an f64.
+    ///
+    /// An f64 only has a 53 bit mantissa, so u64 and i64 values with a magnitude above 2^53 are
+    /// rounded to the nearest representable value.  Raw bytes have no value and are NaN.
+    pub fn as_f64(&self) -> f64 {
+        match *self {
+            DecodedValue::U8(value) => value as f64,
+            DecodedValue::U16(value) => value as f64,
+            DecodedValue::U32(value) => value as f64,
+            DecodedValue::U64(value) => value as f64,
+            DecodedValue::I8(value) => value as f64,
+            DecodedValue::I16(value) => value as f64,
+            DecodedValue::I32(value) => value as f64,
+            DecodedValue::I64(value) => value as f64,
+            DecodedValue::F32(value) => value as f64,
+            DecodedValue::F64(value) => value,
+            DecodedValue::Raw { .. } => f64::NAN,
+        }
+    }
+}
+
+impl<'a> From<DecodedInput<'a>> for (DecodedValue, FieldName) {
+    fn from(value: DecodedInput<'a>) -> Self {
+        let name = *value.raw_name();
+        let value = match value {
+            DecodedInput::U8 { value, .. } => DecodedValue::U8(value),
+            DecodedInput::U16 { value, .. } => DecodedValue::U16(value),
+            DecodedInput::U32 { value, .. } => DecodedValue::U32(value),
+            DecodedInput::U64 { value, .. } => DecodedValue::U64(value),
+            DecodedInput::I8 { value, .. } => DecodedValue::I8(value),
+            DecodedInput::I16 { value, .. } => DecodedValue::I16(value),
+            DecodedInput::I32 { value, .. } => DecodedValue::I32(value),
+            DecodedInput::I64 { value, .. } => DecodedValue::I64(value),
+            DecodedInput::F32 { value, .. } => DecodedValue::F32(value),
+            DecodedInput::F64 { value, .. } => DecodedValue::F64(value),
+            DecodedInput::Raw { bytes, .. } => {
+                // A field is at most 8 bytes
+                let mut raw = [0u8; 8];
+                raw[..bytes.len()].copy_from_slice(bytes);
+                DecodedValue::Raw { bytes: raw, len: bytes.len() as u8 }
+            },
+        };
+        (value, name)
+    }
+}
+
+/// Every integer converts losslessly except a u64 above `i64::MAX`, which is returned as the error
+/// along with the floats and raw bytes
+impl<'a> TryFrom<DecodedInput<'a>> for i64 {
+    /// The input that has no i64 value
+    type Error = DecodedInput<'a>;
+
+    fn try_from(value: DecodedInput<'a>) -> Result<Self, Self::Error> {
+        match value {
+            DecodedInput::U8 { value, .. } => Ok(value as i64),
+            DecodedInput::U16 { value, .. } => Ok(value as i64),
+            DecodedInput::U32 { value, .. } => Ok(value as i64),
+            DecodedInput::U64 { value: unsigned, .. } => i64::try_from(unsigned).map_err(|_| value),
+            DecodedInput::I8 { value, .. } => Ok(value as i64),
+            DecodedInput::I16 { value, .. } => Ok(value as i64),
+            DecodedInput::I32 { value, .. } => Ok(value as i64),
+            DecodedInput::I64 { value, .. } => Ok(value),
+            DecodedInput::F32 { .. } | DecodedInput::F64 { .. } | DecodedInput::Raw { .. } => Err(value),
+        }
+    }
+}
+
+/// Every variant converts like `DecodedValue::as_f64`: the floats and the integers up to 32 bits are
+/// lossless, u64 and i64 values with a magnitude above 2^53 are rounded and raw bytes are NaN
+impl<'a> From<DecodedInput<'a>> for f64 {
+    fn from(value: DecodedInput<'a>) -> Self {
+        <(DecodedValue, FieldName)>::from(value).0.as_f64()
+    }
+}
+
+/// Trim a field name to the bytes before the first NUL (without trailing whitespace) so the
+/// padding, or any garbage left after the terminator, is never exposed.  An all-zero name is empty.
+pub fn trim_name(name: &[u8; 10]) -> &[u8] {
+    let end = name.iter().position(|&byte| byte == 0).unwrap_or(name.len());
+    name[..end].trim_ascii_end()
 }
 
 #[derive(Clone, Copy, Debug, Format, PartialEq, Eq)]
@@ -292,22 +694,45 @@ pub enum DecodeError {
     OutOfBounds,
     /// The requested data type is unknown (this is likely to occur for 8 or 16 bit floats)
     UnknownDataType,
+    /// The value does not have the data size and type the decode instructions declare for the
+    /// field
+    MismatchedDataType,
 }
 
 pub trait Decode<'a> {
     fn decode(&self, idx: usize, decode_instructions: &'a DecodeInstructions) -> Result<DecodedInput<'a>, DecodeError>;
+
+    /// Decode a field like `decode`, but return the raw bytes (`DecodedInput::Raw`) of a field
+    /// with a size and type that cannot be decoded instead of `DecodeError::UnknownDataType` (i.e.
+    /// for a tooling UI that would rather display the bytes than fail)
+    fn decode_lenient(&'a self, idx: usize, decode_instructions: &'a DecodeInstructions) -> Result<DecodedInput<'a>, DecodeError>;
+
+    /// Decode every declared field in order (`DecodeInstructions::declared_field_count` fields)
+    fn iter_decoded(&'a self, decode_instructions: &'a DecodeInstructions) -> impl Iterator<Item = Result<DecodedInput<'a>, DecodeError>> + 'a;
+}
+
+/// The offset of a declared field in other input, checking the field fits
+fn field_offset(idx: usize, decode_instructions: &DecodeInstructions) -> Result<usize, DecodeError> {
+    if idx >= decode_instructions.declared_field_count() {
+        return Err(DecodeError::OutOfBounds);
+    }
+
+    // The offset of a field is the sum of the actual sizes of the fields before it
+    let mut cumulative_counter = 0;
+    for i in 0..idx {
+        cumulative_counter += decode_instructions.data_sizes[i] as usize;
+    }
+
+    if cumulative_counter + decode_instructions.data_sizes[idx] as usize > 24 {
+        return Err(DecodeError::OutOfBounds);
+    }
+
+    Ok(cumulative_counter)
 }
 
 impl<'a> Decode<'a> for OtherInput {
     fn decode(&self, idx: usize, decode_instructions: &'a DecodeInstructions) -> Result<DecodedInput<'a>, DecodeError> {
-        let mut cumulative_counter = 0;
-        for i in 0..idx {
-            cumulative_counter += decode_instructions.data_sizes[i] as usize;
-        }
-
-        if cumulative_counter + decode_instructions.data_sizes[idx] as usize > 24 {
-            return Err(DecodeError::OutOfBounds);
-        }
+        let cumulative_counter = field_offset(idx, decode_instructions)?;
 
         match (decode_instructions.data_sizes[idx], decode_instructions.data_types[idx]) {
             (DataSize::One, DataType::Unsigned) => Ok(DecodedInput::U8 {
@@ -353,12 +778,210 @@ impl<'a> Decode<'a> for OtherInput {
             _ => Err(DecodeError::UnknownDataType),
         }
     }
+
+    fn decode_lenient(&'a self, idx: usize, decode_instructions: &'a DecodeInstructions) -> Result<DecodedInput<'a>, DecodeError> {
+        match self.decode(idx, decode_instructions) {
+            Err(DecodeError::UnknownDataType) => {
+                let offset = field_offset(idx, decode_instructions)?;
+                Ok(DecodedInput::Raw {
+                    bytes: &self[offset..(offset + decode_instructions.data_sizes[idx] as usize)],
+                    name: &decode_instructions.fields[idx],
+                })
+            },
+            decoded => decoded,
+        }
+    }
+
+    fn iter_decoded(&'a self, decode_instructions: &'a DecodeInstructions) -> impl Iterator<Item = Result<DecodedInput<'a>, DecodeError>> + 'a {
+        (0..decode_instructions.declared_field_count()).map(|idx| self.decode(idx, decode_instructions))
+    }
+}
+
+/// A value that can be written into other input and decoded with a matching data size and type
+pub trait IntoDecoded: Copy {
+    /// The data size of the value
+    const DATA_SIZE: DataSize;
+    /// The data type of the value
+    const DATA_TYPE: DataType;
+
+    /// Write the value into the start of the buffer (little endian)
+    fn write_le(self, buffer: &mut [u8]);
+}
+
+macro_rules! impl_into_decoded {
+    ($($ty:ty => ($size:expr, $data_type:expr)),* $(,)?) => {
+        $(
+            impl IntoDecoded for $ty {
+                const DATA_SIZE: DataSize = $size;
+                const DATA_TYPE: DataType = $data_type;
+
+                fn write_le(self, buffer: &mut [u8]) {
+                    buffer[..Self::DATA_SIZE as usize].copy_from_slice(&self.to_le_bytes());
+                }
+            }
+        )*
+    };
+}
+
+impl_into_decoded! {
+    u8 => (DataSize::One, DataType::Unsigned),
+    u16 => (DataSize::Two, DataType::Unsigned),
+    u32 => (DataSize::Four, DataType::Unsigned),
+    u64 => (DataSize::Eight, DataType::Unsigned),
+    i8 => (DataSize::One, DataType::Signed),
+    i16 => (DataSize::Two, DataType::Signed),
+    i32 => (DataSize::Four, DataType::Signed),
+    i64 => (DataSize::Eight, DataType::Signed),
+    f32 => (DataSize::Four, DataType::Floating),
+    f64 => (DataSize::Eight, DataType::Floating),
+}
+
+/// Write typed values into other input (the counterpart of `Decode`)
+pub trait Encode {
+    /// Write a value at the byte offset, returning the number of bytes written
+    fn write<T: IntoDecoded>(&mut self, offset: usize, value: T) -> Result<usize, DecodeError>;
+
+    /// Write a value into the declared field at `idx` (at the offset `decode` reads it from).  The
+    /// value must have the field's data size and type (raw bytes only need its size), and the
+    /// name of the value is ignored.
+    fn encode(&mut self, idx: usize, value: DecodedInput, decode_instructions: &DecodeInstructions) -> Result<(), DecodeError>;
+}
+
+/// Write a value at the offset if it has the data size and type of the field
+fn write_field<T: IntoDecoded>(
+    other_input: &mut OtherInput,
+    offset: usize,
+    value: T,
+    field: (DataSize, DataType),
+) -> Result<(), DecodeError> {
+    if (T::DATA_SIZE, T::DATA_TYPE) != field {
+        return Err(DecodeError::MismatchedDataType);
+    }
+    other_input.write(offset, value).map(|_| ())
+}
+
+impl Encode for OtherInput {
+    fn write<T: IntoDecoded>(&mut self, offset: usize, value: T) -> Result<usize, DecodeError> {
+        let size = T::DATA_SIZE as usize;
+        if offset + size > self.len() {
+            return Err(DecodeError::OutOfBounds);
+        }
+
+        value.write_le(&mut self[offset..]);
+        Ok(size)
+    }
+
+    fn encode(&mut self, idx: usize, value: DecodedInput, decode_instructions: &DecodeInstructions) -> Result<(), DecodeError> {
+        let offset = field_offset(idx, decode_instructions)?;
+        let field = (decode_instructions.data_sizes[idx], decode_instructions.data_types[idx]);
+
+        match value {
+            DecodedInput::U8 { value, .. } => write_field(self, offset, value, field),
+            DecodedInput::U16 { value, .. } => write_field(self, offset, value, field),
+            DecodedInput::U32 { value, .. } => write_field(self, offset, value, field),
+            DecodedInput::U64 { value, .. } => write_field(self, offset, value, field),
+            DecodedInput::I8 { value, .. } => write_field(self, offset, value, field),
+            DecodedInput::I16 { value, .. } => write_field(self, offset, value, field),
+            DecodedInput::I32 { value, .. } => write_field(self, offset, value, field),
+            DecodedInput::I64 { value, .. } => write_field(self, offset, value, field),
+            DecodedInput::F32 { value, .. } => write_field(self, offset, value, field),
+            DecodedInput::F64 { value, .. } => write_field(self, offset, value, field),
+            DecodedInput::Raw { bytes, .. } => {
+                if bytes.len() != field.0 as usize {
+                    return Err(DecodeError::MismatchedDataType);
+                }
+                // The field offset was checked to fit the field
+                self[offset..(offset + bytes.len())].copy_from_slice(bytes);
+                Ok(())
+            },
+        }
+    }
+}
+
+/// Decode every declared field of an other input to an f64 with its trimmed name (e.g. for a
+/// numeric dashboard).
+///
+/// See `DecodedValue::as_f64` for the precision lost by 64 bit integers.  A name that is not valid
+/// utf-8 is left empty.
+pub fn decode_f64_fields(
+    other_input: &OtherInput,
+    decode_instructions: &DecodeInstructions,
+) -> Result<Vec<(f64, String<10>), 24>, DecodeError> {
+    let mut fields = Vec::new();
+    for decoded in other_input.iter_decoded(decode_instructions) {
+        let decoded = decoded?;
+        let name = core::str::from_utf8(decoded.name()).unwrap_or("");
+        let (value, _) = <(DecodedValue, FieldName)>::from(decoded);
+        // A trimmed name is at most 10 bytes and at most 24 fields are declared so neither the
+        // name nor the vec can overflow
+        let _ = fields.push((value.as_f64(), String::try_from(name).unwrap_or_default()));
+    }
+    Ok(fields)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_decode_f64_fields() {
+        let mut decode_instructions = DecodeInstructions::default();
+        decode_instructions.data_sizes[1] = DataSize::Two;
+        decode_instructions.data_types[1] = DataType::Signed;
+        decode_instructions.data_sizes[2] = DataSize::Four;
+        decode_instructions.data_types[2] = DataType::Floating;
+        decode_instructions.fields[0] = *b"speed\0\0\0\0\0";
+        decode_instructions.fields[1] = *b"temp      ";
+        decode_instructions.fields[2] = *b"volts\0\0\0\0\0";
+
+        let mut input = [0u8; 24];
+        input.write(0, 42u8).unwrap();
+        input.write(1, -40i16).unwrap();
+        input.write(3, 3.5f32).unwrap();
+
+        let fields = decode_f64_fields(&input, &decode_instructions).unwrap();
+        assert_eq!(fields.len(), 3);
+        assert_eq!(fields[0], (42.0, String::try_from("speed").unwrap()));
+        assert_eq!(fields[1], (-40.0, String::try_from("temp").unwrap()));
+        assert_eq!(fields[2], (3.5, String::try_from("volts").unwrap()));
+    }
+
+    #[test]
+    fn test_module_id_reserved() {
+        assert_eq!(ModuleId::try_from(ModuleId::UNASSIGNED), Err(0));
+        assert_eq!(ModuleId::new(0), None);
+
+        let mut buffer = [0xFFu8; DecodeInstructions::PACKED_SIZE];
+        DecodeInstructions::default().pack(&mut buffer).unwrap();
+        assert_eq!(buffer[0..2], [0, 0]);
+        assert_eq!(DecodeInstructions::unpack(&buffer).unwrap().module_id, None);
+    }
+
+    #[test]
+    fn test_unpack_decode_instructions_buffer_size() {
+        let mut decode_instructions = DecodeInstructions::default();
+        decode_instructions.fields[23] = *b"last\0\0\0\0\0\0";
+        let mut buffer = [0u8; DecodeInstructions::PACKED_SIZE];
+        decode_instructions.pack(&mut buffer).unwrap();
+
+        assert_eq!(DecodeInstructions::unpack(&buffer[..100]), Err(PackingError::InvalidBufferSize));
+        assert_eq!(DecodeInstructions::unpack(&buffer[..DecodeInstructions::PACKED_SIZE - 1]), Err(PackingError::InvalidBufferSize));
+        assert_eq!(DecodeInstructions::unpack(&buffer), Ok(decode_instructions));
+    }
+
+    #[test]
+    fn test_module_id_valid() {
+        let module_id = ModuleId::try_from(0x1234).unwrap();
+        assert_eq!(module_id.get(), 0x1234);
+        assert_eq!(u16::from(module_id), 0x1234);
+
+        let decode_instructions = DecodeInstructions { module_id: Some(module_id), ..Default::default() };
+        let mut buffer = [0u8; DecodeInstructions::PACKED_SIZE];
+        decode_instructions.pack(&mut buffer).unwrap();
+        assert_eq!(buffer[0..2], [0x34, 0x12]);
+        assert_eq!(DecodeInstructions::unpack(&buffer), Ok(decode_instructions));
+    }
+
     #[test]
     fn test_pack_data_sizes() {
         let mut buffer = [0u8; 3];
@@ -441,7 +1064,7 @@ mod tests {
         fields[9][0..5].copy_from_slice(b"test9");
 
         let decode_instruction = DecodeInstructions {
-            module_id: 0x1212,
+            module_id: ModuleId::new(0x1212),
             data_sizes,
             data_types,
             fields
@@ -458,10 +1081,10 @@ mod tests {
         expected_buffer[2] = 0b00100101;
         expected_buffer[3] = 0b11111110;
         expected_buffer[4] = 0b00000111;
-        // Data Types
-        expected_buffer[5] = 0b1110_0101;
-        expected_buffer[6] = 0b1111_1111;
-        expected_buffer[7] = 0b1111_1111;
+        // Data Types (only the 13 fields that fit, the two byte signed field takes a single bit)
+        expected_buffer[5] = 0b1111_0001;
+        expected_buffer[6] = 0b0111_1111;
+        expected_buffer[7] = 0b0000_0000;
         // Fields
         expected_buffer[8..13].copy_from_slice(b"test0");
         expected_buffer[18..23].copy_from_slice(b"test1");
@@ -487,10 +1110,10 @@ mod tests {
         buffer[2] = 0b00100101;
         buffer[3] = 0b11111110;
         buffer[4] = 0b00000111;
-        // Data Types
-        buffer[5] = 0b1110_0101;
-        buffer[6] = 0b1111_1111;
-        buffer[7] = 0b1111_1111;
+        // Data Types (only the 13 fields that fit, the two byte signed field takes a single bit)
+        buffer[5] = 0b1111_0001;
+        buffer[6] = 0b0111_1111;
+        buffer[7] = 0b0000_0000;
         // Fields
         buffer[8..13].copy_from_slice(b"test0");
         buffer[18..23].copy_from_slice(b"test1");
@@ -527,7 +1150,7 @@ mod tests {
         fields[9][0..5].copy_from_slice(b"test9");
 
         let expected_instruction = DecodeInstructions {
-            module_id: 0x1212,
+            module_id: ModuleId::new(0x1212),
             data_sizes,
             data_types,
             fields
@@ -560,7 +1183,7 @@ mod tests {
         fields[9][0..5].copy_from_slice(b"test9");
 
         let decode_instruction = DecodeInstructions {
-            module_id: 0x1212,
+            module_id: ModuleId::new(0x1212),
             data_sizes,
             data_types,
             fields
@@ -573,6 +1196,100 @@ mod tests {
         assert_eq!(instruction, decode_instruction);
     }
 
+    #[test]
+    fn test_decode_instructions_semantic_diff() {
+        let mut decode_instructions = DecodeInstructions::default();
+        decode_instructions.data_sizes[0] = DataSize::Eight;
+        decode_instructions.fields[0][0..4].copy_from_slice(b"temp");
+
+        let mut padded = decode_instructions;
+        padded.fields[0][4..7].copy_from_slice(b"\0\xAB\xCD");
+        padded.fields[23][0..4].copy_from_slice(b"junk");
+        assert!(!decode_instructions.differs_semantically(&padded));
+
+        let mut renamed = decode_instructions;
+        renamed.fields[0][0..4].copy_from_slice(b"pres");
+        assert!(decode_instructions.differs_semantically(&renamed));
+
+        let mut new_module = decode_instructions;
+        new_module.module_id = ModuleId::new(0x0042);
+        assert!(decode_instructions.differs_semantically(&new_module));
+    }
+
+    #[test]
+    fn test_decode_unnamed_fields_empty_names() {
+        let mut decode_instructions = DecodeInstructions::default();
+        decode_instructions.fields[2][0..4].copy_from_slice(b"last");
+        let input = [0xAAu8; 24];
+
+        for i in 0..2 {
+            let decoded = input.decode(i, &decode_instructions).unwrap();
+            assert_eq!(decoded.name(), b"");
+        }
+        assert_eq!(input.decode(2, &decode_instructions).unwrap().name(), b"last");
+    }
+
+    #[test]
+    fn test_decode_past_declared_fields() {
+        let mut decode_instructions = DecodeInstructions::default();
+        decode_instructions.fields[0][0..3].copy_from_slice(b"one");
+        decode_instructions.fields[1][0..3].copy_from_slice(b"two");
+        decode_instructions.fields[2][0..5].copy_from_slice(b"three");
+        let input = [0u8; 24];
+
+        assert_eq!(decode_instructions.declared_field_count(), 3);
+        assert!(input.decode(2, &decode_instructions).is_ok());
+        assert_eq!(input.decode(5, &decode_instructions), Err(DecodeError::OutOfBounds));
+        assert_eq!(input.decode(24, &decode_instructions), Err(DecodeError::OutOfBounds));
+        assert_eq!(input.decode(0, &DecodeInstructions::default()), Err(DecodeError::OutOfBounds));
+    }
+
+    #[test]
+    fn test_decode_trims_garbage_after_name() {
+        let mut decode_instructions = DecodeInstructions::default();
+        decode_instructions.fields[0] = *b"temp \0\xAB\xCD\xEF\x12";
+
+        let input = [0u8; 24];
+        let decoded = input.decode(0, &decode_instructions).unwrap();
+        assert_eq!(decoded.name(), b"temp");
+        assert_eq!(decoded.raw_name(), b"temp \0\xAB\xCD\xEF\x12");
+    }
+
+    #[test]
+    fn test_write_then_decode_each_type() {
+        fn round_trip<T: IntoDecoded>(value: T) -> DecodedValue {
+            let mut decode_instructions = DecodeInstructions::default();
+            decode_instructions.data_sizes[1] = T::DATA_SIZE;
+            decode_instructions.data_types[1] = T::DATA_TYPE;
+            decode_instructions.fields[1] = *b"value\0\0\0\0\0";
+
+            let mut input: OtherInput = [0u8; 24];
+            assert_eq!(input.write(1, value), Ok(T::DATA_SIZE as usize));
+
+            let (decoded, _) = input.decode(1, &decode_instructions).unwrap().into();
+            decoded
+        }
+
+        assert_eq!(round_trip(0xABu8), DecodedValue::U8(0xAB));
+        assert_eq!(round_trip(0xABCDu16), DecodedValue::U16(0xABCD));
+        assert_eq!(round_trip(0xABCD_EF01u32), DecodedValue::U32(0xABCD_EF01));
+        assert_eq!(round_trip(0xABCD_EF01_2345_6789u64), DecodedValue::U64(0xABCD_EF01_2345_6789));
+        assert_eq!(round_trip(-12i8), DecodedValue::I8(-12));
+        assert_eq!(round_trip(-1234i16), DecodedValue::I16(-1234));
+        assert_eq!(round_trip(-123_456i32), DecodedValue::I32(-123_456));
+        assert_eq!(round_trip(-123_456_789_012i64), DecodedValue::I64(-123_456_789_012));
+        assert_eq!(round_trip(1.5f32), DecodedValue::F32(1.5));
+        assert_eq!(round_trip(-2.25f64), DecodedValue::F64(-2.25));
+    }
+
+    #[test]
+    fn test_write_out_of_bounds() {
+        let mut input: OtherInput = [0u8; 24];
+        assert_eq!(input.write(16, 1u64), Ok(8));
+        assert_eq!(input.write(17, 1u64), Err(DecodeError::OutOfBounds));
+        assert_eq!(input.write(24, 1u8), Err(DecodeError::OutOfBounds));
+    }
+
     #[test]
     fn test_decode_u8() {
         let mut decode_instructions = DecodeInstructions::default();
@@ -640,6 +1357,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_decode_u64_after_eight_byte_field() {
+        let mut decode_instructions = DecodeInstructions::default();
+        decode_instructions.data_sizes[0] = DataSize::Eight;
+        decode_instructions.data_sizes[1] = DataSize::Eight;
+        decode_instructions.fields[1] = *b"dinosaur__";
+
+        let mut input = [0u8; 24];
+        input[0..8].copy_from_slice(&u64::MAX.to_le_bytes());
+        input[8..16].copy_from_slice(&0x0123456789ABCDEFu64.to_le_bytes());
+
+        if let DecodedInput::U64 { value, name } = input.decode(1, &decode_instructions).unwrap() {
+            assert_eq!(value, 0x0123456789ABCDEF);
+            assert_eq!(name, b"dinosaur__");
+        } else {
+            panic!("the field after an eight byte field should decode as a u64");
+        }
+    }
+
     #[test]
     fn test_decode_i8() {
         let mut decode_instructions = DecodeInstructions::default();
@@ -746,4 +1482,421 @@ mod tests {
             assert!(false);
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_decode_lenient_raw() {
+        // There is no f16 support, so a two byte float cannot be decoded
+        let mut decode_instructions = DecodeInstructions::default();
+        decode_instructions.data_sizes[1] = DataSize::Two;
+        decode_instructions.data_types[1] = DataType::Floating;
+        decode_instructions.fields[1] = *b"half\0\0\0\0\0\0";
+
+        let mut input = [0u8; 24];
+        input[1..3].copy_from_slice(&[0x00, 0x3C]);
+
+        assert_eq!(input.decode(1, &decode_instructions), Err(DecodeError::UnknownDataType));
+        let decoded = input.decode_lenient(1, &decode_instructions).unwrap();
+        assert_eq!(decoded, DecodedInput::Raw { bytes: &[0x00, 0x3C], name: b"half\0\0\0\0\0\0" });
+        assert_eq!(decoded.name(), b"half");
+
+        let (value, _) = <(DecodedValue, FieldName)>::from(decoded);
+        assert_eq!(value, DecodedValue::Raw { bytes: [0x00, 0x3C, 0, 0, 0, 0, 0, 0], len: 2 });
+        assert!(value.as_f64().is_nan());
+    }
+
+    #[test]
+    fn test_decoded_input_into_i64() {
+        const NAME: &[u8; 10] = b"field\0\0\0\0\0";
+
+        assert_eq!(i64::try_from(DecodedInput::U8 { value: 200, name: NAME }), Ok(200));
+        assert_eq!(i64::try_from(DecodedInput::U32 { value: u32::MAX, name: NAME }), Ok(u32::MAX as i64));
+        assert_eq!(i64::try_from(DecodedInput::U64 { value: i64::MAX as u64, name: NAME }), Ok(i64::MAX));
+        assert_eq!(i64::try_from(DecodedInput::I8 { value: -5, name: NAME }), Ok(-5));
+        assert_eq!(i64::try_from(DecodedInput::I16 { value: i16::MIN, name: NAME }), Ok(i16::MIN as i64));
+        assert_eq!(i64::try_from(DecodedInput::I64 { value: i64::MIN, name: NAME }), Ok(i64::MIN));
+
+        let too_large = DecodedInput::U64 { value: u64::MAX, name: NAME };
+        assert_eq!(i64::try_from(too_large), Err(too_large));
+        let float = DecodedInput::F32 { value: 1.0, name: NAME };
+        assert_eq!(i64::try_from(float), Err(float));
+        let double = DecodedInput::F64 { value: -2.5, name: NAME };
+        assert_eq!(i64::try_from(double), Err(double));
+        let raw = DecodedInput::Raw { bytes: &[1, 2], name: NAME };
+        assert_eq!(i64::try_from(raw), Err(raw));
+    }
+
+    #[test]
+    fn test_decoded_input_into_f64() {
+        const NAME: &[u8; 10] = b"field\0\0\0\0\0";
+
+        assert_eq!(f64::from(DecodedInput::U8 { value: 7, name: NAME }), 7.0);
+        assert_eq!(f64::from(DecodedInput::U16 { value: 600, name: NAME }), 600.0);
+        assert_eq!(f64::from(DecodedInput::U32 { value: u32::MAX, name: NAME }), u32::MAX as f64);
+        assert_eq!(f64::from(DecodedInput::U64 { value: 1 << 53, name: NAME }), (1u64 << 53) as f64);
+        assert_eq!(f64::from(DecodedInput::I8 { value: -8, name: NAME }), -8.0);
+        assert_eq!(f64::from(DecodedInput::I16 { value: -300, name: NAME }), -300.0);
+        assert_eq!(f64::from(DecodedInput::I32 { value: i32::MIN, name: NAME }), i32::MIN as f64);
+        assert_eq!(f64::from(DecodedInput::I64 { value: -(1 << 53), name: NAME }), -((1u64 << 53) as f64));
+        assert_eq!(f64::from(DecodedInput::F32 { value: 0.25, name: NAME }), 0.25);
+        assert_eq!(f64::from(DecodedInput::F64 { value: 125.75, name: NAME }), 125.75);
+        assert!(f64::from(DecodedInput::Raw { bytes: &[0x00, 0x3C], name: NAME }).is_nan());
+    }
+
+    #[test]
+    fn test_decode_lenient_decodable() {
+        let mut decode_instructions = DecodeInstructions::default();
+        decode_instructions.data_types[0] = DataType::Signed;
+
+        let input = [0xFFu8; 24];
+        assert_eq!(input.decode_lenient(0, &decode_instructions), input.decode(0, &decode_instructions));
+        assert_eq!(input.decode_lenient(24, &decode_instructions), Err(DecodeError::OutOfBounds));
+    }
+
+    /// Instructions with a 24 byte field layout followed by sizes, types and names that do not fit
+    fn overfull_instructions() -> DecodeInstructions {
+        let mut decode_instructions = DecodeInstructions {
+            module_id: ModuleId::new(0x0102),
+            ..DecodeInstructions::default()
+        };
+        decode_instructions.data_sizes[0] = DataSize::Eight;
+        decode_instructions.data_sizes[1] = DataSize::Eight;
+        decode_instructions.data_sizes[2] = DataSize::Four;
+        decode_instructions.data_sizes[3] = DataSize::Four;
+        decode_instructions.data_types[1] = DataType::Floating;
+        decode_instructions.fields[0] = *b"x\0\0\0\0\0\0\0\0\0";
+        decode_instructions.fields[3] = *b"y  \0junk\0\0";
+
+        // Past the 24 byte other input
+        decode_instructions.data_sizes[4] = DataSize::Two;
+        decode_instructions.data_sizes[5] = DataSize::Eight;
+        decode_instructions.data_types[22] = DataType::Signed;
+        decode_instructions.fields[10] = *b"unused\0\0\0\0";
+        decode_instructions
+    }
+
+    #[test]
+    fn test_decode_instructions_round_trip() {
+        let decode_instructions = overfull_instructions();
+        let mut packed = [0u8; DecodeInstructions::PACKED_SIZE];
+        decode_instructions.pack(&mut packed).unwrap();
+
+        // The sizes and types past the other input are lost, everything else is kept
+        let unpacked = DecodeInstructions::unpack(&packed).unwrap();
+        assert_ne!(unpacked, decode_instructions);
+        assert_eq!(unpacked.data_sizes[4], DataSize::One);
+        assert_eq!(unpacked.data_types[22], DataType::Unsigned);
+        assert_eq!(unpacked.fields, decode_instructions.fields);
+        assert!(unpacked.semantically_eq(&decode_instructions));
+
+        let mut repacked = [0u8; DecodeInstructions::PACKED_SIZE];
+        unpacked.pack(&mut repacked).unwrap();
+        assert_eq!(repacked, packed);
+    }
+
+    #[test]
+    fn test_decode_instructions_filler_bits_cleared() {
+        let mut packed = [0u8; DecodeInstructions::PACKED_SIZE];
+        overfull_instructions().pack(&mut packed).unwrap();
+
+        // The four sizes (4 + 4 + 3 + 3 bits) end at bit 14, so the bits after are filler
+        let mut with_filler = packed;
+        with_filler[4] |= 0x80;
+        let unpacked = DecodeInstructions::unpack(&with_filler).unwrap();
+
+        let mut repacked = [0u8; DecodeInstructions::PACKED_SIZE];
+        unpacked.pack(&mut repacked).unwrap();
+        assert_eq!(repacked, packed);
+    }
+
+    #[test]
+    fn test_canonical_pack() {
+        let decode_instructions = overfull_instructions();
+        let mut equivalent = decode_instructions;
+        equivalent.fields[3] = *b"y\0\0\0\0\0\0\0\0\0";
+        equivalent.data_sizes[4] = DataSize::Eight;
+        equivalent.data_types[22] = DataType::Floating;
+        equivalent.fields[10] = [0u8; 10];
+        assert!(equivalent.semantically_eq(&decode_instructions));
+
+        let mut canonical = [0u8; DecodeInstructions::PACKED_SIZE];
+        decode_instructions.canonical_pack(&mut canonical).unwrap();
+        let mut canonical_equivalent = [0u8; DecodeInstructions::PACKED_SIZE];
+        equivalent.canonical_pack(&mut canonical_equivalent).unwrap();
+        assert_eq!(canonical, canonical_equivalent);
+
+        // A size that overflows the other input stays an overflow
+        let mut overflowing = DecodeInstructions::default();
+        overflowing.data_sizes[..3].copy_from_slice(&[DataSize::Eight, DataSize::Eight, DataSize::Four]);
+        overflowing.data_sizes[3] = DataSize::Eight;
+        overflowing.canonical_pack(&mut canonical).unwrap();
+        assert_eq!(DecodeInstructions::unpack(&canonical).unwrap().field_count(), 3);
+
+        // The canonical form round trips
+        let unpacked = DecodeInstructions::unpack(&canonical_equivalent).unwrap();
+        let mut repacked = [0u8; DecodeInstructions::PACKED_SIZE];
+        unpacked.canonical_pack(&mut repacked).unwrap();
+        assert_eq!(repacked, canonical_equivalent);
+    }
+
+    #[test]
+    fn test_data_size_type_round_trip_property() {
+        // A fixed xorshift so the sequences are reproducible
+        let mut state = 0x2545_F491u32;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        };
+
+        for _ in 0..50_000 {
+            // Random sizes until the other input is filled (the last size may overflow it), with
+            // random types (only the four and eight byte fields can be floating point)
+            let mut data_sizes = [DataSize::One; 24];
+            let mut data_types = [DataType::Unsigned; 24];
+            let mut used = 0;
+            for (data_size, data_type) in data_sizes.iter_mut().zip(data_types.iter_mut()) {
+                if used >= 24 {
+                    break;
+                }
+                *data_size = [DataSize::One, DataSize::Two, DataSize::Four, DataSize::Eight][next() as usize % 4];
+                *data_type = match (next() % 3, *data_size) {
+                    (0, _) => DataType::Unsigned,
+                    (1, _) | (_, DataSize::One | DataSize::Two) => DataType::Signed,
+                    _ => DataType::Floating,
+                };
+                used += *data_size as usize;
+            }
+
+            // The sizes and types packed as `DecodeInstructions` packs them
+            let mut packed = [0u8; 6];
+            data_sizes.pack(&mut packed[0..3]).unwrap();
+            pack_field_types(&data_sizes, &data_types, &mut packed[3..6]);
+            let unpacked_sizes = <[DataSize; 24]>::unpack(&packed[0..3]).unwrap();
+            let unpacked_types = unpack_field_types(&unpacked_sizes, &packed[3..6]);
+
+            let field_count = fitting_fields(&data_sizes);
+            assert_eq!(fitting_fields(&unpacked_sizes), field_count, "{:?}", data_sizes);
+            assert_eq!(unpacked_sizes[..field_count], data_sizes[..field_count]);
+            assert_eq!(unpacked_types[..field_count], data_types[..field_count], "{:?}", data_sizes);
+        }
+    }
+
+    #[test]
+    fn test_unpack_data_sizes_cut_off_overflow() {
+        // 23 one byte fields take 23 bits, so the bit of the overflowing two byte size is cut off
+        let mut data_sizes = [DataSize::One; 24];
+        data_sizes[23] = DataSize::Two;
+        let mut buffer = [0u8; 3];
+        data_sizes.pack(&mut buffer).unwrap();
+        assert_eq!(buffer, [0xFF, 0xFF, 0x7F]);
+
+        let unpacked = <[DataSize; 24]>::unpack(&buffer).unwrap();
+        assert_eq!(unpacked[..23], [DataSize::One; 23]);
+        assert_eq!(unpacked[23], DataSize::Eight);
+    }
+
+    #[test]
+    fn test_unpack_data_sizes_zero_bits() {
+        assert_eq!(<[DataSize; 24]>::unpack(&[0, 0, 0]), Err(PackingError::InvalidEncoding));
+
+        // Four one byte fields, then a run of zero bits far from the end
+        assert_eq!(<[DataSize; 24]>::unpack(&[0b0000_1111, 0, 0xFF]), Err(PackingError::InvalidEncoding));
+
+        // An erased decode instructions buffer is rejected rather than hanging
+        assert_eq!(DecodeInstructions::unpack(&[0u8; DecodeInstructions::PACKED_SIZE]), Err(PackingError::InvalidEncoding));
+
+        // A buffer that is too small is still told apart from one that is garbage
+        assert_eq!(<[DataSize; 24]>::unpack(&[0, 0]), Err(PackingError::InvalidBufferSize));
+    }
+
+    #[test]
+    fn test_unpack_data_types_zero_bits() {
+        assert_eq!(<[DataType; 24]>::unpack(&[0, 0, 0]), Err(PackingError::InvalidEncoding));
+        assert_eq!(<[DataType; 24]>::unpack(&[0b0000_0111, 0, 0xFF]), Err(PackingError::InvalidEncoding));
+
+        // 23 unsigned types take 23 bits, so the bit of the overflowing signed type is cut off
+        let mut data_types = [DataType::Unsigned; 24];
+        data_types[23] = DataType::Signed;
+        let mut buffer = [0u8; 3];
+        data_types.pack(&mut buffer).unwrap();
+        assert_eq!(buffer, [0xFF, 0xFF, 0x7F]);
+
+        let unpacked = <[DataType; 24]>::unpack(&buffer).unwrap();
+        assert_eq!(unpacked[..23], [DataType::Unsigned; 23]);
+    }
+
+    #[test]
+    fn test_decode_instructions_signed_bytes_round_trip() {
+        // 24 signed one byte fields would take 48 bits with the self-delimiting type codes
+        let decode_instructions = DecodeInstructions { data_types: [DataType::Signed; 24], ..DecodeInstructions::default() };
+        let mut packed = [0u8; DecodeInstructions::PACKED_SIZE];
+        decode_instructions.pack(&mut packed).unwrap();
+
+        assert_eq!(DecodeInstructions::unpack(&packed), Ok(decode_instructions));
+    }
+
+    /// Format a decoded input with `Display`
+    fn display(input: DecodedInput) -> String<32> {
+        use core::fmt::Write;
+
+        let mut formatted = String::new();
+        write!(formatted, "{}", input).unwrap();
+        formatted
+    }
+
+    #[test]
+    fn test_decoded_input_name_str_short() {
+        let name = *b"temp\0\0\0\0\0\0";
+        let input = DecodedInput::I8 { value: 42, name: &name };
+        assert_eq!(input.name_str(), Some("temp"));
+        assert_eq!(display(input).as_str(), "temp = 42");
+    }
+
+    #[test]
+    fn test_decoded_input_name_str_full_length() {
+        let name = *b"pressure_2";
+        let input = DecodedInput::F32 { value: 1.5, name: &name };
+        assert_eq!(input.name_str(), Some("pressure_2"));
+        assert_eq!(display(input).as_str(), "pressure_2 = 1.5");
+    }
+
+    #[test]
+    fn test_decoded_input_name_str_embedded_nul() {
+        // The name ends at the first NUL even with bytes after it
+        let name = *b"ab\0cdefghi";
+        let input = DecodedInput::Raw { bytes: &[0x01, 0xAB], name: &name };
+        assert_eq!(input.name_str(), Some("ab"));
+        assert_eq!(display(input).as_str(), "ab = [01, ab]");
+    }
+
+    #[test]
+    fn test_decoded_input_name_str_non_ascii() {
+        let name = *b"t\xE9mp\0\0\0\0\0\0";
+        let input = DecodedInput::U16 { value: 7, name: &name };
+        assert_eq!(input.name_str(), None);
+        assert_eq!(display(input).as_str(), "t\\xe9mp = 7");
+    }
+
+    #[test]
+    fn test_encode_round_trips_decode() {
+        let mut decode_instructions = DecodeInstructions::default();
+        decode_instructions.data_sizes[..5].copy_from_slice(&[DataSize::One, DataSize::Two, DataSize::Four, DataSize::Eight, DataSize::Four]);
+        decode_instructions.data_types[..5].copy_from_slice(&[DataType::Signed, DataType::Unsigned, DataType::Floating, DataType::Signed, DataType::Unsigned]);
+        for (idx, name) in [b"a", b"b", b"c", b"d", b"e"].iter().enumerate() {
+            decode_instructions.fields[idx][0] = name[0];
+        }
+
+        let name = [0u8; 10];
+        let values = [
+            DecodedInput::I8 { value: -5, name: &name },
+            DecodedInput::U16 { value: 0xBEEF, name: &name },
+            DecodedInput::F32 { value: 2.5, name: &name },
+            DecodedInput::I64 { value: -1_234_567_890_123, name: &name },
+            DecodedInput::U32 { value: 0x0102_0304, name: &name },
+        ];
+
+        let mut other_input = OtherInput::default();
+        for (idx, value) in values.iter().enumerate() {
+            other_input.encode(idx, *value, &decode_instructions).unwrap();
+        }
+
+        // The field at index 4 starts after 1 + 2 + 4 + 8 bytes
+        assert_eq!(other_input[15..19], [0x04, 0x03, 0x02, 0x01]);
+        for (idx, value) in values.iter().enumerate() {
+            let decoded = other_input.decode(idx, &decode_instructions).unwrap();
+            assert_eq!(<(DecodedValue, FieldName)>::from(decoded).0, <(DecodedValue, FieldName)>::from(*value).0);
+            assert_eq!(decoded.raw_name(), &decode_instructions.fields[idx]);
+        }
+    }
+
+    #[test]
+    fn test_encode_mismatched_field() {
+        let mut decode_instructions = DecodeInstructions::default();
+        decode_instructions.data_sizes[1] = DataSize::Two;
+        decode_instructions.data_types[1] = DataType::Signed;
+        decode_instructions.fields[1] = *b"temp\0\0\0\0\0\0";
+
+        let name = [0u8; 10];
+        let mut other_input = OtherInput::default();
+        assert_eq!(other_input.encode(1, DecodedInput::U16 { value: 1, name: &name }, &decode_instructions), Err(DecodeError::MismatchedDataType));
+        assert_eq!(other_input.encode(1, DecodedInput::I32 { value: 1, name: &name }, &decode_instructions), Err(DecodeError::MismatchedDataType));
+        assert_eq!(other_input.encode(24, DecodedInput::U8 { value: 1, name: &name }, &decode_instructions), Err(DecodeError::OutOfBounds));
+        assert_eq!(other_input, OtherInput::default());
+
+        // Raw bytes are written whatever the type, as long as they fill the field
+        assert_eq!(other_input.encode(1, DecodedInput::Raw { bytes: &[0xAA], name: &name }, &decode_instructions), Err(DecodeError::MismatchedDataType));
+        other_input.encode(1, DecodedInput::Raw { bytes: &[0xFE, 0xFF], name: &name }, &decode_instructions).unwrap();
+        assert_eq!(other_input.decode(1, &decode_instructions), Ok(DecodedInput::I16 { value: -2, name: &decode_instructions.fields[1] }));
+    }
+
+    #[test]
+    fn test_iter_decoded_fields_in_order() {
+        let mut decode_instructions = DecodeInstructions::default();
+        decode_instructions.data_sizes[..4].copy_from_slice(&[DataSize::Two, DataSize::One, DataSize::Eight, DataSize::Four]);
+        decode_instructions.data_types[..4].copy_from_slice(&[DataType::Unsigned, DataType::Signed, DataType::Floating, DataType::Signed]);
+        decode_instructions.fields[0] = *b"rpm\0\0\0\0\0\0\0";
+        decode_instructions.fields[1] = *b"trim\0\0\0\0\0\0";
+        decode_instructions.fields[3] = *b"offset\0\0\0\0";
+
+        let mut other_input = OtherInput::default();
+        other_input.write(0, 3000u16).unwrap();
+        other_input.write(2, -4i8).unwrap();
+        other_input.write(3, 0.125f64).unwrap();
+        other_input.write(11, -70_000i32).unwrap();
+
+        let decoded: Vec<DecodedInput, 24> = other_input.iter_decoded(&decode_instructions).map(Result::unwrap).collect();
+        let fields = &decode_instructions.fields;
+        assert_eq!(decoded.as_slice(), [
+            DecodedInput::U16 { value: 3000, name: &fields[0] },
+            DecodedInput::I8 { value: -4, name: &fields[1] },
+            // An unnamed field before a named one is still declared
+            DecodedInput::F64 { value: 0.125, name: &fields[2] },
+            DecodedInput::I32 { value: -70_000, name: &fields[3] },
+        ]);
+    }
+
+    #[test]
+    fn test_iter_decoded_reports_undecodable_field() {
+        let mut decode_instructions = DecodeInstructions::default();
+        decode_instructions.data_types[0] = DataType::Floating;
+        decode_instructions.fields[0] = *b"half\0\0\0\0\0\0";
+
+        let other_input = OtherInput::default();
+        let mut decoded = other_input.iter_decoded(&decode_instructions);
+        assert_eq!(decoded.next(), Some(Err(DecodeError::UnknownDataType)));
+        assert_eq!(decoded.next(), None);
+    }
+
+    /// Decode instructions with a named field of each size
+    fn named_sizes(data_sizes: &[DataSize]) -> DecodeInstructions {
+        let mut decode_instructions = DecodeInstructions::default();
+        decode_instructions.data_sizes[..data_sizes.len()].copy_from_slice(data_sizes);
+        for field in decode_instructions.fields[..data_sizes.len()].iter_mut() {
+            *field = *b"field\0\0\0\0\0";
+        }
+        decode_instructions
+    }
+
+    #[test]
+    fn test_validate_sizes_under_24() {
+        assert_eq!(named_sizes(&[DataSize::Eight, DataSize::Four, DataSize::One]).validate(), Ok(()));
+        assert_eq!(DecodeInstructions::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_sizes_exactly_24() {
+        assert_eq!(named_sizes(&[DataSize::Eight; 3]).validate(), Ok(()));
+        assert_eq!(named_sizes(&[DataSize::One; 24]).validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_sizes_over_24() {
+        let decode_instructions = named_sizes(&[DataSize::Eight; 12]);
+        assert_eq!(decode_instructions.validate(), Err(DecodeError::OutOfBounds));
+        // Only the fields that fit would have been decoded
+        assert_eq!(decode_instructions.declared_field_count(), 3);
+
+        assert_eq!(named_sizes(&[DataSize::Eight, DataSize::Eight, DataSize::Four, DataSize::Two, DataSize::Four]).validate(), Err(DecodeError::OutOfBounds));
+    }
+}
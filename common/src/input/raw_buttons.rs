@@ -0,0 +1,156 @@
+//!
+//! Raw Button States
+//! 
+
+use derive_builder::Builder;
+use defmt::Format;
+use crate::packing::{Pack, PackingError, Unpack};
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq, Default, Builder)]
+#[builder(build_fn(error(validation_error = false)))]
+/// The raw level of each physical button on the main input module (before multi-tap decoding)
+pub struct RawButtons {
+    #[builder(default = "false")]
+    /// The zero button
+    pub zero: bool,
+
+    #[builder(default = "false")]
+    /// The one button
+    pub one: bool,
+
+    #[builder(default = "false")]
+    /// The two button
+    pub two: bool,
+
+    #[builder(default = "false")]
+    /// The three button
+    pub three: bool,
+
+    #[builder(default = "false")]
+    /// The four button
+    pub four: bool,
+
+    #[builder(default = "false")]
+    /// The five button
+    pub five: bool,
+
+    #[builder(default = "false")]
+    /// The six button
+    pub six: bool,
+
+    #[builder(default = "false")]
+    /// The seven button
+    pub seven: bool,
+
+    #[builder(default = "false")]
+    /// The eight button
+    pub eight: bool,
+
+    #[builder(default = "false")]
+    /// The nine button
+    pub nine: bool,
+
+    #[builder(default = "false")]
+    /// The back button
+    pub back: bool,
+
+    #[builder(default = "false")]
+    /// The front button
+    pub front: bool,
+
+    #[builder(default = "false")]
+    /// The shift switch
+    pub switch: bool,
+}
+
+impl RawButtons {
+    /// The length of the packed raw buttons
+    pub const PACKED_SIZE: usize = 2;
+}
+
+impl Pack for RawButtons {
+    fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
+        if buffer.len() < Self::PACKED_SIZE {
+            return Err(PackingError::InvalidBufferSize);
+        }
+
+        buffer[0] = ((self.zero as u8) << 7)
+            | ((self.one as u8) << 6)
+            | ((self.two as u8) << 5)
+            | ((self.three as u8) << 4)
+            | ((self.four as u8) << 3)
+            | ((self.five as u8) << 2)
+            | ((self.six as u8) << 1)
+            | (self.seven as u8);
+        buffer[1] = ((self.eight as u8) << 7)
+            | ((self.nine as u8) << 6)
+            | ((self.back as u8) << 5)
+            | ((self.front as u8) << 4)
+            | ((self.switch as u8) << 3);
+        Ok(())
+    }
+}
+
+impl Unpack for RawButtons {
+    fn unpack(buffer: &[u8]) -> Result<Self, PackingError>
+    where
+        Self: Sized,
+    {
+        if buffer.len() < Self::PACKED_SIZE {
+            return Err(PackingError::InvalidBufferSize);
+        }
+
+        Ok(Self {
+            zero: buffer[0] & (1 << 7) != 0,
+            one: buffer[0] & (1 << 6) != 0,
+            two: buffer[0] & (1 << 5) != 0,
+            three: buffer[0] & (1 << 4) != 0,
+            four: buffer[0] & (1 << 3) != 0,
+            five: buffer[0] & (1 << 2) != 0,
+            six: buffer[0] & (1 << 1) != 0,
+            seven: buffer[0] & 1 != 0,
+            eight: buffer[1] & (1 << 7) != 0,
+            nine: buffer[1] & (1 << 6) != 0,
+            back: buffer[1] & (1 << 5) != 0,
+            front: buffer[1] & (1 << 4) != 0,
+            switch: buffer[1] & (1 << 3) != 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_raw_buttons() {
+        let raw_buttons = RawButtonsBuilder::default()
+            .zero(true)
+            .seven(true)
+            .nine(true)
+            .switch(true)
+            .build()
+            .unwrap();
+
+        let mut buffer = [0u8; 2];
+        raw_buttons.pack(&mut buffer).unwrap();
+        assert_eq!(buffer, [0b1000_0001, 0b0100_1000]);
+    }
+
+    #[test]
+    fn test_pack_unpack_raw_buttons() {
+        let raw_buttons = RawButtonsBuilder::default()
+            .two(true)
+            .five(true)
+            .eight(true)
+            .back(true)
+            .front(true)
+            .build()
+            .unwrap();
+
+        let mut buffer = [0u8; 2];
+        raw_buttons.pack(&mut buffer).unwrap();
+        assert_eq!(raw_buttons, RawButtons::unpack(&buffer).unwrap());
+        assert_eq!(RawButtons::unpack(&buffer[..1]), Err(PackingError::InvalidBufferSize));
+    }
+}
@@ -0,0 +1,114 @@
+//!
+//! Auto-Repeat for Held Keys
+//!
+
+use heapless::Vec;
+
+use super::Input;
+
+/// The number of numpad, keypad and auxiliary keys
+const KEY_COUNT: usize = 10 + 29 + 32;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// Generates repeat events for held keys (numpad, keypad and auxiliary).
+///
+/// A key repeats once it has been held for the initial delay and then once every repeat period
+/// until it is released.  Shift is a modifier so it never repeats.
+pub struct AutoRepeat {
+    /// The time a key must be held before it starts repeating (in milliseconds)
+    initial_delay_ms: u64,
+    /// The time between repeats of a held key (in milliseconds)
+    repeat_period_ms: u64,
+    /// The time each held key will next repeat (in field order: numpad, keypad, auxiliary)
+    next_repeat: [Option<u64>; KEY_COUNT],
+}
+
+impl AutoRepeat {
+    /// Create a new auto-repeat generator
+    pub fn new(initial_delay_ms: u64, repeat_period_ms: u64) -> Self {
+        Self {
+            initial_delay_ms,
+            repeat_period_ms,
+            next_repeat: [None; KEY_COUNT],
+        }
+    }
+
+    /// Feed the next input frame (taken at `now_ms`) and get the labels of the keys that repeat.
+    ///
+    /// A key repeats at most once per frame, so frames should be fed at least as often as the
+    /// repeat period.
+    pub fn update(&mut self, input: &Input, now_ms: u64) -> Vec<&'static str, KEY_COUNT> {
+        let mut repeats = Vec::new();
+
+        let keys = input.numpad.labeled_keys().into_iter()
+            .chain(input.keypad.labeled_keys())
+            .chain(input.auxiliary.labeled_keys());
+        for ((key, pressed), next_repeat) in keys.zip(self.next_repeat.iter_mut()) {
+            if !pressed || key == "shift" {
+                *next_repeat = None;
+                continue;
+            }
+
+            match *next_repeat {
+                None => *next_repeat = Some(now_ms + self.initial_delay_ms),
+                Some(time) if now_ms >= time => {
+                    // There are only `KEY_COUNT` keys so this can never overflow
+                    let _ = repeats.push(key);
+                    *next_repeat = Some((time + self.repeat_period_ms).max(now_ms + 1));
+                },
+                Some(_) => (),
+            }
+        }
+
+        repeats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn held_a() -> Input {
+        let mut input = Input::default();
+        input.keypad.a = true;
+        input
+    }
+
+    #[test]
+    fn test_auto_repeat_held_key() {
+        let mut auto_repeat = AutoRepeat::new(500, 100);
+
+        assert!(auto_repeat.update(&held_a(), 0).is_empty());
+        assert!(auto_repeat.update(&held_a(), 490).is_empty());
+        assert_eq!(auto_repeat.update(&held_a(), 500), ["a"]);
+        assert!(auto_repeat.update(&held_a(), 550).is_empty());
+        assert_eq!(auto_repeat.update(&held_a(), 600), ["a"]);
+        assert_eq!(auto_repeat.update(&held_a(), 710), ["a"]);
+        assert_eq!(auto_repeat.update(&held_a(), 800), ["a"]);
+    }
+
+    #[test]
+    fn test_auto_repeat_stops_on_release() {
+        let mut auto_repeat = AutoRepeat::new(500, 100);
+
+        assert!(auto_repeat.update(&held_a(), 0).is_empty());
+        assert_eq!(auto_repeat.update(&held_a(), 500), ["a"]);
+        assert!(auto_repeat.update(&Input::default(), 600).is_empty());
+
+        // Pressing the key again restarts the initial delay
+        assert!(auto_repeat.update(&held_a(), 700).is_empty());
+        assert!(auto_repeat.update(&held_a(), 800).is_empty());
+        assert_eq!(auto_repeat.update(&held_a(), 1200), ["a"]);
+    }
+
+    #[test]
+    fn test_auto_repeat_ignores_shift() {
+        let mut auto_repeat = AutoRepeat::new(0, 100);
+        let mut input = Input::default();
+        input.keypad.shift = true;
+        input.numpad.one = true;
+
+        assert!(auto_repeat.update(&input, 0).is_empty());
+        assert_eq!(auto_repeat.update(&input, 0), ["1"]);
+    }
+}
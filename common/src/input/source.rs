@@ -0,0 +1,193 @@
+//!
+//! Request Handling Shared by the Input Modules
+//!
+//! Every input module answers the same requests whether it is read over spi (an extension) or
+//! i2c (the main input module), so the request to response logic lives here and the modules only
+//! move the bytes over their bus.
+//!
+
+use crate::{packing::Pack, protocol::{ANALOG_LEN, AUXILIARY_LEN, FULL_FRAME_LEN, KEYPAD_LEN, NUMPAD_LEN}};
+
+use super::{debug_frame::DebugFrame, Input, InputRequest, UNKNOWN_REQUEST_RESPONSE};
+
+/// The length of the largest response packed by `InputSource::respond` for the input requests
+pub const MAX_SOURCE_RESPONSE_LEN: usize = DebugFrame::PACKED_SIZE;
+
+/// A module that serves the input requests, independent of the bus the requests arrive over
+pub trait InputSource {
+    /// The input frame a request is served from (a module may latch frames between requests)
+    fn frame(&mut self, request: InputRequest) -> Input;
+
+    /// Pack the response to a request other than the input requests into `buffer`, returning the
+    /// number of bytes that should be sent (0 for a request the module does not serve)
+    fn respond_other(&mut self, _request: InputRequest, _buffer: &mut [u8]) -> usize {
+        0
+    }
+
+    /// Pack the response to a request opcode into `buffer`, returning the number of bytes that
+    /// should be sent.
+    ///
+    /// The input requests (the full input, its sections and the debug frame) are packed from
+    /// `frame`, every other request is passed to `respond_other`.  Unknown opcodes are answered
+    /// with the single `UNKNOWN_REQUEST_RESPONSE` byte so the requester's read still completes.
+    ///
+    /// `buffer` must hold the response (`MAX_SOURCE_RESPONSE_LEN` bytes always do).
+    fn respond(&mut self, opcode: u8, buffer: &mut [u8]) -> usize {
+        let request = match InputRequest::try_from(opcode) {
+            Ok(request) => request,
+            Err(_) => {
+                buffer[0] = UNKNOWN_REQUEST_RESPONSE;
+                return 1;
+            },
+        };
+
+        match request {
+            InputRequest::FullInput => {
+                self.frame(request).pack(&mut buffer[..FULL_FRAME_LEN]).unwrap();
+                FULL_FRAME_LEN
+            },
+            InputRequest::Numpad => {
+                self.frame(request).numpad.pack(&mut buffer[..NUMPAD_LEN]).unwrap();
+                NUMPAD_LEN
+            },
+            InputRequest::Keypad => {
+                self.frame(request).keypad.pack(&mut buffer[..KEYPAD_LEN]).unwrap();
+                KEYPAD_LEN
+            },
+            InputRequest::Auxiliary => {
+                self.frame(request).auxiliary.pack(&mut buffer[..AUXILIARY_LEN]).unwrap();
+                AUXILIARY_LEN
+            },
+            InputRequest::Analog => {
+                self.frame(request).analog.pack(&mut buffer[..ANALOG_LEN]).unwrap();
+                ANALOG_LEN
+            },
+            InputRequest::DebugFrame => {
+                DebugFrame::new(&self.frame(request)).pack(&mut buffer[..DebugFrame::PACKED_SIZE]).unwrap();
+                DebugFrame::PACKED_SIZE
+            },
+            _ => self.respond_other(request, buffer),
+        }
+    }
+}
+
+/// An input serves every request from itself
+impl InputSource for Input {
+    fn frame(&mut self, _request: InputRequest) -> Input {
+        *self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use defmt::Format;
+    use embedded_hal::i2c::{ErrorKind, ErrorType, I2c, Operation, SevenBitAddress};
+
+    use crate::{input::{InputModuleDriver, BUILD_ID_LEN}, packing::Unpack};
+
+    const BUILD_ID: [u8; BUILD_ID_LEN] = *b"0123abcd";
+
+    #[derive(Clone, Copy, Debug, Format, PartialEq, Eq)]
+    /// The in-memory bus never fails
+    struct NoError;
+
+    impl embedded_hal::i2c::Error for NoError {
+        fn kind(&self) -> ErrorKind {
+            ErrorKind::Other
+        }
+    }
+
+    /// Serves the input requests from an input and the build id from `respond_other`
+    struct TestSource(Input);
+
+    impl InputSource for TestSource {
+        fn frame(&mut self, _request: InputRequest) -> Input {
+            self.0
+        }
+
+        fn respond_other(&mut self, request: InputRequest, buffer: &mut [u8]) -> usize {
+            match request {
+                InputRequest::BuildId => {
+                    buffer[..BUILD_ID_LEN].copy_from_slice(&BUILD_ID);
+                    BUILD_ID_LEN
+                },
+                _ => 0,
+            }
+        }
+    }
+
+    /// An in-memory i2c bus where a write is a request to the source and a read takes its response
+    struct MemoryBus<S> {
+        source: S,
+        response: [u8; MAX_SOURCE_RESPONSE_LEN],
+        len: usize,
+    }
+
+    impl<S: InputSource> MemoryBus<S> {
+        fn new(source: S) -> Self {
+            Self { source, response: [0u8; MAX_SOURCE_RESPONSE_LEN], len: 0 }
+        }
+    }
+
+    impl<S> ErrorType for MemoryBus<S> {
+        type Error = NoError;
+    }
+
+    impl<S: InputSource> I2c<SevenBitAddress> for MemoryBus<S> {
+        fn transaction(&mut self, _address: SevenBitAddress, operations: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+            for operation in operations {
+                match operation {
+                    Operation::Write(bytes) => self.len = self.source.respond(bytes[0], &mut self.response),
+                    Operation::Read(buffer) => {
+                        // A read past the response is padded with zeros
+                        buffer.fill(0);
+                        let len = self.len.min(buffer.len());
+                        buffer[..len].copy_from_slice(&self.response[..len]);
+                    },
+                }
+            }
+            Ok(())
+        }
+    }
+
+    fn test_input() -> Input {
+        let mut input = Input::from_str_keys("5kS").unwrap();
+        input.auxiliary.hash = true;
+        input.analog.a4 = 0x0321;
+        #[cfg(feature = "other-input")]
+        {
+            input.other_input_one[3] = 0xAB;
+        }
+        input
+    }
+
+    #[test]
+    fn test_source_serves_driver() {
+        let input = test_input();
+        let mut driver = InputModuleDriver::new(0x42, MemoryBus::new(TestSource(input)));
+
+        assert_eq!(driver.get_input(), Ok(input));
+        assert_eq!(driver.get_numpad(), Ok(input.numpad));
+        assert_eq!(driver.get_keypad(), Ok(input.keypad));
+        assert_eq!(driver.get_auxiliary(), Ok(input.auxiliary));
+        assert_eq!(driver.get_analog(), Ok(input.analog));
+        assert_eq!(driver.get_build_id(), Ok(BUILD_ID));
+
+        let (frame, _) = driver.get_debug_frame().unwrap();
+        assert!(frame.is_valid());
+        assert_eq!(Input::unpack(&frame.bytes), Ok(input));
+    }
+
+    #[test]
+    fn test_source_unknown_and_unserved() {
+        let mut source = test_input();
+        let mut buffer = [0u8; MAX_SOURCE_RESPONSE_LEN];
+
+        assert_eq!(source.respond(0xEE, &mut buffer), 1);
+        assert_eq!(buffer[0], UNKNOWN_REQUEST_RESPONSE);
+        assert_eq!(source.respond(InputRequest::BuildId as u8, &mut buffer), 0);
+        assert_eq!(source.respond(InputRequest::Numpad as u8, &mut buffer), NUMPAD_LEN);
+    }
+}
@@ -0,0 +1,302 @@
+//!
+//! Analog Threshold Events
+//!
+//! A program driving a menu from an analog input (i.e. a volume knob) usually only cares when the
+//! input crosses a level, so rather than streaming the raw values the main input module can queue
+//! an event each time a channel crosses its configured threshold.  Each threshold has a hysteresis
+//! band around it: the channel must rise past the top of the band to cross upward and fall past the
+//! bottom of the band to cross downward, so a value resting near the threshold does not chatter.
+//!
+
+use defmt::Format;
+use heapless::Deque;
+
+use crate::packing::{Pack, PackingError, Unpack};
+
+use super::analog::{AnalogInputs, ANALOG_DISCONNECTED};
+
+/// The number of events queued before the oldest event is dropped
+pub const THRESHOLD_QUEUE_LEN: usize = 16;
+
+/// The byte sent in response to a `ThresholdEvents` request while no event is queued
+pub const NO_THRESHOLD_EVENT: u8 = 0xFF;
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq)]
+/// The threshold of a single analog channel
+pub struct AnalogThreshold {
+    /// The level the channel crosses
+    pub level: u16,
+    /// How far past the level the channel must move to cross it
+    pub hysteresis: u16,
+}
+
+impl AnalogThreshold {
+    /// The top of the hysteresis band (the channel crosses upward at or above it)
+    fn rising_level(&self) -> u16 {
+        self.level.saturating_add(self.hysteresis)
+    }
+
+    /// The bottom of the hysteresis band (the channel crosses downward at or below it)
+    fn falling_level(&self) -> u16 {
+        self.level.saturating_sub(self.hysteresis)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq, Default)]
+/// The threshold of each analog channel (`None` for a channel without events)
+pub struct ThresholdConfig {
+    /// The threshold of each channel (in the order of `AnalogInputs::channels`)
+    pub channels: [Option<AnalogThreshold>; 6],
+}
+
+impl ThresholdConfig {
+    /// The length of the packed threshold config (the level then the hysteresis of each channel)
+    pub const PACKED_SIZE: usize = 6 * 4;
+}
+
+impl Pack for ThresholdConfig {
+    fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
+        if buffer.len() < Self::PACKED_SIZE {
+            return Err(PackingError::InvalidBufferSize);
+        }
+
+        // A channel without a threshold packs the disconnected level, which no channel can read
+        for (chunk, threshold) in buffer.chunks_exact_mut(4).zip(self.channels) {
+            let threshold = threshold.unwrap_or(AnalogThreshold { level: ANALOG_DISCONNECTED, hysteresis: 0 });
+            chunk[0..2].copy_from_slice(&threshold.level.to_le_bytes());
+            chunk[2..4].copy_from_slice(&threshold.hysteresis.to_le_bytes());
+        }
+        Ok(())
+    }
+}
+
+impl Unpack for ThresholdConfig {
+    fn unpack(buffer: &[u8]) -> Result<Self, PackingError> where Self: Sized {
+        if buffer.len() < Self::PACKED_SIZE {
+            return Err(PackingError::InvalidBufferSize);
+        }
+
+        let mut config = Self::default();
+        for (chunk, threshold) in buffer.chunks_exact(4).zip(config.channels.iter_mut()) {
+            let level = u16::from_le_bytes([chunk[0], chunk[1]]);
+            let hysteresis = u16::from_le_bytes([chunk[2], chunk[3]]);
+            *threshold = (level != ANALOG_DISCONNECTED).then_some(AnalogThreshold { level, hysteresis });
+        }
+        Ok(config)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq)]
+/// The direction a channel crossed its threshold in
+pub enum ThresholdDirection {
+    /// The channel rose above its threshold
+    Rising,
+    /// The channel fell below its threshold
+    Falling,
+}
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq)]
+/// A channel crossing its threshold
+pub struct ThresholdEvent {
+    /// The channel that crossed its threshold (0..6)
+    pub channel: u8,
+    /// The direction the channel crossed in
+    pub direction: ThresholdDirection,
+}
+
+impl ThresholdEvent {
+    /// The length of the packed event (the channel with the falling flag in the top bit)
+    pub const PACKED_SIZE: usize = 1;
+}
+
+impl Pack for ThresholdEvent {
+    fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
+        if buffer.len() < Self::PACKED_SIZE {
+            return Err(PackingError::InvalidBufferSize);
+        }
+
+        let falling = match self.direction {
+            ThresholdDirection::Rising => 0,
+            ThresholdDirection::Falling => 0x80,
+        };
+        buffer[0] = falling | self.channel;
+        Ok(())
+    }
+}
+
+impl Unpack for ThresholdEvent {
+    fn unpack(buffer: &[u8]) -> Result<Self, PackingError> where Self: Sized {
+        if buffer.len() < Self::PACKED_SIZE {
+            return Err(PackingError::InvalidBufferSize);
+        }
+
+        let channel = buffer[0] & 0x7F;
+        if channel >= 6 {
+            return Err(PackingError::InvalidEncoding);
+        }
+        let direction = if buffer[0] & 0x80 == 0 {
+            ThresholdDirection::Rising
+        } else {
+            ThresholdDirection::Falling
+        };
+        Ok(Self { channel, direction })
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Detects the analog channels crossing their thresholds and queues an event for each crossing
+pub struct ThresholdDetector {
+    /// The threshold of each channel
+    config: ThresholdConfig,
+    /// Whether each channel is above its threshold (`None` until the channel is first read)
+    above: [Option<bool>; 6],
+    /// The events waiting to be read
+    events: Deque<ThresholdEvent, THRESHOLD_QUEUE_LEN>,
+}
+
+impl ThresholdDetector {
+    /// Create a new detector for the thresholds
+    pub const fn new(config: ThresholdConfig) -> Self {
+        Self {
+            config,
+            above: [None; 6],
+            events: Deque::new(),
+        }
+    }
+
+    /// Replace the thresholds, discarding the queued events
+    pub fn configure(&mut self, config: ThresholdConfig) {
+        *self = Self::new(config);
+    }
+
+    /// Feed the next analog inputs, queueing an event for each channel that crossed its threshold.
+    ///
+    /// The first reading of a channel (or a reading inside the hysteresis band) only settles which
+    /// side of the threshold the channel is on, and a disconnected channel is ignored.  When the
+    /// queue is full the oldest event is dropped.
+    pub fn update(&mut self, analog: &AnalogInputs) {
+        let channels = analog.channels();
+        for (channel, (threshold, above)) in self.config.channels.iter().zip(self.above.iter_mut()).enumerate() {
+            let raw = channels[channel];
+            let Some(threshold) = threshold else {
+                continue;
+            };
+            if raw == ANALOG_DISCONNECTED {
+                continue;
+            }
+
+            let crossed = match *above {
+                Some(false) | None if raw >= threshold.rising_level() => ThresholdDirection::Rising,
+                Some(true) | None if raw <= threshold.falling_level() => ThresholdDirection::Falling,
+                _ => continue,
+            };
+            let first = above.is_none();
+            *above = Some(crossed == ThresholdDirection::Rising);
+            if first {
+                continue;
+            }
+
+            if self.events.is_full() {
+                self.events.pop_front();
+            }
+            // There is always room after dropping the oldest event
+            let _ = self.events.push_back(ThresholdEvent { channel: channel as u8, direction: crossed });
+        }
+    }
+
+    /// Take the oldest queued event
+    pub fn next_event(&mut self) -> Option<ThresholdEvent> {
+        self.events.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VOLUME: AnalogThreshold = AnalogThreshold { level: 2000, hysteresis: 100 };
+
+    fn volume_detector() -> ThresholdDetector {
+        let mut config = ThresholdConfig::default();
+        config.channels[2] = Some(VOLUME);
+        ThresholdDetector::new(config)
+    }
+
+    fn a2(raw: u16) -> AnalogInputs {
+        AnalogInputs { a2: raw, ..Default::default() }
+    }
+
+    #[test]
+    fn test_threshold_crossing_up_and_down() {
+        let mut detector = volume_detector();
+
+        detector.update(&a2(500));
+        assert_eq!(detector.next_event(), None);
+
+        detector.update(&a2(2100));
+        assert_eq!(detector.next_event(), Some(ThresholdEvent { channel: 2, direction: ThresholdDirection::Rising }));
+
+        detector.update(&a2(1900));
+        assert_eq!(detector.next_event(), Some(ThresholdEvent { channel: 2, direction: ThresholdDirection::Falling }));
+        assert_eq!(detector.next_event(), None);
+    }
+
+    #[test]
+    fn test_threshold_hysteresis_prevents_chatter() {
+        let mut detector = volume_detector();
+        detector.update(&a2(1500));
+
+        // Jitter around the level stays inside the hysteresis band
+        for raw in [1950, 2050, 1990, 2099, 1901, 2000] {
+            detector.update(&a2(raw));
+        }
+        assert_eq!(detector.next_event(), None);
+
+        detector.update(&a2(2150));
+        for raw in [2050, 1950, 2099, 1901] {
+            detector.update(&a2(raw));
+        }
+        assert_eq!(detector.next_event(), Some(ThresholdEvent { channel: 2, direction: ThresholdDirection::Rising }));
+        assert_eq!(detector.next_event(), None);
+    }
+
+    #[test]
+    fn test_threshold_queue_drops_oldest() {
+        let mut detector = volume_detector();
+        detector.update(&a2(0));
+        for _ in 0..THRESHOLD_QUEUE_LEN {
+            detector.update(&a2(4000));
+            detector.update(&a2(0));
+        }
+
+        // The queue holds the latest crossings, which end falling
+        let mut last = None;
+        let mut count = 0;
+        while let Some(event) = detector.next_event() {
+            last = Some(event.direction);
+            count += 1;
+        }
+        assert_eq!(count, THRESHOLD_QUEUE_LEN);
+        assert_eq!(last, Some(ThresholdDirection::Falling));
+    }
+
+    #[test]
+    fn test_pack_unpack_thresholds() {
+        let mut config = ThresholdConfig::default();
+        config.channels[0] = Some(AnalogThreshold { level: 0x0123, hysteresis: 0x0045 });
+        config.channels[5] = Some(VOLUME);
+
+        let mut buffer = [0u8; ThresholdConfig::PACKED_SIZE];
+        config.pack(&mut buffer).unwrap();
+        assert_eq!(buffer[0..4], [0x23, 0x01, 0x45, 0x00]);
+        assert_eq!(buffer[4..6], ANALOG_DISCONNECTED.to_le_bytes());
+        assert_eq!(ThresholdConfig::unpack(&buffer), Ok(config));
+
+        let event = ThresholdEvent { channel: 5, direction: ThresholdDirection::Falling };
+        let mut buffer = [0u8; ThresholdEvent::PACKED_SIZE];
+        event.pack(&mut buffer).unwrap();
+        assert_eq!(buffer, [0x85]);
+        assert_eq!(ThresholdEvent::unpack(&buffer), Ok(event));
+        assert_eq!(ThresholdEvent::unpack(&[0x06]), Err(PackingError::InvalidEncoding));
+    }
+}
@@ -0,0 +1,100 @@
+//!
+//! Key Transitions Between Input Frames
+//!
+
+use defmt::Format;
+use heapless::Vec;
+
+use super::Input;
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq)]
+/// A numpad or keypad key that changed state between two frames
+pub struct Transition {
+    /// The label of the key (i.e. "1", "a", or "shift")
+    pub key: &'static str,
+    /// True if the key was pressed, false if it was released
+    pub pressed: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// The numpad and keypad keys that changed between two frames (numpad keys first).
+///
+/// Formats (with defmt) as a compact list of transitions, e.g. `+a -1`
+pub struct Transitions(Vec<Transition, 39>);
+
+impl Transitions {
+    /// Find the numpad and keypad keys that changed between the previous and current frame
+    pub fn between(previous: &Input, current: &Input) -> Self {
+        let mut transitions = Vec::new();
+
+        let numpad = previous.numpad.labeled_keys().into_iter()
+            .zip(current.numpad.labeled_keys());
+        let keypad = previous.keypad.labeled_keys().into_iter()
+            .zip(current.keypad.labeled_keys());
+        for ((key, was_pressed), (_, pressed)) in numpad.chain(keypad) {
+            if was_pressed != pressed {
+                // There are only 39 keys so this can never overflow
+                let _ = transitions.push(Transition { key, pressed });
+            }
+        }
+
+        Self(transitions)
+    }
+
+    /// True if no keys changed
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The transitions in order (numpad 0-9, then keypad shift, enter, backspace, a-z)
+    pub fn iter(&self) -> impl Iterator<Item = &Transition> {
+        self.0.iter()
+    }
+}
+
+impl Format for Transitions {
+    fn format(&self, fmt: defmt::Formatter) {
+        for (i, transition) in self.0.iter().enumerate() {
+            if i != 0 {
+                defmt::write!(fmt, " ");
+            }
+            defmt::write!(
+                fmt,
+                "{=str}{=str}",
+                if transition.pressed { "+" } else { "-" },
+                transition.key,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transitions_between() {
+        let mut previous = Input::default();
+        previous.numpad.one = true;
+        previous.keypad.z = true;
+
+        let mut current = Input::default();
+        current.keypad.a = true;
+        current.keypad.z = true;
+
+        let transitions = Transitions::between(&previous, &current);
+        assert!(transitions.iter().eq([
+            Transition { key: "1", pressed: false },
+            Transition { key: "a", pressed: true },
+        ].iter()));
+    }
+
+    #[test]
+    fn test_no_transitions() {
+        let mut input = Input::default();
+        input.numpad.five = true;
+        input.keypad.shift = true;
+
+        assert!(Transitions::between(&input, &input).is_empty());
+    }
+}
@@ -6,8 +6,14 @@
 
 pub mod input;
 
+pub mod protocol;
+
 pub mod packing;
 
+pub mod crc;
+
+pub mod time;
+
 pub mod prelude;
 
 pub mod output;
@@ -2,11 +2,16 @@
 //! Packing and unpacking traits
 //! 
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+use defmt::Format;
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq)]
 /// Error from packing data
 pub enum PackingError {
     /// The buffer size was not large enought to accomidate the data
     InvalidBufferSize,
+    /// The buffer holds a bit pattern that does not encode any value (i.e. an erased or corrupt
+    /// buffer)
+    InvalidEncoding,
 }
 
 /// Trait for packing data into a buffer for transmission over some protocol
@@ -19,4 +24,56 @@ pub trait Pack {
 pub trait Unpack {
     /// Unpack the data from a given buffer
     fn unpack(buffer: &[u8]) -> Result<Self, PackingError> where Self: Sized;
-}
\ No newline at end of file
+}
+
+/// Trait for data that always packs into the same number of bytes
+pub trait PackedSize {
+    /// The number of bytes the data packs into
+    const PACKED_LEN: usize;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::input::{analog::AnalogInputs, auxiliary::Auxiliary, keypad::Keypad, numpad::Numpad, other::DecodeInstructions, Input};
+
+    /// Check that the data packs into exactly its packed length (and not into one byte less)
+    fn assert_packed_len<T: Pack + Copy + PackedSize>(value: T, expected: usize) {
+        assert_eq!(T::PACKED_LEN, expected);
+
+        let mut buffer = [0u8; 256];
+        assert_eq!(value.pack(&mut buffer[..T::PACKED_LEN]), Ok(()));
+        assert_eq!(value.pack(&mut buffer[..T::PACKED_LEN - 1]), Err(PackingError::InvalidBufferSize));
+    }
+
+    #[test]
+    fn test_numpad_packed_len() {
+        assert_packed_len(Numpad::default(), 2);
+    }
+
+    #[test]
+    fn test_keypad_packed_len() {
+        assert_packed_len(Keypad::default(), 4);
+    }
+
+    #[test]
+    fn test_auxiliary_packed_len() {
+        assert_packed_len(Auxiliary::default(), 4);
+    }
+
+    #[test]
+    fn test_analog_packed_len() {
+        assert_packed_len(AnalogInputs::default(), 12);
+    }
+
+    #[test]
+    fn test_input_packed_len() {
+        assert_packed_len(Input::default(), Input::PACKED_SIZE);
+    }
+
+    #[test]
+    fn test_decode_instructions_packed_len() {
+        assert_packed_len(DecodeInstructions::default(), 248);
+    }
+}
@@ -1,13 +1,47 @@
 //!
 //! Ease of use prelude for the pip-boy common library
-//! 
+//!
+//! A single `use` brings the driver, its errors and the input types into scope:
+//!
+//! ```no_run
+//! use common::prelude::*;
+//! use embedded_hal::i2c::I2c;
+//!
+//! fn read_numpad<I2C: I2c<Error = E>, E: core::fmt::Debug + defmt::Format>(
+//!     driver: &mut InputModuleDriver<I2C>,
+//! ) -> Result<Numpad, DriverError<E>> {
+//!     driver.get_numpad()
+//! }
+//! ```
 
-pub use crate::packing::{Pack, Unpack, PackingError};
+pub use crate::packing::{Pack, PackedSize, Unpack, PackingError};
+pub use crate::crc::Crc;
+pub use crate::time::{Clock, Debouncer, Heartbeat, TimerDuration, TimerInstant};
 pub use crate::input::{
-    Input, InputBuilder,
-    analog::{AnalogInputs, AnalogInputsBuilder},
+    Input, InputBuilder, InputSummary, DigitalInput, PackedInput, InputModuleDriver, DriverError, BuildId, InputRequest, OtherRequest,
+    config::{Config, ConfigError, ReportMode},
+    analog::{AnalogDelta, AnalogInputs, AnalogInputsBuilder, AnalogRangeError, Calibration, TimedAnalog, ANALOG_DISCONNECTED},
     auxiliary::{Auxiliary, AuxiliaryBuilder},
-    keypad::{Keypad, KeypadBuilder},
+    keypad::{Keypad, KeypadBuilder, KeypadKey},
     numpad::{Numpad, NumpadBuilder},
-    other::{DataSize, DataType, DecodeInstructions, OtherInput},
+    raw_buttons::{RawButtons, RawButtonsBuilder},
+    mapping::{ButtonTarget, InputMapping},
+    capabilities::Capabilities,
+    other::{
+        decode_f64_fields, trim_name, DataSize, DataType, Decode, DecodeError, DecodeInstructions, DecodedInput,
+        DecodedValue, Encode, FieldName, IntoDecoded, ModuleId, OtherInput,
+    },
+    transitions::{Transition, Transitions},
+    repeat::AutoRepeat,
+    changes::SectionChanges,
+    chord::ChordDetector,
+    dpad::{DPad, DPadConflict},
+    debug_frame::DebugFrame,
+    alert::{ChangeAlert, ChangeFrame},
+    command_log::{CommandEntry, CommandLog},
+    extension_status::{DisableReason, ExtensionStatus},
+    threshold::{AnalogThreshold, ThresholdConfig, ThresholdDetector, ThresholdDirection, ThresholdEvent},
+    source::{InputSource, MAX_SOURCE_RESPONSE_LEN},
 };
+#[cfg(feature = "async")]
+pub use crate::input::AsyncInputModuleDriver;
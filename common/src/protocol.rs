@@ -0,0 +1,57 @@
+//!
+//! Packed Lengths of the Input Protocol
+//!
+//! The authoritative lengths of each response, for sizing buffers without copying the numbers.
+//!
+
+use crate::input::{analog::AnalogInputs, auxiliary::Auxiliary, keypad::Keypad, numpad::Numpad, other::{DecodeInstructions, OtherInput}, raw_buttons::RawButtons, Input};
+
+pub use crate::input::{EXTENSION_FULL_INPUT_LEN, EXTENSION_HEADER_LEN, MAX_TRANSFER_LEN};
+
+/// The length of a packed full input frame
+pub const FULL_FRAME_LEN: usize = Input::PACKED_SIZE;
+
+/// The length of a packed numpad
+pub const NUMPAD_LEN: usize = Numpad::PACKED_SIZE;
+
+/// The length of a packed keypad
+pub const KEYPAD_LEN: usize = Keypad::PACKED_SIZE;
+
+/// The length of a packed auxiliary
+pub const AUXILIARY_LEN: usize = Auxiliary::PACKED_SIZE;
+
+/// The length of the packed analog inputs
+pub const ANALOG_LEN: usize = AnalogInputs::PACKED_SIZE;
+
+/// The length of packed decode instructions
+pub const DECODE_INSTRUCTIONS_LEN: usize = DecodeInstructions::PACKED_SIZE;
+
+/// The length of an other input
+pub const OTHER_INPUT_LEN: usize = size_of::<OtherInput>();
+
+/// The length of the packed raw buttons
+pub const RAW_BUTTONS_LEN: usize = RawButtons::PACKED_SIZE;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::input::InputRequest;
+
+    #[test]
+    fn test_full_frame_len() {
+        assert_eq!(FULL_FRAME_LEN, Input::PACKED_SIZE);
+        assert_eq!(FULL_FRAME_LEN, InputRequest::FullInput.response_len());
+    }
+
+    #[test]
+    fn test_section_lens_match_responses() {
+        assert_eq!(NUMPAD_LEN, InputRequest::Numpad.response_len());
+        assert_eq!(KEYPAD_LEN, InputRequest::Keypad.response_len());
+        assert_eq!(AUXILIARY_LEN, InputRequest::Auxiliary.response_len());
+        assert_eq!(ANALOG_LEN, InputRequest::Analog.response_len());
+        assert_eq!(DECODE_INSTRUCTIONS_LEN, InputRequest::DecodeN.response_len());
+        assert_eq!(OTHER_INPUT_LEN, InputRequest::OtherN.response_len());
+        assert_eq!(RAW_BUTTONS_LEN, InputRequest::RawButtons.response_len());
+    }
+}
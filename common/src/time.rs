@@ -0,0 +1,187 @@
+//!
+//! Input Timing Shared by the Input Modules
+//!
+
+use defmt::Format;
+use fugit::{Duration, Instant};
+
+/// An instant of the 1 MHz timer used for input timing
+pub type TimerInstant = Instant<u64, 1, 1_000_000>;
+
+/// A duration of the 1 MHz timer used for input timing
+pub type TimerDuration = Duration<u64, 1, 1_000_000>;
+
+/// A source of the current time for the input timing logic (the RTIC monotonic or hal timer on the
+/// device, a controllable clock in tests)
+pub trait Clock {
+    /// The current time
+    fn now(&self) -> TimerInstant;
+}
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq)]
+/// Debounces a button by only accepting a new level once it has been stable for a window
+pub struct Debouncer {
+    /// The time a new level must be held before it is accepted
+    window: TimerDuration,
+    /// The accepted (debounced) level
+    stable: bool,
+    /// The time the raw level first differed from the accepted level (if it currently differs)
+    changed_at: Option<TimerInstant>,
+}
+
+impl Debouncer {
+    /// Create a new debouncer with a stable window, starting at the given level
+    pub const fn new(window: TimerDuration, initial: bool) -> Self {
+        Self {
+            window,
+            stable: initial,
+            changed_at: None,
+        }
+    }
+
+    /// Feed the raw level of the button and get the debounced level
+    pub fn update<C: Clock>(&mut self, clock: &C, raw: bool) -> bool {
+        if raw == self.stable {
+            self.changed_at = None;
+            return self.stable;
+        }
+
+        let now = clock.now();
+        match self.changed_at {
+            Some(changed_at) if now - changed_at >= self.window => {
+                self.stable = raw;
+                self.changed_at = None;
+            },
+            Some(_) => (),
+            None => self.changed_at = Some(now),
+        }
+
+        self.stable
+    }
+
+    /// The debounced level
+    pub fn state(&self) -> bool {
+        self.stable
+    }
+}
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq)]
+/// Tracks whether a polling loop is still running so a watchdog is only fed while it is alive
+pub struct Heartbeat {
+    /// The longest time between beats before the loop is considered stuck
+    max_age: TimerDuration,
+    /// The time of the last beat (`None` before the first beat)
+    last_beat: Option<TimerInstant>,
+}
+
+impl Heartbeat {
+    /// Create a new heartbeat that goes stale after `max_age` without a beat
+    pub const fn new(max_age: TimerDuration) -> Self {
+        Self {
+            max_age,
+            last_beat: None,
+        }
+    }
+
+    /// Record that the polling loop ran
+    pub fn beat<C: Clock>(&mut self, clock: &C) {
+        self.last_beat = Some(clock.now());
+    }
+
+    /// Check if the polling loop beat recently enough that the watchdog should be fed (a loop
+    /// that never beat is not alive)
+    pub fn is_alive<C: Clock>(&self, clock: &C) -> bool {
+        match self.last_beat {
+            Some(last_beat) => clock.now() - last_beat <= self.max_age,
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::cell::Cell;
+    use fugit::ExtU64;
+
+    /// A clock that only moves when advanced
+    struct FakeClock(Cell<TimerInstant>);
+
+    impl FakeClock {
+        fn new() -> Self {
+            Self(Cell::new(TimerInstant::from_ticks(0)))
+        }
+
+        fn advance_ms(&self, ms: u64) {
+            self.0.set(self.0.get() + ms.millis());
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> TimerInstant {
+            self.0.get()
+        }
+    }
+
+    /// Feed a sequence of (milliseconds since the last sample, raw level) and collect the debounced levels
+    fn debounce<const N: usize>(window_ms: u64, samples: [(u64, bool); N]) -> [bool; N] {
+        let clock = FakeClock::new();
+        let mut debouncer = Debouncer::new(window_ms.millis(), false);
+        samples.map(|(ms, raw)| {
+            clock.advance_ms(ms);
+            debouncer.update(&clock, raw)
+        })
+    }
+
+    #[test]
+    fn test_debounce_short_window() {
+        let levels = debounce(5, [(0, true), (1, false), (1, true), (2, true), (2, true), (1, true)]);
+        assert_eq!(levels, [false, false, false, false, false, true]);
+    }
+
+    #[test]
+    fn test_debounce_long_window() {
+        let levels = debounce(20, [(0, true), (5, false), (5, true), (10, true), (9, true), (1, true)]);
+        assert_eq!(levels, [false, false, false, false, false, true]);
+    }
+
+    #[test]
+    fn test_debounce_release() {
+        let clock = FakeClock::new();
+        let mut debouncer = Debouncer::new(5.millis(), true);
+
+        assert!(debouncer.update(&clock, false));
+        clock.advance_ms(2);
+        assert!(debouncer.update(&clock, true));
+        clock.advance_ms(2);
+        assert!(debouncer.update(&clock, false));
+        clock.advance_ms(5);
+        assert!(!debouncer.update(&clock, false));
+        assert!(!debouncer.state());
+    }
+
+    #[test]
+    fn test_heartbeat_fresh() {
+        let clock = FakeClock::new();
+        let mut heartbeat = Heartbeat::new(100.millis());
+
+        heartbeat.beat(&clock);
+        clock.advance_ms(100);
+        assert!(heartbeat.is_alive(&clock));
+        heartbeat.beat(&clock);
+        clock.advance_ms(60);
+        assert!(heartbeat.is_alive(&clock));
+    }
+
+    #[test]
+    fn test_heartbeat_stale() {
+        let clock = FakeClock::new();
+        let mut heartbeat = Heartbeat::new(100.millis());
+        assert!(!heartbeat.is_alive(&clock));
+
+        heartbeat.beat(&clock);
+        clock.advance_ms(101);
+        assert!(!heartbeat.is_alive(&clock));
+    }
+}
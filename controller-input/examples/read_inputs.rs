@@ -19,7 +19,7 @@ mod app {
     use fugit::ExtU32;
     use embedded_hal_0_2::{adc::OneShot, digital::v2::InputPin};
 
-    use controller_input::{peripherals::*, READ_DELAY_US};
+    use controller_input::{peripherals::*, CONTROLLER_MAPPING, READ_DELAY_US};
 
     #[shared]
     struct Shared {
@@ -106,10 +106,7 @@ mod app {
         let b = ctx.local.b.is_high().unwrap();
 
         let input = ctx.shared.input.lock(|input| {
-            input.keypad.a = a;
-            input.keypad.b = b;
-            input.analog.a0 = x;
-            input.analog.a1 = y;
+            CONTROLLER_MAPPING.apply(input, [a, b], [x, y]);
 
             if *ctx.local.iteration % 100 == 0 {
                 Some(input.clone())
@@ -14,12 +14,12 @@ use panic_probe as _;
     dispatchers = [SW0_IRQ, SW1_IRQ]
 )]
 mod app {
-    use common::{input::{Input, InputRequest}, prelude::{AnalogInputsBuilder, AuxiliaryBuilder, KeypadBuilder, NumpadBuilder, Pack}};
+    use common::{input::Input, prelude::{AnalogInputsBuilder, AuxiliaryBuilder, KeypadBuilder, NumpadBuilder}};
     use embedded_hal::spi::MODE_0;
     use rp_pico::{hal::{self, clocks::init_clocks_and_plls, gpio::FunctionSpi, spi::FrameFormat, Sio, Spi, Watchdog}, Pins};
     use embedded_hal_nb::spi::FullDuplex;
 
-    use controller_input::peripherals::*;
+    use controller_input::{handle_request, peripherals::*, MAX_RESPONSE_LEN};
 
     #[shared]
     struct Shared {
@@ -114,46 +114,11 @@ mod app {
         binds = SPI0_IRQ
     )]
     fn relay_inputs(mut ctx: relay_inputs::Context) {
-        ctx.shared.input.lock(|input| {
-            let instruction = InputRequest::from(ctx.local.spi_line.read().unwrap());
-            match instruction {
-                InputRequest::FullInput => {
-                    let mut buffer = [0u8; 71];
-                    input.pack(&mut buffer).unwrap();
-                    for byte in buffer {
-                        ctx.local.spi_line.write(byte).unwrap();
-                    }
-                },
-                InputRequest::Numpad => {
-                    let mut buffer = [0u8; 2];
-                    input.pack(&mut buffer).unwrap();
-                    for byte in buffer {
-                        ctx.local.spi_line.write(byte).unwrap();
-                    }
-                },
-                InputRequest::Keypad => {
-                    let mut buffer = [0u8; 4];
-                    input.pack(&mut buffer).unwrap();
-                    for byte in buffer {
-                        ctx.local.spi_line.write(byte).unwrap();
-                    }
-                },
-                InputRequest::Auxiliary => {
-                    let mut buffer = [0u8; 4];
-                    input.pack(&mut buffer).unwrap();
-                    for byte in buffer {
-                        ctx.local.spi_line.write(byte).unwrap();
-                    }
-                },
-                InputRequest::Analog => {
-                    let mut buffer = [0u8; 12];
-                    input.pack(&mut buffer).unwrap();
-                    for byte in buffer {
-                        ctx.local.spi_line.write(byte).unwrap();
-                    }
-                },
-                _ => (),
-            }
-        });
+        let request = ctx.local.spi_line.read().unwrap();
+        let mut buffer = [0u8; MAX_RESPONSE_LEN];
+        let len = ctx.shared.input.lock(|input| handle_request(request, input, &mut buffer));
+        for byte in &buffer[..len] {
+            ctx.local.spi_line.write(*byte).unwrap();
+        }
     }
 }
\ No newline at end of file
@@ -8,3 +8,216 @@ pub mod peripherals;
 
 /// The amount of time between subsequent readings of the inputs
 pub const READ_DELAY_US: u32 = 1_000;
+
+/// The time the a and b buttons must be stable before a press or release is reported
+pub const BUTTON_DEBOUNCE: TimerDuration = TimerDuration::millis(5);
+
+/// The longest time between input readings before the read loop is considered stuck
+pub const HEARTBEAT_MAX_AGE: TimerDuration = TimerDuration::millis(100);
+
+/// The number of adc reads averaged into each joystick axis reading
+pub const ANALOG_OVERSAMPLES: usize = 4;
+
+/// The time without a feed before the watchdog resets the module
+pub const WATCHDOG_TIMEOUT_US: u32 = 500_000;
+
+/// The time between checks of the read loop heartbeat (feeding the watchdog if it is alive)
+pub const WATCHDOG_FEED_PERIOD_US: u32 = 50_000;
+
+use common::{crc::crc8, time::TimerDuration, input::{analog::{reads_disconnected, ANALOG_DISCONNECTED, DISCONNECTED_RAIL_MARGIN}, debug_frame::DebugFrame, mapping::{ButtonTarget, InputMapping}, source::{InputSource, MAX_SOURCE_RESPONSE_LEN}, Input, InputRequest, EXTENSION_FULL_INPUT_LEN, EXTENSION_HEADER_LEN, EXTENSION_SYNC_BYTE}};
+
+/// Where the controller's buttons (a, b) and joystick axes (x, y) are reported in the input
+pub const CONTROLLER_MAPPING: InputMapping<2, 2> = InputMapping {
+    buttons: [ButtonTarget::Letter('a'), ButtonTarget::Letter('b')],
+    analog: [0, 1],
+};
+
+/// An alternate mapping for using the controller as a game pad (a confirms with enter, b goes back
+/// with backspace)
+pub const GAMEPAD_MAPPING: InputMapping<2, 2> = InputMapping {
+    buttons: [ButtonTarget::Enter, ButtonTarget::Backspace],
+    analog: [0, 1],
+};
+
+/// Mark the joystick axes `ANALOG_DISCONNECTED` when the joystick is unplugged.
+///
+/// Both axes must be pinned at a rail since a connected stick at full deflection may pin one, but
+/// an unplugged stick leaves both pins floating.
+pub fn joystick_axes(x: u16, y: u16) -> [u16; 2] {
+    if reads_disconnected(x, DISCONNECTED_RAIL_MARGIN) && reads_disconnected(y, DISCONNECTED_RAIL_MARGIN) {
+        [ANALOG_DISCONNECTED; 2]
+    } else {
+        [x, y]
+    }
+}
+
+/// Read an analog channel `samples` times and return the integer average of the reads (a
+/// `samples` of 0 reads once)
+pub fn oversample<F: FnMut() -> u16>(samples: usize, mut read: F) -> u16 {
+    let samples = samples.max(1);
+    let sum: u32 = (0..samples).map(|_| read() as u32).sum();
+    (sum / samples as u32) as u16
+}
+
+/// The length of the largest response to a request from the main input module (including the
+/// header)
+pub const MAX_RESPONSE_LEN: usize = EXTENSION_HEADER_LEN + DebugFrame::PACKED_SIZE;
+
+// Fail the build if a response served by the controller outgrows the response buffer.  When
+// serving a new request, add its length here.
+const _: () = {
+    assert!(EXTENSION_FULL_INPUT_LEN <= MAX_RESPONSE_LEN);
+    assert!(EXTENSION_HEADER_LEN + MAX_SOURCE_RESPONSE_LEN <= MAX_RESPONSE_LEN);
+    assert!(EXTENSION_HEADER_LEN + InputRequest::Numpad.response_len() <= MAX_RESPONSE_LEN);
+    assert!(EXTENSION_HEADER_LEN + InputRequest::Keypad.response_len() <= MAX_RESPONSE_LEN);
+    assert!(EXTENSION_HEADER_LEN + InputRequest::Auxiliary.response_len() <= MAX_RESPONSE_LEN);
+    assert!(EXTENSION_HEADER_LEN + InputRequest::Analog.response_len() <= MAX_RESPONSE_LEN);
+    assert!(EXTENSION_HEADER_LEN + InputRequest::DebugFrame.response_len() <= MAX_RESPONSE_LEN);
+};
+
+/// Pack the response to a request from the main input module into `buffer`, returning the number
+/// of bytes that should be sent.
+///
+/// Every response leads with the sync byte and the length of its payload so the main input module
+/// can find a shifted response.  The payloads are answered by the shared `InputSource` logic, so
+/// unknown opcodes are answered with the single `UNKNOWN_REQUEST_RESPONSE` byte and requests the
+/// controller does not serve have an empty payload.
+pub fn handle_request(request: u8, input: &Input, buffer: &mut [u8; MAX_RESPONSE_LEN]) -> usize {
+    let mut source = *input;
+    let (header, payload) = buffer.split_at_mut(EXTENSION_HEADER_LEN);
+    let len = source.respond(request, payload);
+    let len = if request == InputRequest::FullInput as u8 {
+        // The full input is followed by its CRC-8 so the main input module can reject a corrupt one
+        payload[len] = crc8(&payload[..len]);
+        len + 1
+    } else {
+        len
+    };
+
+    header.copy_from_slice(&[EXTENSION_SYNC_BYTE, len as u8]);
+    EXTENSION_HEADER_LEN + len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use common::{input::UNKNOWN_REQUEST_RESPONSE, packing::{Pack, Unpack}, protocol::{ANALOG_LEN, AUXILIARY_LEN, KEYPAD_LEN, NUMPAD_LEN}};
+
+    #[test]
+    fn test_joystick_axes_connected() {
+        assert_eq!(joystick_axes(2048, 2048), [2048, 2048]);
+        assert_eq!(joystick_axes(0, 2048), [0, 2048]);
+        assert_eq!(joystick_axes(2048, 4095), [2048, 4095]);
+    }
+
+    #[test]
+    fn test_joystick_axes_disconnected() {
+        assert_eq!(joystick_axes(0, 0), [ANALOG_DISCONNECTED; 2]);
+        assert_eq!(joystick_axes(3, 4095), [ANALOG_DISCONNECTED; 2]);
+
+        let mut input = Input::default();
+        CONTROLLER_MAPPING.apply(&mut input, [false, false], joystick_axes(4090, 4095));
+        assert_eq!(input.analog.disconnected_mask(), 0b11);
+    }
+
+    #[test]
+    fn test_oversample() {
+        let mut samples = [2040u16, 2052, 2047, 2049].into_iter();
+        assert_eq!(oversample(4, || samples.next().unwrap()), 2047);
+
+        let mut reads = 0;
+        assert_eq!(oversample(0, || { reads += 1; 1234 }), 1234);
+        assert_eq!(reads, 1);
+    }
+
+    #[test]
+    fn test_gamepad_mapping() {
+        let mut input = Input::default();
+        GAMEPAD_MAPPING.apply(&mut input, [true, false], [2048, 1024]);
+
+        assert!(input.keypad.enter);
+        assert!(!input.keypad.backspace);
+        assert!(!input.keypad.a && !input.keypad.b);
+        assert_eq!((input.analog.a0, input.analog.a1), (2048, 1024));
+    }
+
+    #[test]
+    fn test_handle_unknown_request() {
+        let mut buffer = [0u8; MAX_RESPONSE_LEN];
+        let len = handle_request(0xEE, &Input::default(), &mut buffer);
+
+        assert_eq!(len, EXTENSION_HEADER_LEN + 1);
+        assert_eq!(buffer[..len], [EXTENSION_SYNC_BYTE, 1, UNKNOWN_REQUEST_RESPONSE]);
+    }
+
+    #[test]
+    fn test_handle_full_input_request() {
+        let mut input = Input::default();
+        input.keypad.a = true;
+        input.analog.a0 = 0x1234;
+
+        let mut buffer = [0u8; MAX_RESPONSE_LEN];
+        let len = handle_request(InputRequest::FullInput as u8, &input, &mut buffer);
+
+        let mut expected = [0u8; EXTENSION_FULL_INPUT_LEN];
+        expected[0] = EXTENSION_SYNC_BYTE;
+        expected[1] = (Input::PACKED_SIZE + 1) as u8;
+        let crc_index = EXTENSION_HEADER_LEN + Input::PACKED_SIZE;
+        input.pack(&mut expected[EXTENSION_HEADER_LEN..crc_index]).unwrap();
+        expected[crc_index] = crc8(&expected[EXTENSION_HEADER_LEN..crc_index]);
+        assert_eq!(len, EXTENSION_FULL_INPUT_LEN);
+        assert_eq!(buffer[..len], expected);
+    }
+
+    #[test]
+    fn test_handle_section_requests() {
+        let mut input = Input::from_str_keys("4k?").unwrap();
+        input.analog.a1 = 0x0321;
+
+        let sections = [
+            (InputRequest::Numpad, NUMPAD_LEN),
+            (InputRequest::Keypad, KEYPAD_LEN),
+            (InputRequest::Auxiliary, AUXILIARY_LEN),
+            (InputRequest::Analog, ANALOG_LEN),
+        ];
+        for (request, section_len) in sections {
+            let mut buffer = [0u8; MAX_RESPONSE_LEN];
+            let len = handle_request(request as u8, &input, &mut buffer);
+
+            // Each section is packed into a buffer of exactly its own size
+            let mut expected = [0u8; ANALOG_LEN];
+            match request {
+                InputRequest::Numpad => input.numpad.pack(&mut expected[..NUMPAD_LEN]).unwrap(),
+                InputRequest::Keypad => input.keypad.pack(&mut expected[..KEYPAD_LEN]).unwrap(),
+                InputRequest::Auxiliary => input.auxiliary.pack(&mut expected[..AUXILIARY_LEN]).unwrap(),
+                _ => input.analog.pack(&mut expected[..ANALOG_LEN]).unwrap(),
+            }
+            assert_eq!(len, EXTENSION_HEADER_LEN + section_len);
+            assert_eq!(buffer[..EXTENSION_HEADER_LEN], [EXTENSION_SYNC_BYTE, section_len as u8]);
+            assert_eq!(buffer[EXTENSION_HEADER_LEN..len], expected[..section_len]);
+        }
+    }
+
+    #[test]
+    fn test_handle_debug_frame_request() {
+        let mut input = Input::default();
+        input.keypad.b = true;
+
+        let mut buffer = [0u8; MAX_RESPONSE_LEN];
+        let len = handle_request(InputRequest::DebugFrame as u8, &input, &mut buffer);
+
+        assert_eq!(len, EXTENSION_HEADER_LEN + DebugFrame::PACKED_SIZE);
+        assert_eq!(buffer[..EXTENSION_HEADER_LEN], [EXTENSION_SYNC_BYTE, DebugFrame::PACKED_SIZE as u8]);
+        let frame = DebugFrame::unpack(&buffer[EXTENSION_HEADER_LEN..len]).unwrap();
+        assert!(frame.is_valid());
+        assert_eq!(Input::unpack(&frame.bytes), Ok(input));
+    }
+
+    #[test]
+    fn test_handle_unserved_request() {
+        let mut buffer = [0u8; MAX_RESPONSE_LEN];
+        let len = handle_request(InputRequest::DecodeOne as u8, &Input::default(), &mut buffer);
+        assert_eq!(buffer[..len], [EXTENSION_SYNC_BYTE, 0]);
+    }
+}
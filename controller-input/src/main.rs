@@ -17,19 +17,30 @@ use panic_probe as _;
     dispatchers = [SW0_IRQ, SW1_IRQ]
 )]
 mod app {
-    use common::{input::{Input, InputRequest}, prelude::Pack};
+    use common::{input::{mapping::InputMapping, Input}, time::{Clock, Debouncer, Heartbeat, TimerInstant}};
     use embedded_hal::spi::MODE_0;
-    use rp_pico::{hal::{self, adc::AdcPin, clocks::init_clocks_and_plls, gpio::FunctionSpi, spi::FrameFormat, timer::{Alarm, Alarm0}, Adc, Sio, Spi, Timer, Watchdog}, Pins};
+    use rp_pico::{hal::{self, adc::AdcPin, clocks::init_clocks_and_plls, gpio::FunctionSpi, spi::FrameFormat, timer::{Alarm, Alarm0, Alarm1}, Adc, Sio, Spi, Timer, Watchdog}, Pins};
     use fugit::ExtU32;
     use embedded_hal_0_2::{adc::OneShot, digital::v2::InputPin};
     use embedded_hal_nb::spi::FullDuplex;
 
-    use controller_input::{peripherals::*, READ_DELAY_US};
+    use controller_input::{handle_request, joystick_axes, oversample, peripherals::*, ANALOG_OVERSAMPLES, BUTTON_DEBOUNCE, CONTROLLER_MAPPING, HEARTBEAT_MAX_AGE, MAX_RESPONSE_LEN, READ_DELAY_US, WATCHDOG_FEED_PERIOD_US, WATCHDOG_TIMEOUT_US};
+
+    /// The hal timer as the clock for debouncing the buttons and checking the heartbeat
+    struct TimerClock(Timer);
+
+    impl Clock for TimerClock {
+        fn now(&self) -> TimerInstant {
+            self.0.get_counter()
+        }
+    }
 
     #[shared]
     struct Shared {
         // The current input state of the controller
         input: Input,
+        // Beats every input reading so the watchdog is only fed while the read loop is alive
+        heartbeat: Heartbeat,
     }
 
     #[local]
@@ -42,10 +53,26 @@ mod app {
         a: A,
         // The b button
         b: B,
+        // Where the buttons and joystick axes are reported in the input (i.e. `GAMEPAD_MAPPING`)
+        mapping: InputMapping<2, 2>,
+        // The debouncer for the a button
+        a_debouncer: Debouncer,
+        // The debouncer for the b button
+        b_debouncer: Debouncer,
+        // The clock for debouncing the buttons
+        clock: TimerClock,
         // The adc peripheral to read adc values
         adc: Adc,
+        // The number of adc reads averaged into each axis reading
+        oversamples: usize,
         // The alarm to schedule input updates
         alarm: Alarm0,
+        // The watchdog reset if the read loop gets stuck
+        watchdog: Watchdog,
+        // The alarm to schedule watchdog feeds
+        watchdog_alarm: Alarm1,
+        // The clock for checking the heartbeat
+        watchdog_clock: TimerClock,
 
         // The spi line coming into the controller input
         spi_line: SpiLine,
@@ -101,6 +128,11 @@ mod app {
         let mut timer = Timer::new(ctx.device.TIMER, &mut ctx.device.RESETS, &clocks);
         let mut alarm0 = timer.alarm_0().unwrap();
         alarm0.schedule(READ_DELAY_US.micros()).unwrap();
+        alarm0.enable_interrupt();
+        let mut alarm1 = timer.alarm_1().unwrap();
+        alarm1.schedule(WATCHDOG_FEED_PERIOD_US.micros()).unwrap();
+        alarm1.enable_interrupt();
+        watchdog.start(WATCHDOG_TIMEOUT_US.micros());
 
         hal::pac::NVIC::unpend(hal::pac::Interrupt::SPI0_IRQ);
         unsafe {
@@ -110,14 +142,23 @@ mod app {
         (
             Shared {
                 input: Input::default(),
+                heartbeat: Heartbeat::new(HEARTBEAT_MAX_AGE),
             },
             Local {
                 x,
                 y,
                 a,
                 b,
+                mapping: CONTROLLER_MAPPING,
+                a_debouncer: Debouncer::new(BUTTON_DEBOUNCE, false),
+                b_debouncer: Debouncer::new(BUTTON_DEBOUNCE, false),
+                clock: TimerClock(timer),
                 adc,
+                oversamples: ANALOG_OVERSAMPLES,
                 alarm: alarm0,
+                watchdog,
+                watchdog_alarm: alarm1,
+                watchdog_clock: TimerClock(timer),
                 spi_line: spi_slave,
                 csn,
             }
@@ -125,29 +166,41 @@ mod app {
     }
 
     #[task(
-        shared = [input],
-        local = [x, y, a, b, adc, alarm],
+        shared = [input, heartbeat],
+        local = [x, y, a, b, mapping, a_debouncer, b_debouncer, clock, adc, oversamples, alarm],
         priority = 1,
         binds = TIMER_IRQ_0
     )]
     /// Read the current inputs from the peripherals
     fn read_pins(mut ctx: read_pins::Context) {
         ctx.local.alarm.clear_interrupt();
-        let x: u16 = ctx.local.adc.read(ctx.local.x).unwrap();
-        let y: u16 = ctx.local.adc.read(ctx.local.y).unwrap();
-        let a = ctx.local.a.is_high().unwrap();
-        let b = ctx.local.b.is_high().unwrap();
-
-        ctx.shared.input.lock(|input| {
-            input.keypad.a = a;
-            input.keypad.b = b;
-            input.analog.a0 = x;
-            input.analog.a1 = y;
-        });
+        let x = oversample(*ctx.local.oversamples, || ctx.local.adc.read(ctx.local.x).unwrap());
+        let y = oversample(*ctx.local.oversamples, || ctx.local.adc.read(ctx.local.y).unwrap());
+        let a = ctx.local.a_debouncer.update(&*ctx.local.clock, ctx.local.a.is_high().unwrap());
+        let b = ctx.local.b_debouncer.update(&*ctx.local.clock, ctx.local.b.is_high().unwrap());
+
+        ctx.shared.input.lock(|input| ctx.local.mapping.apply(input, [a, b], joystick_axes(x, y)));
+        ctx.shared.heartbeat.lock(|heartbeat| heartbeat.beat(&*ctx.local.clock));
 
         ctx.local.alarm.schedule(READ_DELAY_US.micros()).unwrap();
     }
 
+    #[task(
+        shared = [heartbeat],
+        local = [watchdog, watchdog_alarm, watchdog_clock],
+        priority = 1,
+        binds = TIMER_IRQ_1
+    )]
+    /// Feed the watchdog while the read loop is alive so a stuck loop resets the module
+    fn feed_watchdog(mut ctx: feed_watchdog::Context) {
+        ctx.local.watchdog_alarm.clear_interrupt();
+        if ctx.shared.heartbeat.lock(|heartbeat| heartbeat.is_alive(&*ctx.local.watchdog_clock)) {
+            ctx.local.watchdog.feed();
+        }
+
+        ctx.local.watchdog_alarm.schedule(WATCHDOG_FEED_PERIOD_US.micros()).unwrap();
+    }
+
     #[task(
         shared = [input],
         local = [spi_line, csn],
@@ -156,46 +209,11 @@ mod app {
     )]
     /// Return the current input state of the controller
     fn relay_inputs(mut ctx: relay_inputs::Context) {
-        ctx.shared.input.lock(|input| {
-            let instruction = InputRequest::from(ctx.local.spi_line.read().unwrap());
-            match instruction {
-                InputRequest::FullInput => {
-                    let mut buffer = [0u8; 71];
-                    input.pack(&mut buffer).unwrap();
-                    for byte in buffer {
-                        ctx.local.spi_line.write(byte).unwrap();
-                    }
-                },
-                InputRequest::Numpad => {
-                    let mut buffer = [0u8; 2];
-                    input.pack(&mut buffer).unwrap();
-                    for byte in buffer {
-                        ctx.local.spi_line.write(byte).unwrap();
-                    }
-                },
-                InputRequest::Keypad => {
-                    let mut buffer = [0u8; 4];
-                    input.pack(&mut buffer).unwrap();
-                    for byte in buffer {
-                        ctx.local.spi_line.write(byte).unwrap();
-                    }
-                },
-                InputRequest::Auxiliary => {
-                    let mut buffer = [0u8; 4];
-                    input.pack(&mut buffer).unwrap();
-                    for byte in buffer {
-                        ctx.local.spi_line.write(byte).unwrap();
-                    }
-                },
-                InputRequest::Analog => {
-                    let mut buffer = [0u8; 12];
-                    input.pack(&mut buffer).unwrap();
-                    for byte in buffer {
-                        ctx.local.spi_line.write(byte).unwrap();
-                    }
-                },
-                _ => (),
-            }
-        });
+        let request = ctx.local.spi_line.read().unwrap();
+        let mut buffer = [0u8; MAX_RESPONSE_LEN];
+        let len = ctx.shared.input.lock(|input| handle_request(request, input, &mut buffer));
+        for byte in &buffer[..len] {
+            ctx.local.spi_line.write(*byte).unwrap();
+        }
     }
 }
\ No newline at end of file
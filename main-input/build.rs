@@ -12,6 +12,7 @@ use std::env;
 use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
+use std::process::Command;
 
 fn main() {
     // Put `memory.x` in our output directory and ensure it's
@@ -28,4 +29,32 @@ fn main() {
     // here, we ensure the build script is only re-run when
     // `memory.x` is changed.
     println!("cargo:rerun-if-changed=memory.x");
+
+    // Bake the git hash of the build into the firmware so it can be read back over i2c. Builds
+    // outside of a git checkout are identified as `unknown`.
+    let build_id = Command::new("git")
+        .args(["rev-parse", "--short=8", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|hash| hash.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=PIP_BOY_BUILD_ID={}", build_id);
+
+    // On a branch `HEAD` only names the branch, so a commit changes the branch ref (or the packed
+    // refs once the ref is packed) rather than `HEAD` itself
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/packed-refs");
+    let head_ref = Command::new("git")
+        .args(["rev-parse", "--symbolic-full-name", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|head_ref| head_ref.trim().to_string())
+        .filter(|head_ref| head_ref.starts_with("refs/"));
+    if let Some(head_ref) = head_ref {
+        println!("cargo:rerun-if-changed=../.git/{}", head_ref);
+    }
 }
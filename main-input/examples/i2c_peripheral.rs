@@ -14,17 +14,18 @@ use panic_probe as _;
     dispatchers = [SW0_IRQ, SW1_IRQ]
 )]
 mod app {
-    use common::{input::{Input, InputRequest}, prelude::{AnalogInputsBuilder, AuxiliaryBuilder, KeypadBuilder, NumpadBuilder, Pack}};
+    use common::{input::{Input, InputRequest, UNKNOWN_REQUEST_RESPONSE}, prelude::{AnalogInputsBuilder, AuxiliaryBuilder, KeypadBuilder, NumpadBuilder, Pack}, protocol::{ANALOG_LEN, AUXILIARY_LEN, DECODE_INSTRUCTIONS_LEN, FULL_FRAME_LEN, KEYPAD_LEN, NUMPAD_LEN, OTHER_INPUT_LEN}};
     use rp_pico::{hal::{self, clocks::init_clocks_and_plls, Sio, Watchdog, I2C}, pac::RESETS, Pins};
 
     use rtic_monotonics::rp2040_timer_monotonic;
 
     use main_input::peripherals::*;
+    use main_input::I2cAddress;
 
     rp2040_timer_monotonic!(Mono);
 
     /// The address of this device on the i2c line
-    static mut I2C_ADDRESS: u8 = 0;
+    static I2C_ADDRESS: I2cAddress = I2cAddress::new(0);
 
     #[shared]
     struct Shared {
@@ -36,9 +37,9 @@ mod app {
         /// The resets device peripheral
         resets: RESETS,
         /// The decode instructions for extension 1
-        ext1_decode_instructions: [u8; 248],
+        ext1_decode_instructions: [u8; DECODE_INSTRUCTIONS_LEN],
         /// The decode instructions for extension 2
-        ext2_decode_instructions: [u8; 248],
+        ext2_decode_instructions: [u8; DECODE_INSTRUCTIONS_LEN],
     }
 
     #[local]
@@ -106,8 +107,8 @@ mod app {
                 .a5(125)
                 .build()
                 .unwrap(),
-            other_input_one: [0u8; 24],
-            other_input_two: [0u8; 24],
+            other_input_one: [0u8; OTHER_INPUT_LEN],
+            other_input_two: [0u8; OTHER_INPUT_LEN],
         };
 
         hal::pac::NVIC::unpend(hal::pac::Interrupt::I2C1_IRQ);
@@ -120,8 +121,8 @@ mod app {
                 program_i2c: Some(program_i2c),
                 input_state,
                 resets: ctx.device.RESETS,
-                ext1_decode_instructions: [5u8; 248],
-                ext2_decode_instructions: [5u8; 248],
+                ext1_decode_instructions: [5u8; DECODE_INSTRUCTIONS_LEN],
+                ext2_decode_instructions: [5u8; DECODE_INSTRUCTIONS_LEN],
             },
             Local {
 
@@ -160,7 +161,7 @@ mod app {
                         // Start or Restart
                         let mut buffer = [0u8];
                         i2c.read(&mut buffer);
-                        instruction = Some(InputRequest::from(buffer[0]));
+                        instruction = InputRequest::try_from(buffer[0]).ok();
                         i2c
                     },
                     2 => {
@@ -168,27 +169,27 @@ mod app {
                         if let Some(instruction) = instruction {
                             match instruction {
                                 InputRequest::FullInput => {
-                                    let mut buffer = [0u8; 71];
+                                    let mut buffer = [0u8; FULL_FRAME_LEN];
                                     input.pack(&mut buffer).unwrap();
                                     i2c.write(&buffer);
                                 },
                                 InputRequest::Numpad => {
-                                    let mut buffer = [0u8; 2];
+                                    let mut buffer = [0u8; NUMPAD_LEN];
                                     input.numpad.pack(&mut buffer).unwrap();
                                     i2c.write(&buffer);
                                 },
                                 InputRequest::Keypad => {
-                                    let mut buffer = [0u8; 4];
+                                    let mut buffer = [0u8; KEYPAD_LEN];
                                     input.keypad.pack(&mut buffer).unwrap();
                                     i2c.write(&buffer);
                                 },
                                 InputRequest::Auxiliary => {
-                                    let mut buffer = [0u8; 4];
+                                    let mut buffer = [0u8; AUXILIARY_LEN];
                                     input.auxiliary.pack(&mut buffer).unwrap();
                                     i2c.write(&buffer);
                                 },
                                 InputRequest::Analog => {
-                                    let mut buffer = [0u8; 12];
+                                    let mut buffer = [0u8; ANALOG_LEN];
                                     input.analog.pack(&mut buffer).unwrap();
                                     i2c.write(&buffer);
                                 },
@@ -209,9 +210,25 @@ mod app {
                                     i2c.write(&input.other_input_two);
                                 },
                                 InputRequest::SetAddress => {
-                                    i2c.write(&[unsafe { I2C_ADDRESS }]);
+                                    i2c.write(&[I2C_ADDRESS.get()]);
                                 },
+                                InputRequest::WriteConfig |
+                                InputRequest::ReadConfig |
+                                InputRequest::RawButtons |
+                                InputRequest::OtherN |
+                                InputRequest::DecodeN |
+                                InputRequest::BuildId |
+                                InputRequest::ChangedSections |
+                                InputRequest::DebugFrame |
+                                InputRequest::AlertFrame |
+                                InputRequest::CommandLog |
+                                InputRequest::TimedAnalog |
+                                InputRequest::ReportMode |
+                                InputRequest::ExtensionStatus |
+                                InputRequest::ThresholdEvents => (),
                             }
+                        } else {
+                            i2c.write(&[UNKNOWN_REQUEST_RESPONSE]);
                         }
                         i2c
                     },
@@ -222,9 +239,9 @@ mod app {
                                 InputRequest::SetAddress => {
                                     let mut buffer = [0u8];
                                     i2c.read(&mut buffer);
-                                    unsafe { I2C_ADDRESS = buffer[0] };
+                                    I2C_ADDRESS.set(buffer[0]);
                                     let (block, pins) = i2c.free(resets);
-                                    I2C::new_peripheral_event_iterator(block, pins.0, pins.1, resets, unsafe { I2C_ADDRESS } )
+                                    I2C::new_peripheral_event_iterator(block, pins.0, pins.1, resets, I2C_ADDRESS.get())
                                 },
                                 _ => i2c,
                             }
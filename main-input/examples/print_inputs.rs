@@ -21,10 +21,19 @@ mod app {
     use rtic_monotonics::{rp2040::prelude::*, rp2040_timer_monotonic};
 
     use main_input::peripherals::*;
-    use main_input::{check_three_input, check_four_input, INPUT_UPDATE_DELAY_MS};
+    use main_input::{check_three_input, check_four_input, Clock, TimerInstant, TapDirection, INPUT_UPDATE_DELAY_MS};
 
     rp2040_timer_monotonic!(Mono);
 
+    /// The RTIC monotonic as the clock for the input timing logic
+    struct MonoClock;
+
+    impl Clock for MonoClock {
+        fn now(&self) -> TimerInstant {
+            Mono::now()
+        }
+    }
+
     #[shared]
     struct Shared {
 
@@ -175,9 +184,12 @@ mod app {
         let now = Mono::now();
 
         // Update inputs based on pressed buttons and pressed button states
-        if ctx.local.switch.is_high().unwrap() {
+        let direction = if ctx.local.switch.is_high().unwrap() {
             defmt::info!("SHIFT");
-        }
+            TapDirection::Backward
+        } else {
+            TapDirection::Forward
+        };
 
         if ctx.local.b1.is_high().unwrap() {
             defmt::info!("1");
@@ -185,7 +197,7 @@ mod app {
 
         let b2_high = ctx.local.b2.is_high().unwrap();
         if b2_high {
-            let (a, b, _c) = check_three_input(now, *ctx.local.last_b2_time, ctx.local.last_b2_click);
+            let (a, b, _c) = check_three_input(&MonoClock, *ctx.local.last_b2_time, ctx.local.last_b2_click, direction);
             defmt::info!("2 - {} - UP", if a { "A" } else if b { "B" } else { "C" });
             *ctx.local.last_b2_time = Some(now);
         } else if *ctx.local.last_b2_value {
@@ -195,7 +207,7 @@ mod app {
 
         let b3_high = ctx.local.b3.is_high().unwrap();
         if b3_high {
-            let (d, e, _f) = check_three_input(now, *ctx.local.last_b3_time, ctx.local.last_b2_click);
+            let (d, e, _f) = check_three_input(&MonoClock, *ctx.local.last_b3_time, ctx.local.last_b3_click, direction);
             defmt::info!("3 - {}", if d { "D" } else if e { "E" } else { "F" });
             *ctx.local.last_b3_time = Some(now);
         } else if *ctx.local.last_b3_value {
@@ -205,7 +217,7 @@ mod app {
 
         let b4_high = ctx.local.b4.is_high().unwrap();
         if b4_high {
-            let (g, h, _i) = check_three_input(now, *ctx.local.last_b4_time, ctx.local.last_b4_click);
+            let (g, h, _i) = check_three_input(&MonoClock, *ctx.local.last_b4_time, ctx.local.last_b4_click, direction);
             defmt::info!("4 - {} - LEFT", if g { "G" } else if h { "H" } else { "I" });
             *ctx.local.last_b4_time = Some(now);
         } else if *ctx.local.last_b4_value {
@@ -215,7 +227,7 @@ mod app {
 
         let b5_high = ctx.local.b5.is_high().unwrap();
         if b5_high {
-            let (j, k, _l) = check_three_input(now, *ctx.local.last_b5_time, ctx.local.last_b5_click);
+            let (j, k, _l) = check_three_input(&MonoClock, *ctx.local.last_b5_time, ctx.local.last_b5_click, direction);
             defmt::info!("5 - {}", if j { "J" } else if k { "K" } else { "L" });
             *ctx.local.last_b5_time = Some(now);
         } else if *ctx.local.last_b5_value {
@@ -225,7 +237,7 @@ mod app {
 
         let b6_high = ctx.local.b6.is_high().unwrap();
         if b6_high {
-            let (m, n, _o) = check_three_input(now, *ctx.local.last_b6_time, ctx.local.last_b6_click);
+            let (m, n, _o) = check_three_input(&MonoClock, *ctx.local.last_b6_time, ctx.local.last_b6_click, direction);
             defmt::info!("6 - {} - RIGHT", if m { "M" } else if n { "N" } else { "O" });
             *ctx.local.last_b6_time = Some(now);
         } else if *ctx.local.last_b6_value {
@@ -235,7 +247,7 @@ mod app {
 
         let b7_high = ctx.local.b7.is_high().unwrap();
         if b7_high {
-            let (p, q, r, _s) = check_four_input(now, *ctx.local.last_b7_time, ctx.local.last_b7_click);
+            let (p, q, r, _s) = check_four_input(&MonoClock, *ctx.local.last_b7_time, ctx.local.last_b7_click, direction);
             defmt::info!("7 - {}", if p { "P" } else if q { "Q" } else if r { "R" } else { "S" });
             *ctx.local.last_b7_time = Some(now);
         } else if *ctx.local.last_b7_value {
@@ -245,7 +257,7 @@ mod app {
 
         let b8_high = ctx.local.b8.is_high().unwrap();
         if b8_high {
-            let (t, u, _v) = check_three_input(now, *ctx.local.last_b8_time, ctx.local.last_b8_click);
+            let (t, u, _v) = check_three_input(&MonoClock, *ctx.local.last_b8_time, ctx.local.last_b8_click, direction);
             defmt::info!("8 - {} - DOWN", if t { "T" } else if u { "U" } else { "V" });
             *ctx.local.last_b8_time = Some(now);
         } else if *ctx.local.last_b8_value {
@@ -255,7 +267,7 @@ mod app {
 
         let b9_high = ctx.local.b9.is_high().unwrap();
         if b9_high {
-            let (w, x, y, _z) = check_four_input(now, *ctx.local.last_b9_time, ctx.local.last_b9_click);
+            let (w, x, y, _z) = check_four_input(&MonoClock, *ctx.local.last_b9_time, ctx.local.last_b9_click, direction);
             defmt::info!("9 - {}", if w { "W" } else if x { "X" } else if y { "Y" } else { "Z" });
             *ctx.local.last_b9_time = Some(now);
         } else {
@@ -16,9 +16,8 @@ use panic_probe as _;
 mod app {
     use core::cell::RefCell;
 
-    use common::{input::{Input, InputRequest}, prelude::Unpack};
     use critical_section::Mutex;
-    use embedded_hal::{digital::InputPin, spi::{SpiDevice, MODE_0}};
+    use embedded_hal::{digital::InputPin, spi::MODE_0};
     use rp_pico::{hal::{clocks::init_clocks_and_plls, gpio::{FunctionSpi, Interrupt}, Sio, Spi, Watchdog}, Pins};
     use fugit::{RateExtU32, ExtU32};
 
@@ -27,12 +26,10 @@ mod app {
     use embedded_hal_bus::spi::CriticalSectionDevice;
 
     use main_input::peripherals::*;
+    use main_input::{read_extension_input, EXTENSION_CHECKSUM_RETRIES, EXTENSION_SETTLE_NS, EXTENSION_SYNC_RETRIES};
 
     rp2040_timer_monotonic!(Mono);
 
-    /// Static Variable Holding Spi Bus 0.  This should only every be set and referred to in `init`. Elsewhere, use the actual spi device
-    static mut SPI_BUS: Option<SpiBus0> = None;
-
     #[shared]
     struct Shared {
         /// true if extension 1 is enabled
@@ -55,7 +52,9 @@ mod app {
 
     }
 
-    #[init]
+    // The spi bus is an init local so it lives for `'static` and can be shared by both extension
+    // devices without a `static mut`
+    #[init(local = [spi_bus: Option<SpiBus0> = None])]
     fn init(mut ctx: init::Context) -> (Shared, Local) {
         Mono::start(ctx.device.TIMER, &mut ctx.device.RESETS);
 
@@ -87,20 +86,16 @@ mod app {
             pins.gpio2.into_function::<FunctionSpi>(),
         );
 
-        let bus = Mutex::new(RefCell::new(
-            Spi::<_, _, _, 8>::new(spi_device, spi_pin_layout)
+        let bus: &'static SpiBus0 = ctx.local.spi_bus.insert(Mutex::new(RefCell::new(
+            Spi::<_, _, _, EXTENSION_FRAME_BITS>::new(spi_device, spi_pin_layout)
                 .init(&mut ctx.device.RESETS, 125_000_000u32.Hz(), 16_000_000u32.Hz(), MODE_0)
-        ));
+        )));
 
-        #[allow(static_mut_refs)]
-        unsafe { SPI_BUS.replace(bus); }
         let cs1 = pins.gpio5.into_push_pull_output();
         let cs2 = pins.gpio9.into_push_pull_output();
 
-        #[allow(static_mut_refs)]
-        let ext1_spi = CriticalSectionDevice::new_no_delay(unsafe { SPI_BUS.as_ref().unwrap() }, cs1).unwrap();
-        #[allow(static_mut_refs)]
-        let ext2_spi= CriticalSectionDevice::new_no_delay(unsafe { SPI_BUS.as_ref().unwrap() }, cs2).unwrap();
+        let ext1_spi = CriticalSectionDevice::new(bus, cs1, SettleDelay).unwrap();
+        let ext2_spi = CriticalSectionDevice::new(bus, cs2, SettleDelay).unwrap();
 
         let mut en_ext1 = pins.gpio0.into_pull_down_input();
         let ext1_enabled = en_ext1.is_high().unwrap();
@@ -170,25 +165,25 @@ mod app {
     /// Check the inputs from the spi
     async fn update_inputs(mut ctx: update_inputs::Context) {
         if ctx.shared.ext1_enabled.lock(|ext1_enabled| *ext1_enabled) {
-            let mut buffer = [0u8; 71];
-            ctx.shared.ext1_spi.lock(|spi| {
-                spi.write(&[InputRequest::FullInput as u8]).unwrap();
-                spi.transfer_in_place(&mut buffer).unwrap();
+            let input = ctx.shared.ext1_spi.lock(|spi| {
+                read_extension_input(spi, EXTENSION_SETTLE_NS, EXTENSION_CHECKSUM_RETRIES, EXTENSION_SYNC_RETRIES).unwrap()
             });
-            let input = Input::unpack(&buffer).unwrap();
-            defmt::info!("Keypad 1: {:?}", input.keypad);
+            match input {
+                Some(input) => defmt::info!("Keypad 1: {:?}", input.keypad),
+                None => defmt::warn!("EXT1 Sent Corrupt Input"),
+            }
         } else {
             defmt::info!("EXT1 Not Connected");
         }
 
         if ctx.shared.ext2_enabled.lock(|ext2_enabled| *ext2_enabled) {
-            let mut buffer = [0u8; 71];
-            ctx.shared.ext2_spi.lock(|spi| {
-                spi.write(&[InputRequest::FullInput as u8]).unwrap();
-                spi.transfer_in_place(&mut buffer).unwrap();
+            let input = ctx.shared.ext2_spi.lock(|spi| {
+                read_extension_input(spi, EXTENSION_SETTLE_NS, EXTENSION_CHECKSUM_RETRIES, EXTENSION_SYNC_RETRIES).unwrap()
             });
-            let input = Input::unpack(&buffer).unwrap();
-            defmt::info!("Keypad 2: {:?}", input.keypad);
+            match input {
+                Some(input) => defmt::info!("Keypad 2: {:?}", input.keypad),
+                None => defmt::warn!("EXT2 Sent Corrupt Input"),
+            }
         } else {
             defmt::info!("EXT2 Not Connected");
         }
@@ -0,0 +1,96 @@
+//!
+//! Data Frames of the Extension Bus
+//!
+//! The extension bus uses 8-bit frames by default.  As an advanced option for extensions optimized
+//! for wide transfers, the `wide-spi` feature switches the bus to 16-bit frames.  Requests and
+//! responses are still byte streams: each 16-bit frame carries two bytes with the first byte in
+//! the high half (so the bytes go out on the wire in the same order as with 8-bit frames) and an
+//! odd final byte is padded with zero.
+//!
+
+use common::input::MAX_TRANSFER_LEN;
+
+use crate::extension_read_len;
+
+/// The most frames in a single transfer (the longest response read with 8-bit frames)
+pub const MAX_FRAMES: usize = extension_read_len(MAX_TRANSFER_LEN);
+
+/// A data frame of the extension bus
+pub trait FrameWord: Copy + Default + 'static {
+    /// The number of bytes carried by a frame
+    const BYTES: usize;
+
+    /// The number of frames needed to carry `len` bytes
+    fn frames_for(len: usize) -> usize {
+        len.div_ceil(Self::BYTES)
+    }
+
+    /// Pack bytes into frames, returning the number of frames used
+    fn pack_frames(bytes: &[u8], frames: &mut [Self]) -> usize;
+
+    /// Unpack frames into bytes (stopping when either runs out)
+    fn unpack_frames(frames: &[Self], bytes: &mut [u8]);
+}
+
+impl FrameWord for u8 {
+    const BYTES: usize = 1;
+
+    fn pack_frames(bytes: &[u8], frames: &mut [Self]) -> usize {
+        frames[..bytes.len()].copy_from_slice(bytes);
+        bytes.len()
+    }
+
+    fn unpack_frames(frames: &[Self], bytes: &mut [u8]) {
+        let len = frames.len().min(bytes.len());
+        bytes[..len].copy_from_slice(&frames[..len]);
+    }
+}
+
+impl FrameWord for u16 {
+    const BYTES: usize = 2;
+
+    fn pack_frames(bytes: &[u8], frames: &mut [Self]) -> usize {
+        for (frame, chunk) in frames.iter_mut().zip(bytes.chunks(2)) {
+            *frame = u16::from_be_bytes([chunk[0], chunk.get(1).copied().unwrap_or(0)]);
+        }
+        Self::frames_for(bytes.len())
+    }
+
+    fn unpack_frames(frames: &[Self], bytes: &mut [u8]) {
+        for (chunk, frame) in bytes.chunks_mut(2).zip(frames) {
+            let frame_bytes = frame.to_be_bytes();
+            chunk.copy_from_slice(&frame_bytes[..chunk.len()]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_unpack_wide_frames() {
+        let bytes = [0x01, 0x23, 0x45, 0x67, 0x89];
+        let mut frames = [0u16; 4];
+
+        assert_eq!(u16::pack_frames(&bytes, &mut frames), 3);
+        assert_eq!(frames, [0x0123, 0x4567, 0x8900, 0]);
+
+        let mut unpacked = [0u8; 5];
+        u16::unpack_frames(&frames[..3], &mut unpacked);
+        assert_eq!(unpacked, bytes);
+    }
+
+    #[test]
+    fn test_pack_unpack_byte_frames() {
+        let bytes = [0x01, 0x23, 0x45];
+        let mut frames = [0u8; 4];
+
+        assert_eq!(u8::pack_frames(&bytes, &mut frames), 3);
+        assert_eq!(frames, [0x01, 0x23, 0x45, 0]);
+
+        let mut unpacked = [0u8; 3];
+        u8::unpack_frames(&frames, &mut unpacked);
+        assert_eq!(unpacked, bytes);
+    }
+}
@@ -6,58 +6,1344 @@
 
 pub mod peripherals;
 
-use fugit::{ExtU32, Instant};
+pub mod storage;
+
+pub mod scanner;
+
+pub mod frames;
+use frames::{FrameWord, MAX_FRAMES};
+
+use common::{crc::crc8, input::{BuildId, Input, InputRequest, BUILD_ID_LEN, changes::SectionChanges, source::{InputSource, MAX_SOURCE_RESPONSE_LEN}, EXTENSION_FULL_INPUT_LEN, EXTENSION_HEADER_LEN, EXTENSION_SYNC_BYTE, analog::{AnalogInputs, Calibration}, extension_status::DisableReason, config::{Config, ConfigError, ReportMode, CONFIG_LEN, MAX_I2C_ADDRESS, MAX_POLL_RATE_MS, MIN_I2C_ADDRESS}, other::DecodeInstructions, raw_buttons::RawButtons}, packing::Unpack, protocol::DECODE_INSTRUCTIONS_LEN};
+use core::sync::atomic::{AtomicU8, Ordering};
+pub use common::time::{Clock, TimerDuration, TimerInstant};
+use embedded_hal::{digital::InputPin, spi::{Operation, SpiDevice}};
+use fugit::ExtU32;
 
 /// The amount of time between updating the input state
 pub const INPUT_UPDATE_DELAY_MS: u32 = 10;
+
+/// The longest time between input updates before the update loop is considered stuck (twice the
+/// slowest poll rate)
+pub const HEARTBEAT_MAX_AGE: TimerDuration = TimerDuration::millis(2 * MAX_POLL_RATE_MS as u64);
+
+/// The time without a feed before the watchdog resets the module
+pub const WATCHDOG_TIMEOUT_MS: u32 = 3_000;
+
+/// The time between checks of the update loop heartbeat (feeding the watchdog if it is alive)
+pub const WATCHDOG_FEED_PERIOD_MS: u32 = 250;
+
 /// The amount of time between button presses to consider the press as modulating the key value
 const SEQUENCE_DELAY_MS: u32 = 500;
 
-/// From the outputs of a pin, check which of the three inputs should be selected
-pub fn check_three_input(
-    now: Instant<u64, 1, 1_000_000>,
-    last_time: Option<Instant<u64, 1, 1_000_000>>,
-    last_click: &mut u8,
-) -> (bool, bool, bool) {
-    match last_time {
-        Some(time) => {
-            if now - time < SEQUENCE_DELAY_MS.millis::<1, 1_000_000>() {
-                *last_click += 1;
-                match *last_click % 3 {
-                    0 => (true, false, false),
-                    1 => (false, true, false),
-                    _ => (false, false, true),
+/// The configuration used until a program module writes a config
+pub const DEFAULT_CONFIG: Config = Config {
+    address: 0,
+    poll_rate_ms: INPUT_UPDATE_DELAY_MS as u16,
+    calibration: [Calibration { min: 0, center: 2048, max: 4095, deadzone: 0 }; 6],
+    mode_flags: 0,
+    latch_timeout_ms: 1_000,
+    report_mode: ReportMode::Both as u8,
+    max_keys: 0,
+};
+
+/// The i2c address of the device, safe to share between `init` and the i2c interrupt.
+///
+/// The address is a single byte, so an atomic load/store (available on the cortex-m0+ without a
+/// critical section) is enough to read and replace it without a `static mut`.
+pub struct I2cAddress(AtomicU8);
+
+impl I2cAddress {
+    /// Create a new i2c address
+    pub const fn new(address: u8) -> Self {
+        Self(AtomicU8::new(address))
+    }
+
+    /// Get the current i2c address
+    pub fn get(&self) -> u8 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Replace the current i2c address
+    pub fn set(&self, address: u8) {
+        self.0.store(address, Ordering::Relaxed);
+    }
+}
+
+/// The identifier of this firmware build (the start of the git hash, set by `build.rs`)
+pub const BUILD_ID: BuildId = build_id(env!("PIP_BOY_BUILD_ID"));
+
+/// Convert a build identifier string to a fixed length build id (truncated or zero padded)
+const fn build_id(id: &str) -> BuildId {
+    let bytes = id.as_bytes();
+    let mut build_id = [0u8; BUILD_ID_LEN];
+    let mut i = 0;
+    while i < BUILD_ID_LEN && i < bytes.len() {
+        build_id[i] = bytes[i];
+        i += 1;
+    }
+    build_id
+}
+
+/// The amount of time between checking the extensions for new decode instructions
+pub const DECODE_REFRESH_DELAY_MS: u32 = 1_000;
+
+/// The amount of time between checks for a changed config to persist (so a burst of config writes
+/// rewrites the flash sector once)
+pub const CONFIG_PERSIST_DELAY_MS: u32 = 100;
+
+/// The time to wait after selecting an extension before clocking data (in nanoseconds).  Raise
+/// this for extensions that need time to wake up after chip-select is asserted.
+pub const EXTENSION_SETTLE_NS: u32 = 0;
+
+/// Send a request to an extension and read its response, waiting `settle_ns` after chip-select is
+/// asserted for each transfer (no delay is inserted when `settle_ns` is 0).
+///
+/// The request and response are packed into the bus's data frames (see `frames`).
+pub fn extension_request<W: FrameWord, S: SpiDevice<W>>(
+    spi: &mut S,
+    settle_ns: u32,
+    request: &[u8],
+    response: &mut [u8],
+) -> Result<(), S::Error> {
+    let mut request_frames = [W::default(); MAX_FRAMES];
+    let request_len = W::pack_frames(request, &mut request_frames);
+    let request_frames = &request_frames[..request_len];
+    let mut response_frames = [W::default(); MAX_FRAMES];
+    let response_frames = &mut response_frames[..W::frames_for(response.len())];
+
+    if settle_ns == 0 {
+        spi.write(request_frames)?;
+        spi.transfer_in_place(response_frames)?;
+    } else {
+        spi.transaction(&mut [Operation::DelayNs(settle_ns), Operation::Write(request_frames)])?;
+        spi.transaction(&mut [Operation::DelayNs(settle_ns), Operation::TransferInPlace(response_frames)])?;
+    }
+
+    W::unpack_frames(response_frames, response);
+    Ok(())
+}
+
+/// Check if freshly fetched decode instructions differ from the cached instructions, meaning the
+/// extension was reconfigured and the cache must be replaced.  A cache that cannot be unpacked
+/// is always replaced.
+pub fn decode_instructions_changed(cached: &[u8; DECODE_INSTRUCTIONS_LEN], fetched: &[u8; DECODE_INSTRUCTIONS_LEN]) -> bool {
+    match (DecodeInstructions::unpack(cached), DecodeInstructions::unpack(fetched)) {
+        (Ok(cached), Ok(fetched)) => cached.differs_semantically(&fetched),
+        (Err(_), _) => true,
+        (Ok(_), Err(_)) => false,
+    }
+}
+
+/// The number of times a corrupt full input from an extension is re-read before the extension is
+/// disabled
+pub const EXTENSION_CHECKSUM_RETRIES: u8 = 2;
+
+/// The number of times a full input shifted past the resync slack (so its payload was cut off) or
+/// missing its header is re-read before the extension is disabled
+pub const EXTENSION_SYNC_RETRIES: u8 = 3;
+
+/// The number of bytes read past each extension response, so a response shifted by up to this many
+/// bytes (the extension was not ready or the transfer started mid-byte) is still read whole
+pub const EXTENSION_RESYNC_SLACK: usize = 2;
+
+/// The number of bytes read for an extension response with a `payload_len` byte payload
+pub const fn extension_read_len(payload_len: usize) -> usize {
+    EXTENSION_HEADER_LEN + payload_len + EXTENSION_RESYNC_SLACK
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// The framing of a response from an extension
+pub enum ExtensionFraming {
+    /// The header starts this many bytes into the response and the whole payload follows it
+    Synced(usize),
+    /// The header starts this many bytes into the response, too late for the whole payload to
+    /// follow it (the response must be re-read)
+    Truncated(usize),
+    /// The response has no header for the payload at all (the extension is not answering or does
+    /// not serve the request)
+    Missing,
+}
+
+/// Scan a response for the header of a `payload_len` byte payload.  A stale byte may happen to
+/// equal the sync byte, so a header is only accepted if its length byte matches the payload.
+pub fn extension_framing(response: &[u8], payload_len: usize) -> ExtensionFraming {
+    let header = [EXTENSION_SYNC_BYTE, payload_len as u8];
+    match response.windows(EXTENSION_HEADER_LEN).position(|bytes| bytes == header) {
+        Some(offset) if offset + EXTENSION_HEADER_LEN + payload_len <= response.len() => ExtensionFraming::Synced(offset),
+        Some(offset) => ExtensionFraming::Truncated(offset),
+        None => ExtensionFraming::Missing,
+    }
+}
+
+/// Request a response from an extension and copy its payload into `payload` (only written if the
+/// response is `Synced`), scanning past any bytes the response was shifted by
+pub fn extension_payload<W: FrameWord, S: SpiDevice<W>>(
+    spi: &mut S,
+    settle_ns: u32,
+    request: &[u8],
+    payload: &mut [u8],
+) -> Result<ExtensionFraming, S::Error> {
+    let mut buffer = [0u8; MAX_FRAMES];
+    let response = &mut buffer[..extension_read_len(payload.len())];
+    extension_request(spi, settle_ns, request, response)?;
+
+    let framing = extension_framing(response, payload.len());
+    if let ExtensionFraming::Synced(offset) = framing {
+        let start = offset + EXTENSION_HEADER_LEN;
+        payload.copy_from_slice(&response[start..start + payload.len()]);
+    }
+    Ok(framing)
+}
+
+/// Check the CRC-8 an extension appends to its full input (the payload of a full input response)
+pub fn extension_response_valid(payload: &[u8; EXTENSION_FULL_INPUT_LEN - EXTENSION_HEADER_LEN]) -> bool {
+    crc8(&payload[..Input::PACKED_SIZE]) == payload[Input::PACKED_SIZE]
+}
+
+/// The number of times the decode instructions of a newly connected extension are fetched before
+/// giving up (the periodic refresh still picks them up if the extension recovers later)
+pub const DECODE_FETCH_ATTEMPTS: u8 = 5;
+
+/// The delay before re-fetching invalid decode instructions (doubled after every attempt)
+pub const DECODE_FETCH_BACKOFF_MS: u32 = 10;
+
+/// Read the full input of an extension, re-reading a response shifted past the resync slack (or
+/// missing its header) up to `sync_retries` times and a corrupt response up to `checksum_retries`
+/// times.
+///
+/// Returns why the extension should be disabled rather than trusted if the transfer failed or the
+/// retries ran out.
+pub fn read_extension_input<W: FrameWord, S: SpiDevice<W>>(
+    spi: &mut S,
+    settle_ns: u32,
+    checksum_retries: u8,
+    sync_retries: u8,
+) -> Result<Input, DisableReason> {
+    let mut checksum_attempts = 0;
+    let mut sync_attempts = 0;
+    loop {
+        let mut payload = [0u8; EXTENSION_FULL_INPUT_LEN - EXTENSION_HEADER_LEN];
+        let framing = extension_payload(spi, settle_ns, &[InputRequest::FullInput as u8], &mut payload)
+            .map_err(|_| DisableReason::SpiError)?;
+        match framing {
+            ExtensionFraming::Synced(_) if extension_response_valid(&payload) => {
+                return Ok(Input::unpack(&payload[..Input::PACKED_SIZE]).unwrap());
+            },
+            ExtensionFraming::Synced(_) => {
+                checksum_attempts += 1;
+                if checksum_attempts > checksum_retries {
+                    return Err(DisableReason::ChecksumErrors);
                 }
-            } else {
-                *last_click = 0;
-                (true, false, false)
-            }
+            },
+            ExtensionFraming::Truncated(_) | ExtensionFraming::Missing => {
+                sync_attempts += 1;
+                if sync_attempts > sync_retries {
+                    return Err(DisableReason::Timeout);
+                }
+            },
+        }
+    }
+}
+
+/// Move the input of the second extension to its slots (its analog channels a0-a2 to a3-a5 and its
+/// first other input to the second) so it can be merged into the first extension's input
+pub fn second_extension_input(input: Input) -> Input {
+    Input {
+        analog: AnalogInputs {
+            a3: input.analog.a0,
+            a4: input.analog.a1,
+            a5: input.analog.a2,
+            ..Default::default()
         },
-        None => (true, false, false),
+        other_input_one: [0u8; 24],
+        other_input_two: input.other_input_one,
+        ..input
+    }
+}
+
+/// The reason an extension is disabled after its enable line changes (`None` once it is plugged in)
+pub fn connection_disable_reason(connected: bool) -> Option<DisableReason> {
+    (!connected).then_some(DisableReason::Unplugged)
+}
+
+/// Record the level of an extension's enable line, returning whether the extension is now connected
+/// if its connection changed (`None` while it is unchanged)
+pub fn update_connection(enabled: &mut bool, connected: bool) -> Option<bool> {
+    if *enabled == connected {
+        return None;
+    }
+    *enabled = connected;
+    Some(connected)
+}
+
+/// Check if fetched decode instructions are usable.  An extension that has not finished booting
+/// answers with all zeros, which is never valid.
+pub fn decode_instructions_valid(fetched: &[u8; DECODE_INSTRUCTIONS_LEN]) -> bool {
+    fetched.iter().any(|byte| *byte != 0) && DecodeInstructions::unpack(fetched).is_ok()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// What to do after fetching the decode instructions of a newly connected extension
+pub enum DecodeFetch {
+    /// The decode instructions are valid and can be cached
+    Valid,
+    /// The decode instructions are invalid and should be fetched again after the delay (in milliseconds)
+    RetryAfterMs(u32),
+    /// Every attempt returned invalid decode instructions so the extension's decode instructions
+    /// are unavailable
+    Unavailable,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// Retry policy (with exponential backoff) for fetching the decode instructions of a newly
+/// connected extension
+pub struct DecodeFetchRetry {
+    /// The number of fetches checked so far
+    attempts: u8,
+}
+
+impl DecodeFetchRetry {
+    /// Start a new series of fetches
+    pub const fn new() -> Self {
+        Self { attempts: 0 }
+    }
+
+    /// Check the result of a fetch and decide what to do next
+    pub fn check(&mut self, fetched: &[u8; DECODE_INSTRUCTIONS_LEN]) -> DecodeFetch {
+        self.attempts += 1;
+        if decode_instructions_valid(fetched) {
+            DecodeFetch::Valid
+        } else if self.attempts >= DECODE_FETCH_ATTEMPTS {
+            DecodeFetch::Unavailable
+        } else {
+            DecodeFetch::RetryAfterMs(DECODE_FETCH_BACKOFF_MS << (self.attempts - 1))
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// Serves the sections of the input from a latched frame so a program reading several sections
+/// with separate requests (i.e. the numpad, then the analog inputs) never mixes two frames.
+///
+/// The guarantee: sections read one after another come from the same frame as long as no section
+/// is read twice.  Reading a section that was already served from the latched frame (or a full
+/// input, which is consistent by itself) latches the current frame.
+pub struct FrameSnapshot {
+    /// The latched frame (`None` until the first read)
+    frame: Option<Input>,
+    /// The sections (see `SectionChanges`) served from the latched frame
+    served: u8,
+}
+
+impl FrameSnapshot {
+    /// Create a new snapshot with no latched frame
+    pub const fn new() -> Self {
+        Self {
+            frame: None,
+            served: 0,
+        }
+    }
+
+    /// Get the frame to serve a section (i.e. `SectionChanges::NUMPAD`) from
+    pub fn section(&mut self, section: u8, current: &Input) -> Input {
+        let frame = match self.frame {
+            Some(frame) if self.served & section == 0 => frame,
+            _ => {
+                self.served = 0;
+                *self.frame.insert(*current)
+            },
+        };
+        self.served |= section;
+        frame
+    }
+
+    /// Get the frame to serve a full input from (any section read after it latches a new frame)
+    pub fn full(&mut self, current: &Input) -> Input {
+        self.frame = Some(*current);
+        self.served = u8::MAX;
+        *current
+    }
+}
+
+/// Serves the input requests from a frame snapshot, so the sections a program reads belong to the
+/// same frame (requests without a section are served from the current input)
+pub struct SnapshotSource<'a> {
+    /// The snapshot the sections are served from
+    pub snapshot: &'a mut FrameSnapshot,
+    /// The current input
+    pub current: &'a Input,
+}
+
+impl InputSource for SnapshotSource<'_> {
+    fn frame(&mut self, request: InputRequest) -> Input {
+        match request {
+            InputRequest::FullInput => self.snapshot.full(self.current),
+            InputRequest::Numpad => self.snapshot.section(SectionChanges::NUMPAD, self.current),
+            InputRequest::Keypad => self.snapshot.section(SectionChanges::KEYPAD, self.current),
+            InputRequest::Auxiliary => self.snapshot.section(SectionChanges::AUXILIARY, self.current),
+            InputRequest::Analog => self.snapshot.section(SectionChanges::ANALOG, self.current),
+            _ => *self.current,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// The response to an input request packed ahead of the i2c write.
+///
+/// The i2c interrupt copies the shared input out of its lock and packs the response from the copy,
+/// so the input update task is only blocked for the copy rather than for the packing and the write.
+pub struct PackedResponse {
+    /// The packed response
+    bytes: [u8; MAX_SOURCE_RESPONSE_LEN],
+    /// The number of bytes of the response
+    len: usize,
+}
+
+impl PackedResponse {
+    /// Pack the response to a request from a source (see `InputSource::respond`)
+    pub fn new<S: InputSource>(source: &mut S, request: InputRequest) -> Self {
+        let mut bytes = [0u8; MAX_SOURCE_RESPONSE_LEN];
+        let len = source.respond(request as u8, &mut bytes);
+        Self { bytes, len }
+    }
+
+    /// The bytes to write
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// Latches pressed buttons until the program reads the input (see `MODE_LATCH_KEYS`), clearing
+/// latched buttons that go unread for the latch timeout so a program that crashed or stopped
+/// polling never leaves a phantom press.  Without a timeout it accumulates every press until the
+/// next read (see `MODE_ACCUMULATE`).
+pub struct KeyLatch {
+    /// The latched buttons (numpad, keypad and auxiliary only)
+    latched: Input,
+    /// The time the first unread button was latched (`None` while nothing is latched)
+    latched_since: Option<TimerInstant>,
+}
+
+impl KeyLatch {
+    /// Age out unread buttons older than `timeout` (if any), then latch the buttons pressed in
+    /// `input` and report every latched button in `input`
+    pub fn update<C: Clock>(&mut self, clock: &C, input: &mut Input, timeout: Option<TimerDuration>) {
+        let now = clock.now();
+        let expired = self.latched_since.zip(timeout).is_some_and(|(since, timeout)| now - since >= timeout);
+        if expired {
+            self.read();
+        }
+
+        if !input.is_empty() && self.latched_since.is_none() {
+            self.latched_since = Some(now);
+        }
+        self.latched.numpad = or_keys(self.latched.numpad.into(), input.numpad.into()).into();
+        self.latched.keypad = or_keys(self.latched.keypad.into(), input.keypad.into()).into();
+        self.latched.auxiliary = or_keys(self.latched.auxiliary.into(), input.auxiliary.into()).into();
+
+        input.numpad = self.latched.numpad;
+        input.keypad = self.latched.keypad;
+        input.auxiliary = self.latched.auxiliary;
+    }
+
+    /// Clear the latched buttons once the program reads the input
+    pub fn read(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Combine the levels of two sets of buttons (pressed in either)
+fn or_keys<const N: usize>(a: [bool; N], b: [bool; N]) -> [bool; N] {
+    core::array::from_fn(|i| a[i] || b[i])
+}
+
+/// Check a `SetAddress` request against the current address.
+///
+/// Returns the address to switch to once the transaction stops, `None` if the module already
+/// uses the requested address (so repeated requests never reconfigure the peripheral twice), or
+/// `ConfigError::InvalidAddress` if the address is reserved.
+pub fn check_set_address(current: u8, requested: u8) -> Result<Option<u8>, ConfigError> {
+    if !(MIN_I2C_ADDRESS..=MAX_I2C_ADDRESS).contains(&requested) {
+        return Err(ConfigError::InvalidAddress);
+    }
+
+    Ok((requested != current).then_some(requested))
+}
+
+/// Switch the config to a report mode, returning whether the config changed (so writing the mode
+/// the module already uses never rewrites the flash)
+pub fn update_report_mode(config: &mut Config, mode: ReportMode) -> bool {
+    if config.report_mode == mode as u8 {
+        return false;
+    }
+    config.report_mode = mode as u8;
+    true
+}
+
+/// Unpack a config stored before the layout version (see `CONFIG_LAYOUT_VERSION`) was added.
+///
+/// Those configs end after the calibration or after any of the fields added later (the latch
+/// timeout, report mode and key limit), with the rest of the page left erased, so every erased
+/// field is taken from `DEFAULT_CONFIG` instead of discarding the config (and the provisioned
+/// address with it).
+pub fn migrate_legacy_config(stored: &[u8; CONFIG_LEN]) -> Option<Config> {
+    let mut config = Config::unpack(stored).ok()?;
+    if stored[52..54] == [0xFF, 0xFF] {
+        config.latch_timeout_ms = DEFAULT_CONFIG.latch_timeout_ms;
+    }
+    if stored[54] == 0xFF {
+        config.report_mode = DEFAULT_CONFIG.report_mode;
+    }
+    if stored[55] == 0xFF {
+        config.max_keys = DEFAULT_CONFIG.max_keys;
     }
+
+    config.validate().ok()?;
+    Some(config)
+}
+
+/// Drop the lowest-priority keys of a frame holding more than `max_keys` keys (0 for no limit),
+/// returning whether any key was dropped.
+///
+/// The keys are prioritized in field order: the numpad (0-9), then enter, backspace and the letters
+/// (a-z), then the auxiliary characters, so the first `max_keys` pressed keys are kept.  Shift is a
+/// modifier (like the modifier byte of a HID report) so it never counts toward the limit.
+pub fn limit_keys(input: &mut Input, max_keys: u8) -> bool {
+    let mut kept = 0;
+    let mut dropped = false;
+    let mut limit = |pressed: &mut bool| {
+        if !*pressed || max_keys == 0 {
+            return;
+        }
+        if kept < max_keys {
+            kept += 1;
+        } else {
+            *pressed = false;
+            dropped = true;
+        }
+    };
+
+    for digit in 0..10 {
+        // Every digit 0-9 has a button
+        limit(input.numpad.digit_mut(digit).unwrap());
+    }
+    limit(&mut input.keypad.enter);
+    limit(&mut input.keypad.backspace);
+    for letter in 'a'..='z' {
+        limit(input.keypad.letter_mut(letter).unwrap());
+    }
+    for (label, _) in input.auxiliary.labeled_keys() {
+        // Every auxiliary label is the single character of its button
+        limit(input.auxiliary.char_mut(label.chars().next().unwrap()).unwrap());
+    }
+
+    dropped
+}
+
+/// Read the raw level of every physical button.
+///
+/// The pins are ordered 0-9, back, front, switch.
+pub fn read_raw_buttons<E: embedded_hal::digital::Error>(pins: [&mut dyn InputPin<Error = E>; 13]) -> RawButtons {
+    let [zero, one, two, three, four, five, six, seven, eight, nine, back, front, switch] =
+        pins.map(|pin| pin.is_high().unwrap());
+
+    RawButtons { zero, one, two, three, four, five, six, seven, eight, nine, back, front, switch }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+/// The direction repeated presses of a button cycle through its options
+pub enum TapDirection {
+    /// Cycle through the options in order (i.e. a, b, c)
+    #[default]
+    Forward,
+    /// Cycle through the options in reverse (i.e. c, b, a)
+    Backward,
+}
+
+/// Advance the click counter of a button with `options` options, returning the selected option
+fn next_click(
+    now: TimerInstant,
+    last_time: Option<TimerInstant>,
+    last_click: &mut u8,
+    options: u8,
+    direction: TapDirection,
+) -> u8 {
+    let in_sequence = match last_time {
+        Some(time) => now - time < SEQUENCE_DELAY_MS.millis::<1, 1_000_000>(),
+        None => false,
+    };
+
+    *last_click = match (in_sequence, direction) {
+        (true, TapDirection::Forward) => (*last_click % options + 1) % options,
+        (true, TapDirection::Backward) => (*last_click % options + options - 1) % options,
+        (false, TapDirection::Forward) => 0,
+        (false, TapDirection::Backward) => options - 1,
+    };
+    *last_click
+}
+
+/// From the outputs of a pin, check which of the `N` inputs should be selected, returning its index
+/// (always less than `N`).
+///
+/// A button must have between 2 and 255 options (checked at compile time), a single option would
+/// never cycle and the click counter is a `u8`.
+pub fn check_n_input<const N: usize, C: Clock>(
+    clock: &C,
+    last_time: Option<TimerInstant>,
+    last_click: &mut u8,
+    direction: TapDirection,
+) -> usize {
+    const { assert!(N >= 2 && N <= u8::MAX as usize, "a multi-tap button has 2 to 255 options") };
+
+    next_click(clock.now(), last_time, last_click, N as u8, direction) as usize
+}
+
+/// From the outputs of a pin, check which of the three inputs should be selected
+pub fn check_three_input<C: Clock>(
+    clock: &C,
+    last_time: Option<TimerInstant>,
+    last_click: &mut u8,
+    direction: TapDirection,
+) -> (bool, bool, bool) {
+    let selected = check_n_input::<3, _>(clock, last_time, last_click, direction);
+    (selected == 0, selected == 1, selected == 2)
 }
 
 /// From the outputs of a pin, check which of the four inputs should be selected
-pub fn check_four_input(
-    now: Instant<u64, 1, 1_000_000>,
-    last_time: Option<Instant<u64, 1, 1_000_000>>,
+pub fn check_four_input<C: Clock>(
+    clock: &C,
+    last_time: Option<TimerInstant>,
     last_click: &mut u8,
+    direction: TapDirection,
 ) -> (bool, bool, bool, bool) {
-    match last_time {
-        Some(time) => {
-            if now - time < SEQUENCE_DELAY_MS.millis::<1, 1_000_000>() {
-                *last_click += 1;
-                match *last_click % 4 {
-                    0 => (true, false, false, false),
-                    1 => (false, true, false, false),
-                    2 => (false, false, true, false),
-                    _ => (false, false, false, true),
-                }
-            } else {
-                *last_click = 0;
-                (true, false, false, false)
+    let selected = check_n_input::<4, _>(clock, last_time, last_click, direction);
+    (selected == 0, selected == 1, selected == 2, selected == 3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::{cell::Cell, convert::Infallible};
+    use fugit::ExtU64;
+    use common::{input::{keypad::Keypad, numpad::Numpad, other::ModuleId}, packing::Pack, protocol::{KEYPAD_LEN, NUMPAD_LEN}};
+    use embedded_hal::digital::ErrorType;
+    use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction};
+
+    extern crate std;
+    use std::vec;
+
+    /// A pin fixed at a level
+    struct Level(bool);
+
+    impl ErrorType for Level {
+        type Error = Infallible;
+    }
+
+    impl InputPin for Level {
+        fn is_high(&mut self) -> Result<bool, Self::Error> {
+            Ok(self.0)
+        }
+
+        fn is_low(&mut self) -> Result<bool, Self::Error> {
+            Ok(!self.0)
+        }
+    }
+
+    /// A clock that only moves when advanced
+    struct FakeClock(Cell<TimerInstant>);
+
+    impl FakeClock {
+        fn at_ms(ms: u64) -> Self {
+            Self(Cell::new(at_ms(ms)))
+        }
+
+        fn advance_ms(&self, ms: u64) {
+            self.0.set(self.0.get() + ms.millis());
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> TimerInstant {
+            self.0.get()
+        }
+    }
+
+    fn at_ms(ms: u64) -> TimerInstant {
+        TimerInstant::from_ticks(ms * 1_000)
+    }
+
+    #[test]
+    fn test_check_three_input_forward() {
+        let mut last_click = 0;
+        let mut last_time = None;
+        let mut selected = [(false, false, false); 4];
+        for (i, selection) in selected.iter_mut().enumerate() {
+            let clock = FakeClock::at_ms(100 * i as u64);
+            *selection = check_three_input(&clock, last_time, &mut last_click, TapDirection::Forward);
+            last_time = Some(clock.now());
+        }
+
+        assert_eq!(selected, [
+            (true, false, false),
+            (false, true, false),
+            (false, false, true),
+            (true, false, false),
+        ]);
+    }
+
+    #[test]
+    fn test_check_three_input_backward() {
+        let mut last_click = 0;
+        let mut last_time = None;
+        let mut selected = [(false, false, false); 4];
+        for (i, selection) in selected.iter_mut().enumerate() {
+            let clock = FakeClock::at_ms(100 * i as u64);
+            *selection = check_three_input(&clock, last_time, &mut last_click, TapDirection::Backward);
+            last_time = Some(clock.now());
+        }
+
+        assert_eq!(selected, [
+            (false, false, true),
+            (false, true, false),
+            (true, false, false),
+            (false, false, true),
+        ]);
+    }
+
+    #[test]
+    fn test_check_four_input_forward_then_backward() {
+        let mut last_click = 0;
+        let forward = TapDirection::Forward;
+        let backward = TapDirection::Backward;
+
+        assert_eq!(check_four_input(&FakeClock::at_ms(0), None, &mut last_click, forward), (true, false, false, false));
+        assert_eq!(check_four_input(&FakeClock::at_ms(100), Some(at_ms(0)), &mut last_click, forward), (false, true, false, false));
+        assert_eq!(check_four_input(&FakeClock::at_ms(200), Some(at_ms(100)), &mut last_click, forward), (false, false, true, false));
+        assert_eq!(check_four_input(&FakeClock::at_ms(300), Some(at_ms(200)), &mut last_click, backward), (false, true, false, false));
+        assert_eq!(check_four_input(&FakeClock::at_ms(400), Some(at_ms(300)), &mut last_click, backward), (true, false, false, false));
+        assert_eq!(check_four_input(&FakeClock::at_ms(500), Some(at_ms(400)), &mut last_click, backward), (false, false, false, true));
+    }
+
+    #[test]
+    fn test_check_three_input_sequence_timeout() {
+        let mut last_click = 0;
+        check_three_input(&FakeClock::at_ms(0), None, &mut last_click, TapDirection::Forward);
+        check_three_input(&FakeClock::at_ms(100), Some(at_ms(0)), &mut last_click, TapDirection::Forward);
+
+        let selected = check_three_input(&FakeClock::at_ms(1_000), Some(at_ms(100)), &mut last_click, TapDirection::Forward);
+        assert_eq!(selected, (true, false, false));
+    }
+
+    #[test]
+    fn test_check_input_independent_click_counters() {
+        let mut last_b2_click = 0;
+        let mut last_b3_click = 0;
+        let mut last_b9_click = 0;
+        let forward = TapDirection::Forward;
+
+        // Tap 2 twice, then 3 and 9 once each while 2's sequence is still running
+        assert_eq!(check_three_input(&FakeClock::at_ms(0), None, &mut last_b2_click, forward), (true, false, false));
+        assert_eq!(check_three_input(&FakeClock::at_ms(100), Some(at_ms(0)), &mut last_b2_click, forward), (false, true, false));
+        assert_eq!(check_three_input(&FakeClock::at_ms(200), None, &mut last_b3_click, forward), (true, false, false));
+        assert_eq!(check_four_input(&FakeClock::at_ms(250), None, &mut last_b9_click, forward), (true, false, false, false));
+
+        // Each button continues its own sequence
+        assert_eq!(check_three_input(&FakeClock::at_ms(300), Some(at_ms(200)), &mut last_b3_click, forward), (false, true, false));
+        assert_eq!(check_three_input(&FakeClock::at_ms(400), Some(at_ms(100)), &mut last_b2_click, forward), (false, false, true));
+        assert_eq!(check_four_input(&FakeClock::at_ms(450), Some(at_ms(250)), &mut last_b9_click, forward), (false, true, false, false));
+        assert_eq!((last_b2_click, last_b3_click, last_b9_click), (2, 1, 1));
+    }
+
+    #[test]
+    fn test_i2c_address_get_set() {
+        static ADDRESS: I2cAddress = I2cAddress::new(0x42);
+        assert_eq!(ADDRESS.get(), 0x42);
+
+        ADDRESS.set(0x24);
+        assert_eq!(ADDRESS.get(), 0x24);
+    }
+
+    #[test]
+    fn test_build_id_truncated_or_padded() {
+        assert_eq!(build_id("0123456789abcdef"), *b"01234567");
+        assert_eq!(build_id("unknown"), *b"unknown\0");
+    }
+
+    #[test]
+    fn test_decode_instructions_changed_module_id() {
+        let decode_instructions = DecodeInstructions::default();
+        let mut cached = [0u8; DECODE_INSTRUCTIONS_LEN];
+        decode_instructions.pack(&mut cached).unwrap();
+
+        let mut fetched = [0u8; DECODE_INSTRUCTIONS_LEN];
+        decode_instructions.pack(&mut fetched).unwrap();
+        assert!(!decode_instructions_changed(&cached, &fetched));
+
+        DecodeInstructions { module_id: ModuleId::new(0x0042), ..decode_instructions }.pack(&mut fetched).unwrap();
+        assert!(decode_instructions_changed(&cached, &fetched));
+    }
+
+    #[test]
+    fn test_decode_fetch_retry_until_valid() {
+        let mut valid = [0u8; DECODE_INSTRUCTIONS_LEN];
+        DecodeInstructions { module_id: ModuleId::new(0x0042), ..DecodeInstructions::default() }.pack(&mut valid).unwrap();
+
+        let mut retry = DecodeFetchRetry::new();
+        assert_eq!(retry.check(&[0u8; DECODE_INSTRUCTIONS_LEN]), DecodeFetch::RetryAfterMs(DECODE_FETCH_BACKOFF_MS));
+        assert_eq!(retry.check(&[0u8; DECODE_INSTRUCTIONS_LEN]), DecodeFetch::RetryAfterMs(DECODE_FETCH_BACKOFF_MS * 2));
+        assert_eq!(retry.check(&valid), DecodeFetch::Valid);
+    }
+
+    #[test]
+    fn test_decode_fetch_retry_unavailable() {
+        let mut retry = DecodeFetchRetry::new();
+        for _ in 1..DECODE_FETCH_ATTEMPTS {
+            assert!(matches!(retry.check(&[0u8; DECODE_INSTRUCTIONS_LEN]), DecodeFetch::RetryAfterMs(_)));
+        }
+        assert_eq!(retry.check(&[0u8; DECODE_INSTRUCTIONS_LEN]), DecodeFetch::Unavailable);
+    }
+
+    #[test]
+    fn test_read_raw_buttons() {
+        let mut levels = [false, true, false, false, true, false, false, false, false, true, true, false, true].map(Level);
+        let [b0, b1, b2, b3, b4, b5, b6, b7, b8, b9, bback, bfront, switch] = &mut levels;
+
+        let raw_buttons = read_raw_buttons([b0, b1, b2, b3, b4, b5, b6, b7, b8, b9, bback, bfront, switch]);
+        assert_eq!(raw_buttons, RawButtons {
+            one: true,
+            four: true,
+            nine: true,
+            back: true,
+            switch: true,
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_frame_snapshot_consistent_sections() {
+        let first = Input::from_str_keys("1a").unwrap();
+        let mut second = Input::from_str_keys("2b").unwrap();
+        second.analog.a0 = 500;
+
+        // The input updates between the numpad and analog reads
+        let mut snapshot = FrameSnapshot::new();
+        assert_eq!(snapshot.section(SectionChanges::NUMPAD, &first), first);
+        assert_eq!(snapshot.section(SectionChanges::ANALOG, &second), first);
+        assert_eq!(snapshot.section(SectionChanges::KEYPAD, &second), first);
+
+        // Reading the numpad again starts the next frame
+        assert_eq!(snapshot.section(SectionChanges::NUMPAD, &second), second);
+        assert_eq!(snapshot.section(SectionChanges::ANALOG, &first), second);
+    }
+
+    #[test]
+    fn test_frame_snapshot_full_input() {
+        let first = Input::from_str_keys("1a").unwrap();
+        let second = Input::from_str_keys("2b").unwrap();
+
+        let mut snapshot = FrameSnapshot::new();
+        assert_eq!(snapshot.section(SectionChanges::KEYPAD, &first), first);
+        assert_eq!(snapshot.full(&second), second);
+        assert_eq!(snapshot.section(SectionChanges::KEYPAD, &first), first);
+    }
+
+    #[test]
+    fn test_snapshot_source() {
+        let first = Input::from_str_keys("1a").unwrap();
+        let second = Input::from_str_keys("2b").unwrap();
+        let mut snapshot = FrameSnapshot::new();
+        let mut buffer = [0u8; MAX_SOURCE_RESPONSE_LEN];
+
+        let mut source = SnapshotSource { snapshot: &mut snapshot, current: &first };
+        assert_eq!(source.respond(InputRequest::Numpad as u8, &mut buffer), NUMPAD_LEN);
+        assert_eq!(Numpad::unpack(&buffer), Ok(first.numpad));
+
+        // The keypad is still served from the first frame
+        let mut source = SnapshotSource { snapshot: &mut snapshot, current: &second };
+        assert_eq!(source.respond(InputRequest::Keypad as u8, &mut buffer), KEYPAD_LEN);
+        assert_eq!(Keypad::unpack(&buffer), Ok(first.keypad));
+    }
+
+    #[test]
+    fn test_packed_response_matches_in_place_packing() {
+        let mut shared = Input::from_str_keys("4gS").unwrap();
+        shared.analog.a2 = 0x0ABC;
+        let copy = shared;
+        let requests = [
+            InputRequest::Numpad,
+            InputRequest::Analog,
+            InputRequest::Numpad,
+            InputRequest::FullInput,
+            InputRequest::Keypad,
+            InputRequest::Auxiliary,
+            InputRequest::DebugFrame,
+        ];
+
+        let mut in_place = FrameSnapshot::new();
+        let mut ahead = FrameSnapshot::new();
+        for request in requests {
+            let mut buffer = [0u8; MAX_SOURCE_RESPONSE_LEN];
+            let len = SnapshotSource { snapshot: &mut in_place, current: &shared }.respond(request as u8, &mut buffer);
+            let packed = PackedResponse::new(&mut SnapshotSource { snapshot: &mut ahead, current: &copy }, request);
+
+            assert_eq!(packed.as_bytes(), &buffer[..len]);
+        }
+        assert_eq!(in_place, ahead);
+    }
+
+    #[test]
+    fn test_limit_keys_trims_and_flags() {
+        // Eight keys plus shift, limited to the six highest-priority keys (dropping the auxiliary)
+        let mut input = Input::from_str_keys("93Zab#!").unwrap();
+        input.keypad.enter = true;
+
+        assert!(limit_keys(&mut input, 6));
+        let mut expected = Input::from_str_keys("39Zab").unwrap();
+        expected.keypad.enter = true;
+        assert_eq!(input, expected);
+
+        // A frame at the limit is untouched
+        assert!(!limit_keys(&mut input, 6));
+        assert_eq!(input, expected);
+    }
+
+    #[test]
+    fn test_limit_keys_unlimited() {
+        let mut input = Input::from_str_keys("0123456789abcdef!@#").unwrap();
+        let unlimited = input;
+
+        assert!(!limit_keys(&mut input, 0));
+        assert_eq!(input, unlimited);
+        assert!(!limit_keys(&mut input, u8::MAX));
+        assert_eq!(input, unlimited);
+    }
+
+    #[test]
+    fn test_key_latch_holds_until_read() {
+        let clock = FakeClock::at_ms(0);
+        let timeout = Some(TimerDuration::millis(1_000));
+        let mut latch = KeyLatch::default();
+
+        let mut input = Input::from_str_keys("3c").unwrap();
+        latch.update(&clock, &mut input, timeout);
+
+        // The press is released before the program reads it
+        clock.advance_ms(10);
+        let mut input = Input::from_str_keys("!").unwrap();
+        input.analog.a0 = 7;
+        latch.update(&clock, &mut input, timeout);
+        let mut expected = Input::from_str_keys("3c!").unwrap();
+        expected.analog.a0 = 7;
+        assert_eq!(input, expected);
+
+        latch.read();
+        clock.advance_ms(10);
+        let mut input = Input::default();
+        latch.update(&clock, &mut input, timeout);
+        assert!(input.is_empty());
+    }
+
+    #[test]
+    fn test_key_latch_auto_clears_unread() {
+        let clock = FakeClock::at_ms(0);
+        let timeout = Some(TimerDuration::millis(1_000));
+        let mut latch = KeyLatch::default();
+
+        let mut input = Input::from_str_keys("z").unwrap();
+        latch.update(&clock, &mut input, timeout);
+
+        clock.advance_ms(999);
+        let mut input = Input::default();
+        latch.update(&clock, &mut input, timeout);
+        assert!(input.keypad.z);
+
+        // Nothing read the latched press before the timeout
+        clock.advance_ms(1);
+        let mut input = Input::default();
+        latch.update(&clock, &mut input, timeout);
+        assert!(input.is_empty());
+    }
+
+    #[test]
+    fn test_key_latch_accumulates_without_timeout() {
+        let clock = FakeClock::at_ms(0);
+        let mut latch = KeyLatch::default();
+
+        // A press that lasts a single cycle
+        let mut input = Input::from_str_keys("7").unwrap();
+        latch.update(&clock, &mut input, None);
+        for _ in 0..10 {
+            clock.advance_ms(1_000);
+            let mut input = Input::default();
+            latch.update(&clock, &mut input, None);
+        }
+
+        // The next read still reports the press
+        let mut input = Input::from_str_keys("e").unwrap();
+        latch.update(&clock, &mut input, None);
+        assert_eq!(input, Input::from_str_keys("7e").unwrap());
+
+        latch.read();
+        let mut input = Input::default();
+        latch.update(&clock, &mut input, None);
+        assert!(input.is_empty());
+    }
+
+    #[test]
+    fn test_check_set_address_repeated() {
+        let mut address = 0x42;
+        let mut reconfigurations = 0;
+        for _ in 0..5 {
+            if let Some(new_address) = check_set_address(address, 0x24).unwrap() {
+                address = new_address;
+                reconfigurations += 1;
             }
-        },
-        None => (true, false, false, false),
+        }
+
+        assert_eq!(address, 0x24);
+        assert_eq!(reconfigurations, 1);
+    }
+
+    #[test]
+    fn test_check_set_address_invalid() {
+        assert_eq!(check_set_address(0x42, 0x00), Err(ConfigError::InvalidAddress));
+        assert_eq!(check_set_address(0x42, 0x07), Err(ConfigError::InvalidAddress));
+        assert_eq!(check_set_address(0x42, 0x78), Err(ConfigError::InvalidAddress));
+        assert_eq!(check_set_address(0x42, 0xFF), Err(ConfigError::InvalidAddress));
+        assert_eq!(check_set_address(0x42, MIN_I2C_ADDRESS), Ok(Some(MIN_I2C_ADDRESS)));
+        assert_eq!(check_set_address(0x42, MAX_I2C_ADDRESS), Ok(Some(MAX_I2C_ADDRESS)));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_extension_request_without_settle() {
+        let expectations = [
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![0x01]),
+            Transaction::transaction_end(),
+            Transaction::transaction_start(),
+            Transaction::transfer_in_place(vec![0, 0], vec![0x12, 0x34]),
+            Transaction::transaction_end(),
+        ];
+        let mut spi = SpiMock::<u8>::new(&expectations);
+
+        let mut buffer = [0u8; 2];
+        extension_request(&mut spi, 0, &[0x01], &mut buffer).unwrap();
+        assert_eq!(buffer, [0x12, 0x34]);
+
+        spi.done();
+    }
+
+    fn full_input_transfer(response: &[u8]) -> [Transaction<u8>; 6] {
+        // The bytes read past the response are zeros
+        let mut read = vec![0; extension_read_len(Input::PACKED_SIZE + 1)];
+        read[..response.len()].copy_from_slice(response);
+        [
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![InputRequest::FullInput as u8]),
+            Transaction::transaction_end(),
+            Transaction::transaction_start(),
+            Transaction::transfer_in_place(vec![0; read.len()], read),
+            Transaction::transaction_end(),
+        ]
+    }
+
+    fn extension_response(input: Input) -> [u8; EXTENSION_FULL_INPUT_LEN] {
+        let mut response = [0u8; EXTENSION_FULL_INPUT_LEN];
+        response[0] = EXTENSION_SYNC_BYTE;
+        response[1] = (Input::PACKED_SIZE + 1) as u8;
+        input.pack(&mut response[2..]).unwrap();
+        response[EXTENSION_FULL_INPUT_LEN - 1] = crc8(&response[2..EXTENSION_FULL_INPUT_LEN - 1]);
+        response
+    }
+
+    /// The bytes read for a response shifted by `shift` stale bytes (cut off if it is shifted past
+    /// the resync slack)
+    fn shifted_response(response: &[u8; EXTENSION_FULL_INPUT_LEN], shift: usize) -> [u8; extension_read_len(Input::PACKED_SIZE + 1)] {
+        let mut read = [0u8; extension_read_len(Input::PACKED_SIZE + 1)];
+        read[..shift].fill(InputRequest::FullInput as u8);
+        let len = EXTENSION_FULL_INPUT_LEN.min(read.len() - shift);
+        read[shift..shift + len].copy_from_slice(&response[..len]);
+        read
+    }
+
+    #[test]
+    fn test_read_extension_input_retries_corrupt() {
+        let mut input = Input::default();
+        input.keypad.a = true;
+        let valid = extension_response(input);
+        let mut corrupt = valid;
+        corrupt[3] ^= 0x10;
+        assert!(!extension_response_valid(corrupt[EXTENSION_HEADER_LEN..].try_into().unwrap()));
+
+        let expectations: std::vec::Vec<_> = full_input_transfer(&corrupt).into_iter()
+            .chain(full_input_transfer(&valid))
+            .collect();
+        let mut spi = SpiMock::new(&expectations);
+
+        assert_eq!(read_extension_input(&mut spi, 0, 2, 0), Ok(input));
+
+        spi.done();
+    }
+
+    #[test]
+    fn test_read_extension_input_always_corrupt() {
+        let mut corrupt = extension_response(Input::default());
+        corrupt[EXTENSION_FULL_INPUT_LEN - 1] ^= 0xFF;
+
+        let expectations: std::vec::Vec<_> = full_input_transfer(&corrupt).into_iter()
+            .chain(full_input_transfer(&corrupt))
+            .collect();
+        let mut spi = SpiMock::new(&expectations);
+
+        assert_eq!(read_extension_input(&mut spi, 0, 1, 0), Err(DisableReason::ChecksumErrors));
+
+        spi.done();
+    }
+
+    #[test]
+    fn test_extension_framing() {
+        let mut input = Input::default();
+        input.numpad.nine = true;
+        let payload_len = Input::PACKED_SIZE + 1;
+        let response = extension_response(input);
+        assert_eq!(extension_framing(&shifted_response(&response, 0), payload_len), ExtensionFraming::Synced(0));
+
+        // A slave that was not ready answers with a stale byte before the response
+        let mut read = shifted_response(&response, 1);
+        assert_eq!(extension_framing(&read, payload_len), ExtensionFraming::Synced(1));
+
+        // A stale byte equal to the sync byte is skipped since its length does not match
+        read[0] = EXTENSION_SYNC_BYTE;
+        assert_eq!(extension_framing(&read, payload_len), ExtensionFraming::Synced(1));
+
+        // Shifted past the slack, the end of the payload is cut off
+        let shift = EXTENSION_RESYNC_SLACK + 1;
+        assert_eq!(extension_framing(&shifted_response(&response, shift), payload_len), ExtensionFraming::Truncated(shift));
+
+        assert_eq!(extension_framing(&[0u8; EXTENSION_FULL_INPUT_LEN], payload_len), ExtensionFraming::Missing);
+    }
+
+    #[test]
+    fn test_read_extension_input_skips_leading_garbage() {
+        let mut input = Input::default();
+        input.keypad.z = true;
+        let shifted = shifted_response(&extension_response(input), 1);
+        let mut spi = SpiMock::new(&full_input_transfer(&shifted));
+
+        // The resync slack holds the whole shifted response, so it is read without a retry
+        assert_eq!(read_extension_input(&mut spi, 0, 0, 0), Ok(input));
+
+        spi.done();
+    }
+
+    #[test]
+    fn test_second_extension_input_merges() {
+        let mut first = Input::from_str_keys("1a").unwrap();
+        first.analog = AnalogInputs { a0: 10, a1: 20, a2: 30, ..Default::default() };
+        first.other_input_one[0] = 0x11;
+        let mut second = Input::from_str_keys("2b").unwrap();
+        second.analog = AnalogInputs { a0: 40, a1: 50, a2: 60, ..Default::default() };
+        second.other_input_one[0] = 0x22;
+
+        let merged = first | second_extension_input(second);
+        assert!(merged.numpad.one && merged.numpad.two && merged.keypad.a && merged.keypad.b);
+        assert_eq!(merged.analog, AnalogInputs { a0: 10, a1: 20, a2: 30, a3: 40, a4: 50, a5: 60 });
+        assert_eq!(merged.other_input_one[0], 0x11);
+        assert_eq!(merged.other_input_two[0], 0x22);
+    }
+
+    #[test]
+    fn test_read_extension_input_resyncs_shifted() {
+        let mut input = Input::default();
+        input.keypad.q = true;
+        let valid = extension_response(input);
+        let shifted = shifted_response(&valid, EXTENSION_RESYNC_SLACK + 1);
+
+        let expectations: std::vec::Vec<_> = full_input_transfer(&shifted).into_iter()
+            .chain(full_input_transfer(&valid))
+            .collect();
+        let mut spi = SpiMock::new(&expectations);
+
+        // A shifted response does not use up the checksum retries
+        assert_eq!(read_extension_input(&mut spi, 0, 0, 1), Ok(input));
+
+        spi.done();
+    }
+
+    #[test]
+    fn test_read_extension_input_never_synced() {
+        let missing = [0u8; EXTENSION_FULL_INPUT_LEN];
+        let expectations: std::vec::Vec<_> = full_input_transfer(&missing).into_iter()
+            .chain(full_input_transfer(&missing))
+            .collect();
+        let mut spi = SpiMock::new(&expectations);
+
+        assert_eq!(read_extension_input(&mut spi, 0, 2, 1), Err(DisableReason::Timeout));
+
+        spi.done();
+    }
+
+    /// A spi bus whose transfers always fail
+    struct FailingSpi;
+
+    impl embedded_hal::spi::ErrorType for FailingSpi {
+        type Error = embedded_hal::spi::ErrorKind;
+    }
+
+    impl SpiDevice<u8> for FailingSpi {
+        fn transaction(&mut self, _operations: &mut [Operation<'_, u8>]) -> Result<(), Self::Error> {
+            Err(embedded_hal::spi::ErrorKind::Other)
+        }
+    }
+
+    #[test]
+    fn test_read_extension_input_spi_error() {
+        assert_eq!(read_extension_input(&mut FailingSpi, 0, 2, 3), Err(DisableReason::SpiError));
+    }
+
+    #[test]
+    fn test_connection_disable_reason() {
+        assert_eq!(connection_disable_reason(false), Some(DisableReason::Unplugged));
+        assert_eq!(connection_disable_reason(true), None);
+    }
+
+    #[test]
+    fn test_update_connection() {
+        let mut enabled = false;
+        assert_eq!(update_connection(&mut enabled, false), None);
+
+        // A connect is only reported once, then a disconnect flips it back
+        assert_eq!(update_connection(&mut enabled, true), Some(true));
+        assert!(enabled);
+        assert_eq!(update_connection(&mut enabled, true), None);
+        assert_eq!(update_connection(&mut enabled, false), Some(false));
+        assert!(!enabled);
+        assert_eq!(update_connection(&mut enabled, true), Some(true));
+    }
+
+    #[test]
+    fn test_migrate_legacy_config() {
+        let provisioned = Config {
+            address: 0x24,
+            poll_rate_ms: 20,
+            latch_timeout_ms: 500,
+            report_mode: ReportMode::DigitOnly as u8,
+            max_keys: 4,
+            ..DEFAULT_CONFIG
+        };
+        let mut stored = [0u8; CONFIG_LEN];
+        provisioned.pack(&mut stored).unwrap();
+        assert_eq!(migrate_legacy_config(&stored), Some(provisioned));
+
+        // Stored before the key limit, then before the latch timeout and report mode
+        stored[55] = 0xFF;
+        assert_eq!(migrate_legacy_config(&stored), Some(Config { max_keys: 0, ..provisioned }));
+        stored[52..55].fill(0xFF);
+        assert_eq!(
+            migrate_legacy_config(&stored),
+            Some(Config { latch_timeout_ms: 1_000, report_mode: ReportMode::Both as u8, max_keys: 0, ..provisioned })
+        );
+
+        // A corrupt config is still discarded
+        stored[0] = 0x00;
+        assert_eq!(migrate_legacy_config(&stored), None);
+    }
+
+    #[test]
+    fn test_update_report_mode() {
+        let mut config = DEFAULT_CONFIG;
+        let mode = config.reporting();
+        assert!(!update_report_mode(&mut config, mode));
+
+        assert!(update_report_mode(&mut config, ReportMode::LetterOnly));
+        assert_eq!(config.reporting(), ReportMode::LetterOnly);
+        assert!(!update_report_mode(&mut config, ReportMode::LetterOnly));
+    }
+
+    #[test]
+    fn test_extension_request_wide_frames() {
+        let expectations = [
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![0x0100u16]),
+            Transaction::transaction_end(),
+            Transaction::transaction_start(),
+            Transaction::transfer_in_place(vec![0, 0], vec![0x1234u16, 0x5600]),
+            Transaction::transaction_end(),
+        ];
+        let mut spi = SpiMock::<u16>::new(&expectations);
+
+        let mut buffer = [0u8; 3];
+        extension_request(&mut spi, 0, &[0x01], &mut buffer).unwrap();
+        assert_eq!(buffer, [0x12, 0x34, 0x56]);
+
+        spi.done();
+    }
+
+    #[test]
+    fn test_extension_request_with_settle() {
+        let expectations = [
+            Transaction::transaction_start(),
+            Transaction::delay(5_000),
+            Transaction::write_vec(vec![0x01]),
+            Transaction::transaction_end(),
+            Transaction::transaction_start(),
+            Transaction::delay(5_000),
+            Transaction::transfer_in_place(vec![0, 0], vec![0x12, 0x34]),
+            Transaction::transaction_end(),
+        ];
+        let mut spi = SpiMock::<u8>::new(&expectations);
+
+        let mut buffer = [0u8; 2];
+        extension_request(&mut spi, 5_000, &[0x01], &mut buffer).unwrap();
+        assert_eq!(buffer, [0x12, 0x34]);
+
+        spi.done();
+    }
+
+    /// Tap a button with `N` options in sequence (with a stale click counter) and check every
+    /// selection is in bounds and cycles through every option
+    fn check_n_input_in_bounds<const N: usize>() {
+        for direction in [TapDirection::Forward, TapDirection::Backward] {
+            let clock = FakeClock::at_ms(0);
+            let mut last_click = 200;
+            let mut last_time = Some(clock.now());
+            let mut seen = [false; N];
+            for _ in 0..(2 * N) {
+                clock.advance_ms(100);
+                let selected = check_n_input::<N, _>(&clock, last_time, &mut last_click, direction);
+                assert!(selected < N);
+                seen[selected] = true;
+                last_time = Some(clock.now());
+            }
+            assert!(seen.iter().all(|seen| *seen));
+        }
+    }
+
+    #[test]
+    fn test_check_n_input_in_bounds() {
+        check_n_input_in_bounds::<2>();
+        check_n_input_in_bounds::<3>();
+        check_n_input_in_bounds::<4>();
+        check_n_input_in_bounds::<5>();
+        check_n_input_in_bounds::<6>();
+    }
+
+    #[test]
+    fn test_check_three_input_sequence_boundary() {
+        let clock = FakeClock::at_ms(0);
+        let mut last_click = 0;
+        check_three_input(&clock, None, &mut last_click, TapDirection::Forward);
+        let last_time = Some(clock.now());
+
+        // Just inside the sequence window the press moves on to the next option
+        clock.advance_ms(SEQUENCE_DELAY_MS as u64 - 1);
+        assert_eq!(check_three_input(&clock, last_time, &mut last_click, TapDirection::Forward), (false, true, false));
+        let last_time = Some(clock.now());
+
+        // Just outside the sequence window the press starts a new sequence
+        clock.advance_ms(SEQUENCE_DELAY_MS as u64 + 1);
+        assert_eq!(check_three_input(&clock, last_time, &mut last_click, TapDirection::Forward), (true, false, false));
+    }
+}
@@ -22,25 +22,34 @@ use panic_probe as _;
 mod app {
     use core::cell::RefCell;
 
-    use common::{input::{Input, InputRequest}, prelude::{Pack, Unpack}};
+    use common::{time::Heartbeat, input::{analog::TimedAnalog, config::{Config, ReportMode, CONFIG_LEN}, raw_buttons::RawButtons, changes::SectionChanges, alert::{ChangeAlert, ChangeFrame}, command_log::CommandLog, extension_status::ExtensionStatus, threshold::{ThresholdConfig, ThresholdDetector, NO_THRESHOLD_EVENT}, Input, InputRequest, OtherRequest, UNKNOWN_REQUEST_RESPONSE}, prelude::{Pack, Unpack}, protocol::{DECODE_INSTRUCTIONS_LEN, RAW_BUTTONS_LEN}};
     use critical_section::Mutex;
-    use embedded_hal::{digital::InputPin, spi::{SpiDevice, MODE_0}};
+    use embedded_hal::{digital::{InputPin, OutputPin}, spi::MODE_0};
     use rp_pico::{hal::{self, clocks::init_clocks_and_plls, gpio::{FunctionSpi, Interrupt}, Sio, Spi, Watchdog, I2C}, pac::RESETS, Pins};
-    use fugit::{RateExtU32, ExtU32, Instant};
+    use fugit::{RateExtU32, ExtU32};
 
     use rtic_monotonics::{rp2040::prelude::*, rp2040_timer_monotonic};
 
     use embedded_hal_bus::spi::CriticalSectionDevice;
 
     use main_input::peripherals::*;
-    use main_input::{check_three_input, check_four_input, INPUT_UPDATE_DELAY_MS};
+    use main_input::storage::{load_config, store_config};
+    use main_input::scanner::{ButtonScanner, MAIN_INPUT_BUTTONS};
+    use main_input::{Clock, TimerInstant, HEARTBEAT_MAX_AGE, WATCHDOG_FEED_PERIOD_MS, WATCHDOG_TIMEOUT_MS, check_set_address, connection_disable_reason, update_connection, update_report_mode, second_extension_input, decode_instructions_changed, extension_payload, read_extension_input, ExtensionFraming, DecodeFetch, DecodeFetchRetry, read_raw_buttons, FrameSnapshot, KeyLatch, limit_keys, PackedResponse, SnapshotSource, TimerDuration, I2cAddress, BUILD_ID, CONFIG_PERSIST_DELAY_MS, DECODE_REFRESH_DELAY_MS, DEFAULT_CONFIG, EXTENSION_CHECKSUM_RETRIES, EXTENSION_SETTLE_NS, EXTENSION_SYNC_RETRIES};
 
     rp2040_timer_monotonic!(Mono);
 
-    /// Static Variable Holding Spi Bus 0.  This should only every be set and referred to in `init`. Elsewhere, use the actual spi device
-    static mut SPI_BUS: Option<SpiBus0> = None;
+    /// The RTIC monotonic as the clock for the input timing logic
+    struct MonoClock;
+
+    impl Clock for MonoClock {
+        fn now(&self) -> TimerInstant {
+            Mono::now()
+        }
+    }
+
     /// The address of this device on the i2c line
-    static mut I2C_ADDRESS: u8 = 0;
+    static I2C_ADDRESS: I2cAddress = I2cAddress::new(0);
 
     #[shared]
     struct Shared {
@@ -63,16 +72,40 @@ mod app {
 
         /// The current combined input state of the modules
         input_state: Input,
+        /// The time the current input state was sampled (in microseconds, wrapping)
+        input_timestamp_us: u32,
+        /// The raw level of each button on this module (for calibration and debugging)
+        raw_buttons: RawButtons,
         /// The resets device peripheral
         resets: RESETS,
         /// The decode instructions for extension 1
-        ext1_decode_instructions: [u8; 248],
+        ext1_decode_instructions: [u8; DECODE_INSTRUCTIONS_LEN],
         /// The decode instructions for extension 2
-        ext2_decode_instructions: [u8; 248],
+        ext2_decode_instructions: [u8; DECODE_INSTRUCTIONS_LEN],
+        /// The current (persisted) configuration of the module
+        config: Config,
+        /// The config changed since it was last written to flash (persisted by `persist_config`,
+        /// outside of the i2c interrupt)
+        config_dirty: bool,
+        /// Beats every input update so the watchdog is only fed while the update loop is alive
+        heartbeat: Heartbeat,
+        /// The changes since the program last read a change frame
+        alert: ChangeAlert,
+        /// The attention line (held low while a change frame is waiting to be read)
+        attention: Attention,
+        /// The buttons latched until the program reads the input (when latching or accumulating is configured)
+        latch: KeyLatch,
+        /// Why each extension is disabled
+        extension_status: ExtensionStatus,
+        /// The analog threshold events waiting to be read
+        thresholds: ThresholdDetector,
+        /// Keys were dropped by the simultaneous key limit since the last `ChangedSections` request
+        key_overflow: bool,
     }
 
     #[local]
     struct Local {
+        watchdog: Watchdog,
         switch: Switch,
         b1: B1,
         b2: B2,
@@ -88,7 +121,9 @@ mod app {
         bfront: BFront,
     }
 
-    #[init]
+    // The spi bus is an init local so it lives for `'static` and can be shared by both extension
+    // devices without a `static mut`
+    #[init(local = [spi_bus: Option<SpiBus0> = None])]
     fn init(mut ctx: init::Context) -> (Shared, Local) {
         Mono::start(ctx.device.TIMER, &mut ctx.device.RESETS);
 
@@ -112,6 +147,7 @@ mod app {
         )
         .ok()
         .unwrap();
+        watchdog.start((WATCHDOG_TIMEOUT_MS * 1_000).micros());
 
         let spi_device = ctx.device.SPI0;
         let spi_pin_layout = (
@@ -120,20 +156,16 @@ mod app {
             pins.gpio2.into_function::<FunctionSpi>(),
         );
 
-        let bus = Mutex::new(RefCell::new(
-            Spi::<_, _, _, 8>::new(spi_device, spi_pin_layout)
+        let bus: &'static SpiBus0 = ctx.local.spi_bus.insert(Mutex::new(RefCell::new(
+            Spi::<_, _, _, EXTENSION_FRAME_BITS>::new(spi_device, spi_pin_layout)
                 .init(&mut ctx.device.RESETS, 125_000_000u32.Hz(), 16_000_000u32.Hz(), MODE_0)
-        ));
+        )));
 
-        #[allow(static_mut_refs)]
-        unsafe { SPI_BUS.replace(bus); }
         let cs1 = pins.gpio5.into_push_pull_output();
         let cs2 = pins.gpio9.into_push_pull_output();
 
-        #[allow(static_mut_refs)]
-        let ext1_spi = CriticalSectionDevice::new_no_delay(unsafe { SPI_BUS.as_ref().unwrap() }, cs1).unwrap();
-        #[allow(static_mut_refs)]
-        let ext2_spi= CriticalSectionDevice::new_no_delay(unsafe { SPI_BUS.as_ref().unwrap() }, cs2).unwrap();
+        let ext1_spi = CriticalSectionDevice::new(bus, cs1, SettleDelay).unwrap();
+        let ext2_spi = CriticalSectionDevice::new(bus, cs2, SettleDelay).unwrap();
 
         let mut en_ext1 = pins.gpio0.into_pull_down_input();
         let ext1_enabled = en_ext1.is_high().unwrap();
@@ -151,14 +183,37 @@ mod app {
             en_ext2.set_interrupt_enabled(Interrupt::EdgeHigh, true);
         }
 
+        let config = load_config().unwrap_or(DEFAULT_CONFIG);
+        I2C_ADDRESS.set(config.address);
+
         let program_i2c = I2C::new_peripheral_event_iterator(
             ctx.device.I2C1,
             pins.gpio6.reconfigure(),
             pins.gpio7.reconfigure(),
             &mut ctx.device.RESETS,
-            0u8,
+            config.address,
         );
 
+        // The attention line idles high until the first change
+        let mut attention = pins.gpio8.into_push_pull_output();
+        attention.set_high().unwrap();
+
+        if dispatch_input_tasks::spawn().is_err() {
+            defmt::error!("Dispatch Input Tasks was Already Running");
+        }
+
+        if refresh_decode_instructions::spawn().is_err() {
+            defmt::error!("Refresh Decode Instructions was Already Running");
+        }
+
+        if feed_watchdog::spawn().is_err() {
+            defmt::error!("Feed Watchdog was Already Running");
+        }
+
+        if persist_config::spawn().is_err() {
+            defmt::error!("Persist Config was Already Running");
+        }
+
         hal::pac::NVIC::unpend(hal::pac::Interrupt::I2C1_IRQ);
         unsafe {
             hal::pac::NVIC::unmask(hal::pac::Interrupt::I2C1_IRQ);
@@ -174,11 +229,27 @@ mod app {
                 ext2_spi,
                 program_i2c: Some(program_i2c),
                 input_state: Input::default(),
+                input_timestamp_us: 0,
+                raw_buttons: RawButtons::default(),
                 resets: ctx.device.RESETS,
-                ext1_decode_instructions: [0u8; 248],
-                ext2_decode_instructions: [0u8; 248],
+                ext1_decode_instructions: [0u8; DECODE_INSTRUCTIONS_LEN],
+                ext2_decode_instructions: [0u8; DECODE_INSTRUCTIONS_LEN],
+                config,
+                config_dirty: false,
+                heartbeat: Heartbeat::new(HEARTBEAT_MAX_AGE),
+                alert: ChangeAlert::default(),
+                attention,
+                latch: KeyLatch::default(),
+                extension_status: ExtensionStatus {
+                    ext1: connection_disable_reason(ext1_enabled),
+                    ext2: connection_disable_reason(ext2_enabled),
+                },
+                // No channel has a threshold until the program writes them
+                thresholds: ThresholdDetector::new(ThresholdConfig::default()),
+                key_overflow: false,
             },
             Local {
+                watchdog,
                 switch: pins.gpio10.into_pull_down_input(),
                 b1: pins.gpio11.into_pull_up_input(),
                 b2: pins.gpio12.into_pull_up_input(),
@@ -204,14 +275,16 @@ mod app {
     }
 
     #[task(
+        shared = [config],
         priority = 1
     )]
     /// Schedule and dispatch the tasks to update the current input state and read the input state from the
     /// connected buttons and switch
-    async fn dispatch_input_tasks(_ctx: dispatch_input_tasks::Context) {
+    async fn dispatch_input_tasks(mut ctx: dispatch_input_tasks::Context) {
         loop {
             let now  = Mono::now();
-            let next_read = now + INPUT_UPDATE_DELAY_MS.millis();
+            let poll_rate_ms = ctx.shared.config.lock(|config| config.poll_rate_ms as u32);
+            let next_read = now + poll_rate_ms.millis();
 
             if update_inputs::spawn().is_err() {
                 defmt::error!("Update Inputs was Already Running");
@@ -221,62 +294,75 @@ mod app {
         }
     }
 
+    #[task(
+        shared = [heartbeat],
+        local = [watchdog],
+        priority = 1
+    )]
+    /// Feed the watchdog while the input update loop is alive so a stuck loop resets the module
+    async fn feed_watchdog(mut ctx: feed_watchdog::Context) {
+        loop {
+            if ctx.shared.heartbeat.lock(|heartbeat| heartbeat.is_alive(&MonoClock)) {
+                ctx.local.watchdog.feed();
+            }
+
+            Mono::delay(WATCHDOG_FEED_PERIOD_MS.millis()).await;
+        }
+    }
+
+    #[task(
+        shared = [config, config_dirty],
+        priority = 1
+    )]
+    /// Write a changed config to flash.  Rewriting the sector stalls everything (code cannot run
+    /// from flash during an erase), so it is done here rather than in the middle of an i2c transaction.
+    async fn persist_config(mut ctx: persist_config::Context) {
+        loop {
+            let changed = (&mut ctx.shared.config, &mut ctx.shared.config_dirty)
+                .lock(|config, config_dirty| core::mem::take(config_dirty).then_some(*config));
+            if let Some(config) = changed {
+                store_config(config);
+            }
+
+            Mono::delay(CONFIG_PERSIST_DELAY_MS.millis()).await;
+        }
+    }
+
     #[task(
         shared = [
             input_state,
+            input_timestamp_us,
+            raw_buttons,
+            heartbeat,
+            alert,
+            attention,
+            latch,
+            config,
             ext1_enabled,
             ext2_enabled,
+            en_ext1,
+            en_ext2,
             ext1_spi,
             ext2_spi,
+            extension_status,
+            thresholds,
+            key_overflow,
         ],
         local = [
             switch,
-            last_switch_value: bool = false,
-            last_switch_time: Option<Instant<u64, 1, 1_000_000>> = None,
             b1,
-            last_b1_value: bool = true,
-            last_b1_time: Option<Instant<u64, 1, 1_000_000>> = None,
             b2,
-            last_b2_value: bool = true,
-            last_b2_click: u8 = 0,
-            last_b2_time: Option<Instant<u64, 1, 1_000_000>> = None,
             b3,
-            last_b3_value: bool = true,
-            last_b3_click: u8 = 0,
-            last_b3_time: Option<Instant<u64, 1, 1_000_000>> = None,
             b4,
-            last_b4_value: bool = true,
-            last_b4_click: u8 = 0,
-            last_b4_time: Option<Instant<u64, 1, 1_000_000>> = None,
             b5,
-            last_b5_value: bool = true,
-            last_b5_click: u8 = 0,
-            last_b5_time: Option<Instant<u64, 1, 1_000_000>> = None,
             b6,
-            last_b6_value: bool = true,
-            last_b6_click: u8 = 0,
-            last_b6_time: Option<Instant<u64, 1, 1_000_000>> = None,
             b7,
-            last_b7_value: bool = true,
-            last_b7_click: u8 = 0,
-            last_b7_time: Option<Instant<u64, 1, 1_000_000>> = None,
             b8,
-            last_b8_value: bool = true,
-            last_b8_click: u8 = 0,
-            last_b8_time: Option<Instant<u64, 1, 1_000_000>> = None,
             b9,
-            last_b9_value: bool = true,
-            last_b9_click: u8 = 0,
-            last_b9_time: Option<Instant<u64, 1, 1_000_000>> = None,
             bback,
-            last_back_value: bool = true,
-            last_back_time: Option<Instant<u64, 1, 1_000_000>> = None,
             b0,
-            last_b0_value: bool = true,
-            last_b0_time: Option<Instant<u64, 1, 1_000_000>> = None,
             bfront,
-            last_front_value: bool = true,
-            last_front_time: Option<Instant<u64, 1, 1_000_000>> = None,
+            scanner: ButtonScanner<12> = ButtonScanner::new(MAIN_INPUT_BUTTONS),
         ],
         priority = 1
     )]
@@ -287,191 +373,154 @@ mod app {
 
         // Update extension 1 inputs
         if ctx.shared.ext1_enabled.lock(|ext1_enabled| *ext1_enabled) {
-            let mut buffer = [0u8; 71];
-            ctx.shared.ext1_spi.lock(|spi| {
-                spi.write(&[InputRequest::FullInput as u8]).unwrap();
-                spi.transfer_in_place(&mut buffer).unwrap();
+            let input = ctx.shared.ext1_spi.lock(|spi| {
+                read_extension_input(spi, EXTENSION_SETTLE_NS, EXTENSION_CHECKSUM_RETRIES, EXTENSION_SYNC_RETRIES)
             });
-            next_input = Input::unpack(&buffer).unwrap();
+            match input {
+                Ok(input) => next_input = input,
+                Err(reason) => {
+                    // Ignore the extension until it is reconnected rather than trusting corrupt input
+                    defmt::warn!("Extension 1 Failed ({}), Disabling", reason);
+                    (
+                        &mut ctx.shared.ext1_enabled,
+                        &mut ctx.shared.en_ext1,
+                        &mut ctx.shared.extension_status,
+                    ).lock(|ext1_enabled, en_ext1, extension_status| {
+                        *ext1_enabled = false;
+                        extension_status.ext1 = Some(reason);
+                        en_ext1.set_interrupt_enabled(Interrupt::EdgeLow, false);
+                        en_ext1.set_interrupt_enabled(Interrupt::EdgeHigh, true);
+                    });
+                },
+            }
         }
 
         // Update extension 2 inputs
         if ctx.shared.ext2_enabled.lock(|ext2_enabled| *ext2_enabled) {
-            let mut buffer = [0u8; 71];
-            ctx.shared.ext2_spi.lock(|spi| {
-                spi.write(&[InputRequest::FullInput as u8]).unwrap();
-                spi.transfer_in_place(&mut buffer).unwrap();
+            let input = ctx.shared.ext2_spi.lock(|spi| {
+                read_extension_input(spi, EXTENSION_SETTLE_NS, EXTENSION_CHECKSUM_RETRIES, EXTENSION_SYNC_RETRIES)
             });
-            let input = Input::unpack(&buffer).unwrap();
-            next_input |= input;
-            next_input.analog.a3 = input.analog.a0;
-            next_input.analog.a4 = input.analog.a1;
-            next_input.analog.a5 = input.analog.a2;
-            next_input.other_input_two = input.other_input_one;
-        }
-
-        let now = Mono::now();
-
-        // Update inputs based on pressed buttons and pressed button states
-        if ctx.local.switch.is_high().unwrap() {
-            next_input.keypad.shift = true;
-        }
-
-        if ctx.local.b1.is_high().unwrap() {
-            next_input.numpad.one = true;
-        }
-
-        let b2_high = ctx.local.b2.is_high().unwrap();
-        if b2_high {
-            next_input.numpad.two = true;
-
-            (
-                next_input.keypad.a,
-                next_input.keypad.b,
-                next_input.keypad.c
-            ) = check_three_input(now, *ctx.local.last_b2_time, ctx.local.last_b2_click);
-            *ctx.local.last_b2_time = Some(now);
-        } else if *ctx.local.last_b2_value {
-            *ctx.local.last_b2_time = Some(now);
-        }
-        *ctx.local.last_b2_value = b2_high;
-
-        let b3_high = ctx.local.b3.is_high().unwrap();
-        if b3_high {
-            next_input.numpad.three = true;
-            (
-                next_input.keypad.d,
-                next_input.keypad.e,
-                next_input.keypad.f
-            ) = check_three_input(now, *ctx.local.last_b3_time, ctx.local.last_b2_click);
-            *ctx.local.last_b3_time = Some(now);
-        } else if *ctx.local.last_b3_value {
-            *ctx.local.last_b3_time = Some(now);
-        }
-        *ctx.local.last_b3_value = b3_high;
-
-        let b4_high = ctx.local.b4.is_high().unwrap();
-        if b4_high {
-            next_input.numpad.four = true;
-            (
-                next_input.keypad.g,
-                next_input.keypad.h,
-                next_input.keypad.i
-            ) = check_three_input(now, *ctx.local.last_b4_time, ctx.local.last_b4_click);
-            *ctx.local.last_b4_time = Some(now);
-        } else if *ctx.local.last_b4_value {
-            *ctx.local.last_b4_time = Some(now);
-        }
-        *ctx.local.last_b4_value = b4_high;
-
-        let b5_high = ctx.local.b5.is_high().unwrap();
-        if b5_high {
-            next_input.numpad.five = true;
-            (
-                next_input.keypad.j,
-                next_input.keypad.k,
-                next_input.keypad.l
-            ) = check_three_input(now, *ctx.local.last_b5_time, ctx.local.last_b5_click);
-            *ctx.local.last_b5_time = Some(now);
-        } else if *ctx.local.last_b5_value {
-            *ctx.local.last_b5_time = Some(now);
-        }
-        *ctx.local.last_b5_value = b5_high;
-
-        let b6_high = ctx.local.b6.is_high().unwrap();
-        if b6_high {
-            next_input.numpad.six = true;
-            (
-                next_input.keypad.m,
-                next_input.keypad.n,
-                next_input.keypad.o,
-            ) = check_three_input(now, *ctx.local.last_b6_time, ctx.local.last_b6_click);
-            *ctx.local.last_b6_time = Some(now);
-        } else if *ctx.local.last_b6_value {
-            *ctx.local.last_b6_time = Some(now);
-        }
-        *ctx.local.last_b6_value = b6_high;
-
-        let b7_high = ctx.local.b7.is_high().unwrap();
-        if b7_high {
-            next_input.numpad.seven = true;
-            (
-                next_input.keypad.p,
-                next_input.keypad.q,
-                next_input.keypad.r,
-                next_input.keypad.s,
-            ) = check_four_input(now, *ctx.local.last_b7_time, ctx.local.last_b7_click);
-            *ctx.local.last_b7_time = Some(now);
-        } else if *ctx.local.last_b7_value {
-            *ctx.local.last_b7_time = Some(now);
-        }
-        *ctx.local.last_b7_value = b7_high;
-
-        let b8_high = ctx.local.b8.is_high().unwrap();
-        if b8_high {
-            next_input.numpad.eight = true;
-            (
-                next_input.keypad.t,
-                next_input.keypad.u,
-                next_input.keypad.v,
-            ) = check_three_input(now, *ctx.local.last_b8_time, ctx.local.last_b8_click);
-            *ctx.local.last_b8_time = Some(now);
-        } else if *ctx.local.last_b8_value {
-            *ctx.local.last_b8_time = Some(now);
-        }
-        *ctx.local.last_b8_value = b8_high;
-
-        let b9_high = ctx.local.b9.is_high().unwrap();
-        if b9_high {
-            next_input.numpad.nine = true;
-            (
-                next_input.keypad.w,
-                next_input.keypad.x,
-                next_input.keypad.y,
-                next_input.keypad.z
-            ) = check_four_input(now, *ctx.local.last_b9_time, ctx.local.last_b9_click);
-            *ctx.local.last_b9_time = Some(now);
-        } else {
-            *ctx.local.last_b9_time = Some(now);
+            match input {
+                Ok(input) => next_input |= second_extension_input(input),
+                Err(reason) => {
+                    // Ignore the extension until it is reconnected rather than trusting corrupt input
+                    defmt::warn!("Extension 2 Failed ({}), Disabling", reason);
+                    (
+                        &mut ctx.shared.ext2_enabled,
+                        &mut ctx.shared.en_ext2,
+                        &mut ctx.shared.extension_status,
+                    ).lock(|ext2_enabled, en_ext2, extension_status| {
+                        *ext2_enabled = false;
+                        extension_status.ext2 = Some(reason);
+                        en_ext2.set_interrupt_enabled(Interrupt::EdgeLow, false);
+                        en_ext2.set_interrupt_enabled(Interrupt::EdgeHigh, true);
+                    });
+                },
+            }
         }
-        *ctx.local.last_b9_value = b9_high;
 
-        if ctx.local.bback.is_high().unwrap() {
-            next_input.keypad.backspace = true;
+        // Boards that only aggregate extensions have no local buttons to scan
+        let (has_local_buttons, report_mode) = ctx.shared.config.lock(|config| (config.has_local_buttons(), config.reporting()));
+        if has_local_buttons {
+            let raw_buttons = read_raw_buttons([
+                &mut *ctx.local.b0,
+                &mut *ctx.local.b1,
+                &mut *ctx.local.b2,
+                &mut *ctx.local.b3,
+                &mut *ctx.local.b4,
+                &mut *ctx.local.b5,
+                &mut *ctx.local.b6,
+                &mut *ctx.local.b7,
+                &mut *ctx.local.b8,
+                &mut *ctx.local.b9,
+                &mut *ctx.local.bback,
+                &mut *ctx.local.bfront,
+                &mut *ctx.local.switch,
+            ]);
+            ctx.shared.raw_buttons.lock(|raw| *raw = raw_buttons);
+
+            // Update inputs based on pressed buttons and pressed button states
+            ctx.local.scanner.scan(&MonoClock, &raw_buttons, &mut next_input, report_mode);
         }
 
-        if ctx.local.b0.is_high().unwrap() {
-            next_input.numpad.zero = true;
+        // Latched buttons stay set until the program reads the input (or the latch times out, an
+        // accumulated button never times out)
+        let (latches_keys, accumulates_input, latch_timeout_ms, max_keys) = ctx.shared.config.lock(|config| {
+            (config.latches_keys(), config.accumulates_input(), config.latch_timeout_ms, config.max_keys)
+        });
+        if latches_keys || accumulates_input {
+            let timeout = latches_keys.then(|| TimerDuration::millis(latch_timeout_ms as u64));
+            ctx.shared.latch.lock(|latch| latch.update(&MonoClock, &mut next_input, timeout));
         }
 
-        if ctx.local.bfront.is_high().unwrap() {
-            next_input.keypad.enter = true;
+        // The limit applies to the frame the program reads (after latching)
+        let key_overflow = limit_keys(&mut next_input, max_keys);
+        if key_overflow {
+            ctx.shared.key_overflow.lock(|overflow| *overflow = true);
         }
 
-        ctx.shared.input_state.lock(|input_state| {
+        // The 1 MHz monotonic ticks are microseconds (truncated so the timestamp wraps)
+        let timestamp_us = Mono::now().ticks() as u32;
+        (&mut ctx.shared.input_state, &mut ctx.shared.input_timestamp_us).lock(|input_state, input_timestamp_us| {
             *input_state = next_input;
-        })
+            *input_timestamp_us = timestamp_us;
+        });
+        (ctx.shared.alert, ctx.shared.attention).lock(|alert, attention| {
+            if key_overflow {
+                alert.flag_key_overflow();
+            }
+            if alert.update(&next_input) {
+                attention.set_low().unwrap();
+            }
+        });
+        ctx.shared.thresholds.lock(|thresholds| thresholds.update(&next_input.analog));
+        ctx.shared.heartbeat.lock(|heartbeat| heartbeat.beat(&MonoClock));
     }
 
     #[task(
         shared = [
             program_i2c,
             input_state,
+            input_timestamp_us,
+            raw_buttons,
             resets,
             ext1_decode_instructions,
             ext2_decode_instructions,
+            config,
+            config_dirty,
+            alert,
+            attention,
+            latch,
+            extension_status,
+            thresholds,
+            key_overflow,
+        ],
+        local = [
+            pending_address: Option<u8> = None,
+            other_index: u8 = 0,
+            last_served: Option<Input> = None,
+            command_log: CommandLog = CommandLog::new(),
+            snapshot: FrameSnapshot = FrameSnapshot::new(),
         ],
         priority = 2,
         binds = I2C1_IRQ
     )]
     /// Interrupt called when the program makes an I2C Request to the input controller
     fn i2c_interrupt(mut ctx: i2c_interrupt::Context) {
+        // The input is copied out of its lock up front and the responses are packed from the copy,
+        // so the input update task only waits for a frame copy instead of the whole event loop
+        // (which packs and writes responses of up to `DECODE_INSTRUCTIONS_LEN` bytes).  The
+        // timestamp is copied with it so a timed analog response still belongs to a single frame.
+        let (current, timestamp_us) = (&mut ctx.shared.input_state, &mut ctx.shared.input_timestamp_us)
+            .lock(|input_state, input_timestamp_us| (*input_state, *input_timestamp_us));
+        let input = &current;
+
         (
             ctx.shared.program_i2c,
-            ctx.shared.input_state,
             ctx.shared.resets,
-        ).lock(|program_i2c, input, resets| {
+        ).lock(|program_i2c, resets| {
             let mut instruction = None;
+            let mut opcode = None;
             loop {
                 let mut i2c = program_i2c.take().unwrap();
                 let event = i2c.next();
@@ -484,58 +533,130 @@ mod app {
                         // Start or Restart
                         let mut buffer = [0u8];
                         i2c.read(&mut buffer);
-                        instruction = Some(InputRequest::from(buffer[0]));
+                        instruction = InputRequest::try_from(buffer[0]).ok();
+                        opcode = Some(buffer[0]);
                         i2c
                     },
                     2 => {
                         // Transfer Read
                         if let Some(instruction) = instruction {
                             match instruction {
-                                InputRequest::FullInput => {
-                                    let mut buffer = [0u8; 71];
-                                    input.pack(&mut buffer).unwrap();
-                                    i2c.write(&buffer);
+                                // The input requests are answered by the shared `InputSource` logic, with
+                                // the sections served from a latched frame so a program reading several
+                                // sections with separate requests sees a consistent frame (see `FrameSnapshot`)
+                                InputRequest::FullInput |
+                                InputRequest::Numpad |
+                                InputRequest::Keypad |
+                                InputRequest::Auxiliary |
+                                InputRequest::Analog |
+                                InputRequest::DebugFrame => {
+                                    let mut source = SnapshotSource { snapshot: &mut *ctx.local.snapshot, current: input };
+                                    let response = PackedResponse::new(&mut source, instruction);
+                                    i2c.write(response.as_bytes());
+                                    if matches!(instruction, InputRequest::FullInput | InputRequest::Numpad | InputRequest::Keypad | InputRequest::Auxiliary) {
+                                        ctx.shared.latch.lock(|latch| latch.read());
+                                    }
                                 },
-                                InputRequest::Numpad => {
-                                    let mut buffer = [0u8; 2];
-                                    input.numpad.pack(&mut buffer).unwrap();
-                                    i2c.write(&buffer);
+                                InputRequest::DecodeOne |
+                                InputRequest::OtherOne |
+                                InputRequest::DecodeTwo |
+                                InputRequest::OtherTwo |
+                                InputRequest::DecodeN |
+                                InputRequest::OtherN => {
+                                    // The legacy opcodes are aliases of the indexed opcodes at index 0 and 1
+                                    match instruction.other_request(*ctx.local.other_index) {
+                                        Some(OtherRequest::Decode(0)) => {
+                                            let decode_instructions = ctx.shared.ext1_decode_instructions.lock(|decode_instructions| *decode_instructions);
+                                            i2c.write(&decode_instructions);
+                                        },
+                                        Some(OtherRequest::Decode(1)) => {
+                                            let decode_instructions = ctx.shared.ext2_decode_instructions.lock(|decode_instructions| *decode_instructions);
+                                            i2c.write(&decode_instructions);
+                                        },
+                                        Some(OtherRequest::Other(0)) => {
+                                            i2c.write(&ctx.local.snapshot.section(SectionChanges::OTHER_ONE, input).other_input_one);
+                                        },
+                                        Some(OtherRequest::Other(1)) => {
+                                            i2c.write(&ctx.local.snapshot.section(SectionChanges::OTHER_TWO, input).other_input_two);
+                                        },
+                                        _ => {
+                                            i2c.write(&[UNKNOWN_REQUEST_RESPONSE]);
+                                        },
+                                    }
+                                },
+                                InputRequest::SetAddress => {
+                                    i2c.write(&[I2C_ADDRESS.get()]);
                                 },
-                                InputRequest::Keypad => {
-                                    let mut buffer = [0u8; 4];
-                                    input.keypad.pack(&mut buffer).unwrap();
+                                InputRequest::ReadConfig => {
+                                    let config = ctx.shared.config.lock(|config| *config);
+                                    let mut buffer = [0u8; CONFIG_LEN];
+                                    config.pack(&mut buffer).unwrap();
                                     i2c.write(&buffer);
                                 },
-                                InputRequest::Auxiliary => {
-                                    let mut buffer = [0u8; 4];
-                                    input.auxiliary.pack(&mut buffer).unwrap();
+                                InputRequest::RawButtons => {
+                                    let raw_buttons = ctx.shared.raw_buttons.lock(|raw_buttons| *raw_buttons);
+                                    let mut buffer = [0u8; RAW_BUTTONS_LEN];
+                                    raw_buttons.pack(&mut buffer).unwrap();
                                     i2c.write(&buffer);
                                 },
-                                InputRequest::Analog => {
-                                    let mut buffer = [0u8; 12];
-                                    input.analog.pack(&mut buffer).unwrap();
+                                InputRequest::BuildId => {
+                                    i2c.write(&BUILD_ID);
+                                },
+                                InputRequest::ChangedSections => {
+                                    // The changes are relative to the input at the last `ChangedSections`
+                                    // request (or to an empty input for the first request)
+                                    let last_served = ctx.local.last_served.replace(current).unwrap_or_default();
+                                    let mut changes = SectionChanges::between(&last_served, input);
+                                    if ctx.shared.key_overflow.lock(core::mem::take) {
+                                        changes |= SectionChanges::from_bits(SectionChanges::KEY_OVERFLOW);
+                                    }
+                                    let mut buffer = [0u8; SectionChanges::PACKED_SIZE];
+                                    changes.pack(&mut buffer).unwrap();
                                     i2c.write(&buffer);
                                 },
-                                InputRequest::DecodeOne => {
-                                    ctx.shared.ext1_decode_instructions.lock(|decode_instructions| {
-                                        i2c.write(decode_instructions);
+                                InputRequest::AlertFrame => {
+                                    // Reading the frame clears the alert and releases the attention line
+                                    (&mut ctx.shared.alert, &mut ctx.shared.attention).lock(|alert, attention| {
+                                        let mut buffer = [0u8; ChangeFrame::PACKED_SIZE];
+                                        alert.read().pack(&mut buffer).unwrap();
+                                        i2c.write(&buffer);
+                                        attention.set_high().unwrap();
                                     });
                                 },
-                                InputRequest::OtherOne => {
-                                    i2c.write(&input.other_input_one);
+                                InputRequest::CommandLog => {
+                                    // The current request is logged once it stops
+                                    let mut buffer = [0u8; CommandLog::PACKED_SIZE];
+                                    (&*ctx.local.command_log).pack(&mut buffer).unwrap();
+                                    i2c.write(&buffer);
                                 },
-                                InputRequest::DecodeTwo => {
-                                    ctx.shared.ext2_decode_instructions.lock(|decode_instructions| {
-                                        i2c.write(decode_instructions);
-                                    });
+                                InputRequest::TimedAnalog => {
+                                    // The analog inputs and timestamp were copied under the same lock so
+                                    // they always belong to the same frame
+                                    let mut buffer = [0u8; TimedAnalog::PACKED_SIZE];
+                                    TimedAnalog { timestamp_us, analog: input.analog }.pack(&mut buffer).unwrap();
+                                    i2c.write(&buffer);
                                 },
-                                InputRequest::OtherTwo => {
-                                    i2c.write(&input.other_input_two);
+                                InputRequest::ReportMode => {
+                                    ctx.shared.config.lock(|config| i2c.write(&[config.report_mode]));
                                 },
-                                InputRequest::SetAddress => {
-                                    i2c.write(&[unsafe { I2C_ADDRESS }]);
+                                InputRequest::ExtensionStatus => {
+                                    let extension_status = ctx.shared.extension_status.lock(|extension_status| *extension_status);
+                                    let mut buffer = [0u8; ExtensionStatus::PACKED_SIZE];
+                                    extension_status.pack(&mut buffer).unwrap();
+                                    i2c.write(&buffer);
+                                },
+                                InputRequest::ThresholdEvents => {
+                                    let event = ctx.shared.thresholds.lock(|thresholds| thresholds.next_event());
+                                    let mut buffer = [NO_THRESHOLD_EVENT];
+                                    if let Some(event) = event {
+                                        event.pack(&mut buffer).unwrap();
+                                    }
+                                    i2c.write(&buffer);
                                 },
+                                InputRequest::WriteConfig => (),
                             }
+                        } else {
+                            i2c.write(&[UNKNOWN_REQUEST_RESPONSE]);
                         }
                         i2c
                     },
@@ -544,11 +665,59 @@ mod app {
                         if let Some(instruction) = instruction {
                             match instruction {
                                 InputRequest::SetAddress => {
+                                    // The address is only applied once the transaction stops so a burst
+                                    // of writes reconfigures the peripheral at most once
+                                    let mut buffer = [0u8];
+                                    i2c.read(&mut buffer);
+                                    match check_set_address(I2C_ADDRESS.get(), buffer[0]) {
+                                        Ok(address) => *ctx.local.pending_address = address,
+                                        Err(_) => defmt::warn!("Rejected Invalid Address {}", buffer[0]),
+                                    }
+                                    i2c
+                                },
+                                InputRequest::DecodeN | InputRequest::OtherN => {
                                     let mut buffer = [0u8];
                                     i2c.read(&mut buffer);
-                                    unsafe { I2C_ADDRESS = buffer[0] };
-                                    let (block, pins) = i2c.free(resets);
-                                    I2C::new_peripheral_event_iterator(block, pins.0, pins.1, resets, unsafe { I2C_ADDRESS } )
+                                    *ctx.local.other_index = buffer[0];
+                                    i2c
+                                },
+                                InputRequest::WriteConfig => {
+                                    // The whole config is validated before any of it is applied.  Like
+                                    // `SetAddress`, a new address is only applied once the transaction stops
+                                    // and the config is persisted outside of the interrupt.
+                                    let mut buffer = [0u8; CONFIG_LEN];
+                                    i2c.read(&mut buffer);
+                                    match Config::unpack(&buffer) {
+                                        Ok(new_config) if new_config.validate().is_ok() => {
+                                            (&mut ctx.shared.config, &mut ctx.shared.config_dirty).lock(|config, config_dirty| {
+                                                *config = new_config;
+                                                *config_dirty = true;
+                                            });
+                                            let current = I2C_ADDRESS.get();
+                                            *ctx.local.pending_address = (new_config.address != current).then_some(new_config.address);
+                                        },
+                                        _ => defmt::warn!("Rejected Invalid Config"),
+                                    }
+                                    i2c
+                                },
+                                InputRequest::ReportMode => {
+                                    let mut buffer = [0u8];
+                                    i2c.read(&mut buffer);
+                                    match ReportMode::try_from(buffer[0]) {
+                                        Ok(mode) => (&mut ctx.shared.config, &mut ctx.shared.config_dirty).lock(|config, config_dirty| {
+                                            *config_dirty |= update_report_mode(config, mode);
+                                        }),
+                                        Err(_) => defmt::warn!("Rejected Invalid Report Mode {}", buffer[0]),
+                                    }
+                                    i2c
+                                },
+                                InputRequest::ThresholdEvents => {
+                                    // The thresholds are only kept until the module resets
+                                    let mut buffer = [0u8; ThresholdConfig::PACKED_SIZE];
+                                    i2c.read(&mut buffer);
+                                    let config = ThresholdConfig::unpack(&buffer).unwrap();
+                                    ctx.shared.thresholds.lock(|thresholds| thresholds.configure(config));
+                                    i2c
                                 },
                                 _ => i2c,
                             }
@@ -559,7 +728,18 @@ mod app {
                     _ => {
                         // Stop
                         instruction = None;
-                        i2c
+                        if let Some(opcode) = opcode.take() {
+                            ctx.local.command_log.record(opcode);
+                        }
+                        match ctx.local.pending_address.take() {
+                            Some(address) => {
+                                I2C_ADDRESS.set(address);
+                                ctx.shared.config.lock(|config| config.address = address);
+                                let (block, pins) = i2c.free(resets);
+                                I2C::new_peripheral_event_iterator(block, pins.0, pins.1, resets, address)
+                            },
+                            None => i2c,
+                        }
                     }
                 };
                 *program_i2c = Some(i2c);
@@ -571,13 +751,61 @@ mod app {
         shared = [
             ext1_enabled,
             ext2_enabled,
-            en_ext1,
-            en_ext2,
             ext1_spi,
             ext2_spi,
             ext1_decode_instructions,
             ext2_decode_instructions,
         ],
+        priority = 1
+    )]
+    /// Periodically re-fetch the decode instructions of the connected extensions and replace the cached
+    /// instructions if an extension has been reconfigured since it was connected
+    async fn refresh_decode_instructions(mut ctx: refresh_decode_instructions::Context) {
+        loop {
+            let next_refresh = Mono::now() + DECODE_REFRESH_DELAY_MS.millis();
+
+            if ctx.shared.ext1_enabled.lock(|ext1_enabled| *ext1_enabled) {
+                (
+                    &mut ctx.shared.ext1_spi,
+                    &mut ctx.shared.ext1_decode_instructions
+                ).lock(|ext1_spi, decode_instructions| {
+                    let mut buffer = [0u8; DECODE_INSTRUCTIONS_LEN];
+                    let framing = extension_payload(ext1_spi, EXTENSION_SETTLE_NS, &[InputRequest::DecodeOne as u8], &mut buffer).unwrap();
+                    // An unsynced response is skipped until the next refresh
+                    if matches!(framing, ExtensionFraming::Synced(_)) && decode_instructions_changed(decode_instructions, &buffer) {
+                        defmt::info!("Extension 1 Decode Instructions Changed");
+                        *decode_instructions = buffer;
+                    }
+                });
+            }
+
+            if ctx.shared.ext2_enabled.lock(|ext2_enabled| *ext2_enabled) {
+                (
+                    &mut ctx.shared.ext2_spi,
+                    &mut ctx.shared.ext2_decode_instructions
+                ).lock(|ext2_spi, decode_instructions| {
+                    let mut buffer = [0u8; DECODE_INSTRUCTIONS_LEN];
+                    let framing = extension_payload(ext2_spi, EXTENSION_SETTLE_NS, &[InputRequest::DecodeTwo as u8], &mut buffer).unwrap();
+                    // An unsynced response is skipped until the next refresh
+                    if matches!(framing, ExtensionFraming::Synced(_)) && decode_instructions_changed(decode_instructions, &buffer) {
+                        defmt::info!("Extension 2 Decode Instructions Changed");
+                        *decode_instructions = buffer;
+                    }
+                });
+            }
+
+            Mono::delay_until(next_refresh).await;
+        }
+    }
+
+    #[task(
+        shared = [
+            ext1_enabled,
+            ext2_enabled,
+            en_ext1,
+            en_ext2,
+            extension_status,
+        ],
         priority = 1,
         binds = IO_IRQ_BANK0
     )]
@@ -587,13 +815,14 @@ mod app {
             ctx.shared.ext1_enabled,
             ctx.shared.ext2_enabled,
             ctx.shared.en_ext1,
-            ctx.shared.en_ext2
-        ).lock(|ext1_enabled, ext2_enabled, en_ext1, en_ext2| {
+            ctx.shared.en_ext2,
+            ctx.shared.extension_status,
+        ).lock(|ext1_enabled, ext2_enabled, en_ext1, en_ext2, extension_status| {
             // Check if extension 1 has changed connection
             let ext1 = en_ext1.is_high().unwrap();
             let mut new_one = false;
-            if ext1 != *ext1_enabled {
-                *ext1_enabled = ext1;
+            if let Some(ext1) = update_connection(ext1_enabled, ext1) {
+                extension_status.ext1 = connection_disable_reason(ext1);
                 if ext1 {
                     en_ext1.set_interrupt_enabled(Interrupt::EdgeHigh, false);
                     en_ext1.set_interrupt_enabled(Interrupt::EdgeLow, true);
@@ -607,7 +836,8 @@ mod app {
             // Check if extension 2 has changed connection
             let ext2 = en_ext2.is_high().unwrap();
             let mut new_two = false;
-            if ext2 != *ext2_enabled {
+            if let Some(ext2) = update_connection(ext2_enabled, ext2) {
+                extension_status.ext2 = connection_disable_reason(ext2);
                 if ext2 {
                     en_ext2.set_interrupt_enabled(Interrupt::EdgeHigh, false);
                     en_ext2.set_interrupt_enabled(Interrupt::EdgeLow, true);
@@ -621,30 +851,82 @@ mod app {
             (new_one, new_two)
         });
 
-        // Get decode instructions from extension 1
-        if new_one {
-            (
-                ctx.shared.ext1_spi,
-                ctx.shared.ext1_decode_instructions
-            ).lock(|ext1_spi, decode_instructions| {
-                let mut buffer = [0u8; 248];
-                ext1_spi.write(&[InputRequest::DecodeOne as u8]).unwrap();
-                ext1_spi.transfer_in_place(&mut buffer).unwrap();
-                *decode_instructions = buffer;
+        // Get decode instructions from extension 1 (retrying until it has finished booting)
+        if new_one && fetch_ext1_decode_instructions::spawn().is_err() {
+            defmt::error!("Fetch Extension 1 Decode Instructions was Already Running");
+        }
+
+        // Get decode instructions form extension 2 (retrying until it has finished booting)
+        if new_two && fetch_ext2_decode_instructions::spawn().is_err() {
+            defmt::error!("Fetch Extension 2 Decode Instructions was Already Running");
+        }
+    }
+
+    #[task(
+        shared = [
+            ext1_spi,
+            ext1_decode_instructions,
+        ],
+        priority = 1
+    )]
+    /// Fetch the decode instructions of a newly connected extension 1, retrying (with backoff) while the
+    /// extension is still booting.  If every attempt fails the cached instructions are left zeroed (no
+    /// declared fields) so stale instructions from a previous extension are never served.
+    async fn fetch_ext1_decode_instructions(mut ctx: fetch_ext1_decode_instructions::Context) {
+        let mut retry = DecodeFetchRetry::new();
+        loop {
+            let mut buffer = [0u8; DECODE_INSTRUCTIONS_LEN];
+            ctx.shared.ext1_spi.lock(|ext1_spi| {
+                // An unsynced response leaves the buffer zeroed, which is retried as invalid instructions
+                extension_payload(ext1_spi, EXTENSION_SETTLE_NS, &[InputRequest::DecodeOne as u8], &mut buffer).unwrap();
             });
+
+            match retry.check(&buffer) {
+                DecodeFetch::Valid => {
+                    ctx.shared.ext1_decode_instructions.lock(|decode_instructions| *decode_instructions = buffer);
+                    return;
+                },
+                DecodeFetch::RetryAfterMs(delay_ms) => Mono::delay(delay_ms.millis()).await,
+                DecodeFetch::Unavailable => {
+                    defmt::warn!("Extension 1 Decode Instructions Unavailable");
+                    ctx.shared.ext1_decode_instructions.lock(|decode_instructions| *decode_instructions = [0u8; DECODE_INSTRUCTIONS_LEN]);
+                    return;
+                },
+            }
         }
+    }
 
-        // Get decode instructions form extension 2
-        if new_two {
-            (
-                ctx.shared.ext2_spi,
-                ctx.shared.ext2_decode_instructions
-            ).lock(|ext2_spi, decode_instructions| {
-                let mut buffer = [0u8; 248];
-                ext2_spi.write(&[InputRequest::DecodeOne as u8]).unwrap();
-                ext2_spi.transfer_in_place(&mut buffer).unwrap();
-                *decode_instructions = buffer;
+    #[task(
+        shared = [
+            ext2_spi,
+            ext2_decode_instructions,
+        ],
+        priority = 1
+    )]
+    /// Fetch the decode instructions of a newly connected extension 2, retrying (with backoff) while the
+    /// extension is still booting.  If every attempt fails the cached instructions are left zeroed (no
+    /// declared fields) so stale instructions from a previous extension are never served.
+    async fn fetch_ext2_decode_instructions(mut ctx: fetch_ext2_decode_instructions::Context) {
+        let mut retry = DecodeFetchRetry::new();
+        loop {
+            let mut buffer = [0u8; DECODE_INSTRUCTIONS_LEN];
+            ctx.shared.ext2_spi.lock(|ext2_spi| {
+                // An unsynced response leaves the buffer zeroed, which is retried as invalid instructions
+                extension_payload(ext2_spi, EXTENSION_SETTLE_NS, &[InputRequest::DecodeTwo as u8], &mut buffer).unwrap();
             });
+
+            match retry.check(&buffer) {
+                DecodeFetch::Valid => {
+                    ctx.shared.ext2_decode_instructions.lock(|decode_instructions| *decode_instructions = buffer);
+                    return;
+                },
+                DecodeFetch::RetryAfterMs(delay_ms) => Mono::delay(delay_ms.millis()).await,
+                DecodeFetch::Unavailable => {
+                    defmt::warn!("Extension 2 Decode Instructions Unavailable");
+                    ctx.shared.ext2_decode_instructions.lock(|decode_instructions| *decode_instructions = [0u8; DECODE_INSTRUCTIONS_LEN]);
+                    return;
+                },
+            }
         }
     }
 }
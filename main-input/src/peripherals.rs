@@ -9,33 +9,60 @@ use rp_pico::{
     hal::{
         gpio::{
             bank0::{
-                Gpio0, Gpio1, Gpio10, Gpio11, Gpio12, Gpio13, Gpio14, Gpio15, Gpio16, Gpio17, Gpio18, Gpio19, Gpio2, Gpio20, Gpio21, Gpio22, Gpio3, Gpio4, Gpio5, Gpio6, Gpio7, Gpio9
+                Gpio0, Gpio1, Gpio10, Gpio11, Gpio12, Gpio13, Gpio14, Gpio15, Gpio16, Gpio17, Gpio18, Gpio19, Gpio2, Gpio20, Gpio21, Gpio22, Gpio3, Gpio4, Gpio5, Gpio6, Gpio7, Gpio8, Gpio9
             }, FunctionI2c, FunctionSio, FunctionSpi, Pin, PullDown, PullUp, SioInput, SioOutput
         }, i2c::Peripheral, spi::Enabled, Spi, I2C
     },
     pac::{I2C1, SPI0},
 };
 
-use embedded_hal_bus::spi::{CriticalSectionDevice, NoDelay};
+use embedded_hal::delay::DelayNs;
+use embedded_hal_bus::spi::CriticalSectionDevice;
+
+/// The system clock frequency (in MHz) used to convert delays into cycles
+const SYS_CLOCK_MHZ: u32 = 125;
+
+/// Busy-wait delay used for the settle time between selecting an extension and clocking data
+pub struct SettleDelay;
+
+impl DelayNs for SettleDelay {
+    fn delay_ns(&mut self, ns: u32) {
+        let cycles = (ns as u64 * SYS_CLOCK_MHZ as u64).div_ceil(1_000);
+        cortex_m::asm::delay(cycles as u32);
+    }
+}
+
+/// The data frame size (in bits) of the extension bus.  The `wide-spi` feature selects 16-bit frames
+/// for extensions optimized for wide transfers (an advanced option, every extension on the bus
+/// must use the same frame size, see `frames`)
+#[cfg(not(feature = "wide-spi"))]
+pub const EXTENSION_FRAME_BITS: u8 = 8;
+/// The data frame size (in bits) of the extension bus (16-bit frames selected by `wide-spi`)
+#[cfg(feature = "wide-spi")]
+pub const EXTENSION_FRAME_BITS: u8 = 16;
 
 /// SPI0
-type Spi0 = Spi<Enabled, SPI0, (Pin<Gpio3, FunctionSpi, PullDown>, Pin<Gpio4, FunctionSpi, PullDown>, Pin<Gpio2, FunctionSpi, PullDown>)>;
+type Spi0 = Spi<Enabled, SPI0, (Pin<Gpio3, FunctionSpi, PullDown>, Pin<Gpio4, FunctionSpi, PullDown>, Pin<Gpio2, FunctionSpi, PullDown>), EXTENSION_FRAME_BITS>;
 /// A bus for SPI0
 pub type SpiBus0 = Mutex<RefCell<Spi0>>;
 
 /// The enable pin for enabling extension 1
 pub type EnExt1 = Pin<Gpio0, FunctionSio<SioInput>, PullDown>;
 /// The spi device connected to extension 1
-pub type Ext1Spi = CriticalSectionDevice<'static, Spi0, Pin<Gpio5, FunctionSio<SioOutput>, PullDown>, NoDelay>;
+pub type Ext1Spi = CriticalSectionDevice<'static, Spi0, Pin<Gpio5, FunctionSio<SioOutput>, PullDown>, SettleDelay>;
 
 /// The enable pin for enabling extension 2
 pub type EnExt2 = Pin<Gpio1, FunctionSio<SioInput>, PullDown>;
 /// The spi device connected to extension 2
-pub type Ext2Spi = CriticalSectionDevice<'static, Spi0, Pin<Gpio9, FunctionSio<SioOutput>, PullDown>, NoDelay>;
+pub type Ext2Spi = CriticalSectionDevice<'static, Spi0, Pin<Gpio9, FunctionSio<SioOutput>, PullDown>, SettleDelay>;
 
 /// The i2c peripheral the programming modules use to communicate with the main input module
 pub type ProgramI2C = I2C<I2C1, (Pin<Gpio6, FunctionI2c, PullUp>, Pin<Gpio7, FunctionI2c, PullUp>), Peripheral>;
 
+/// The attention line to the programming modules (active low like SMBALERT#, asserted while a change
+/// frame is waiting to be read)
+pub type Attention = Pin<Gpio8, FunctionSio<SioOutput>, PullDown>;
+
 /// The switch
 pub type Switch = Pin<Gpio10, FunctionSio<SioInput>, PullDown>;
 
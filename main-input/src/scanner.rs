@@ -0,0 +1,211 @@
+//!
+//! Generalized Scan of the Main Input Module's Buttons
+//!
+//! Each button is described once (how to read it, the field it sets and its multi-tap letters)
+//! and every button is scanned by the same loop, so the buttons cannot drift out of sync.
+//!
+
+use common::input::{config::ReportMode, raw_buttons::RawButtons, Input};
+
+use crate::{next_click, Clock, TapDirection, TimerInstant};
+
+#[derive(Clone, Copy)]
+/// Describes a single button of the main input module
+pub struct ButtonDescriptor {
+    /// Read the level of the button from the raw buttons
+    pub read: fn(&RawButtons) -> bool,
+    /// Set the field of the button while it is held (i.e. its digit)
+    pub press: fn(&mut Input),
+    /// The letters repeated presses cycle through ("" if the button has none)
+    pub letters: &'static str,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// The timing state of a single button
+struct ButtonState {
+    /// The level of the button at the last scan
+    last_value: bool,
+    /// The index of the last selected letter
+    last_click: u8,
+    /// The last time the button was held or released
+    last_time: Option<TimerInstant>,
+}
+
+impl ButtonState {
+    /// The state of a button that has never been scanned
+    const INITIAL: Self = Self {
+        last_value: true,
+        last_click: 0,
+        last_time: None,
+    };
+}
+
+/// The buttons of the main input module (the switch is handled by the scanner as the shift)
+pub const MAIN_INPUT_BUTTONS: [ButtonDescriptor; 12] = [
+    ButtonDescriptor { read: |raw| raw.one, press: |input| input.numpad.one = true, letters: "" },
+    ButtonDescriptor { read: |raw| raw.two, press: |input| input.numpad.two = true, letters: "abc" },
+    ButtonDescriptor { read: |raw| raw.three, press: |input| input.numpad.three = true, letters: "def" },
+    ButtonDescriptor { read: |raw| raw.four, press: |input| input.numpad.four = true, letters: "ghi" },
+    ButtonDescriptor { read: |raw| raw.five, press: |input| input.numpad.five = true, letters: "jkl" },
+    ButtonDescriptor { read: |raw| raw.six, press: |input| input.numpad.six = true, letters: "mno" },
+    ButtonDescriptor { read: |raw| raw.seven, press: |input| input.numpad.seven = true, letters: "pqrs" },
+    ButtonDescriptor { read: |raw| raw.eight, press: |input| input.numpad.eight = true, letters: "tuv" },
+    ButtonDescriptor { read: |raw| raw.nine, press: |input| input.numpad.nine = true, letters: "wxyz" },
+    ButtonDescriptor { read: |raw| raw.back, press: |input| input.keypad.backspace = true, letters: "" },
+    ButtonDescriptor { read: |raw| raw.zero, press: |input| input.numpad.zero = true, letters: "" },
+    ButtonDescriptor { read: |raw| raw.front, press: |input| input.keypad.enter = true, letters: "" },
+];
+
+/// Scans `N` buttons into an input, keeping the multi-tap timing state of each button
+pub struct ButtonScanner<const N: usize> {
+    /// The buttons to scan
+    buttons: [ButtonDescriptor; N],
+    /// The timing state of each button
+    states: [ButtonState; N],
+}
+
+impl<const N: usize> ButtonScanner<N> {
+    /// Create a new scanner for the buttons
+    pub const fn new(buttons: [ButtonDescriptor; N]) -> Self {
+        Self {
+            buttons,
+            states: [ButtonState::INITIAL; N],
+        }
+    }
+
+    /// Scan the raw buttons into `input`.
+    ///
+    /// Holding the switch shifts the keypad and cycles the multi-tap buttons backward.  The report
+    /// mode picks whether a button with both a digit and letters reports its digit, its selected
+    /// letter or both (the letters keep cycling either way).
+    pub fn scan<C: Clock>(&mut self, clock: &C, raw: &RawButtons, input: &mut Input, mode: ReportMode) {
+        let now = clock.now();
+        let direction = if raw.switch {
+            input.keypad.shift = true;
+            TapDirection::Backward
+        } else {
+            TapDirection::Forward
+        };
+
+        for (button, state) in self.buttons.iter().zip(self.states.iter_mut()) {
+            let high = (button.read)(raw);
+            if high && (button.letters.is_empty() || mode.reports_digits()) {
+                (button.press)(input);
+            }
+
+            if !button.letters.is_empty() {
+                if high {
+                    let options = button.letters.len() as u8;
+                    let click = next_click(now, state.last_time, &mut state.last_click, options, direction);
+                    // The click is always less than the number of letters
+                    let letter = button.letters.chars().nth(click as usize).unwrap();
+                    if let Some(pressed) = input.keypad.letter_mut(letter).filter(|_| mode.reports_letters()) {
+                        *pressed = true;
+                    }
+                    state.last_time = Some(now);
+                } else if state.last_value {
+                    state.last_time = Some(now);
+                }
+            }
+            state.last_value = high;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::cell::Cell;
+    use fugit::ExtU64;
+
+    /// A clock that only moves when advanced
+    struct FakeClock(Cell<TimerInstant>);
+
+    impl Clock for FakeClock {
+        fn now(&self) -> TimerInstant {
+            self.0.get()
+        }
+    }
+
+    impl FakeClock {
+        fn advance_ms(&self, ms: u64) {
+            self.0.set(self.0.get() + ms.millis());
+        }
+    }
+
+    #[test]
+    fn test_scan_digits_and_letters() {
+        let clock = FakeClock(Cell::new(TimerInstant::from_ticks(0)));
+        let mut scanner = ButtonScanner::new(MAIN_INPUT_BUTTONS);
+        let raw = RawButtons { one: true, three: true, nine: true, front: true, ..Default::default() };
+
+        let mut input = Input::default();
+        scanner.scan(&clock, &raw, &mut input, ReportMode::Both);
+
+        let mut expected = Input::from_str_keys("139dw").unwrap();
+        expected.keypad.enter = true;
+        assert_eq!(input, expected);
+    }
+
+    #[test]
+    fn test_scan_switch_shifts_backward() {
+        let clock = FakeClock(Cell::new(TimerInstant::from_ticks(0)));
+        let mut scanner = ButtonScanner::new(MAIN_INPUT_BUTTONS);
+        let raw = RawButtons { seven: true, switch: true, ..Default::default() };
+
+        let mut input = Input::default();
+        scanner.scan(&clock, &raw, &mut input, ReportMode::Both);
+
+        assert_eq!(input, Input::from_str_keys("7S").unwrap());
+    }
+
+    #[test]
+    fn test_scan_report_modes() {
+        let raw = RawButtons { one: true, five: true, front: true, ..Default::default() };
+        let scan = |mode| {
+            let clock = FakeClock(Cell::new(TimerInstant::from_ticks(0)));
+            let mut input = Input::default();
+            ButtonScanner::new(MAIN_INPUT_BUTTONS).scan(&clock, &raw, &mut input, mode);
+            input
+        };
+
+        let mut both = Input::from_str_keys("15j").unwrap();
+        both.keypad.enter = true;
+        let mut digit_only = Input::from_str_keys("15").unwrap();
+        digit_only.keypad.enter = true;
+        // One has no letters so it still reports its digit
+        let mut letter_only = Input::from_str_keys("1j").unwrap();
+        letter_only.keypad.enter = true;
+
+        assert_eq!(scan(ReportMode::Both), both);
+        assert_eq!(scan(ReportMode::DigitOnly), digit_only);
+        assert_eq!(scan(ReportMode::LetterOnly), letter_only);
+    }
+
+    #[test]
+    fn test_scan_independent_multi_tap() {
+        let clock = FakeClock(Cell::new(TimerInstant::from_ticks(0)));
+        let mut scanner = ButtonScanner::new(MAIN_INPUT_BUTTONS);
+        let two = RawButtons { two: true, ..Default::default() };
+        let three = RawButtons { three: true, ..Default::default() };
+
+        // Settle every button released so the first taps start new sequences
+        scanner.scan(&clock, &RawButtons::default(), &mut Input::default(), ReportMode::Both);
+        clock.advance_ms(1_000);
+
+        let mut taps = [Input::default(); 4];
+        for (raw, tap) in [two, two, three, two].iter().zip(taps.iter_mut()) {
+            scanner.scan(&clock, raw, tap, ReportMode::Both);
+            clock.advance_ms(100);
+            scanner.scan(&clock, &RawButtons::default(), &mut Input::default(), ReportMode::Both);
+            clock.advance_ms(100);
+        }
+
+        // Tapping three does not advance two's letter
+        assert!(taps[0].keypad.a);
+        assert!(taps[1].keypad.b);
+        assert!(taps[2].keypad.d);
+        assert!(taps[3].keypad.c);
+    }
+}
@@ -0,0 +1,61 @@
+//!
+//! Persistent storage of the main input module's configuration in the last sector of flash
+//!
+
+use common::{input::config::{Config, CONFIG_LAYOUT_VERSION, CONFIG_LEN}, packing::{Pack, Unpack}};
+
+use crate::migrate_legacy_config;
+
+/// The offset (from the start of flash) of the sector reserved for the config (see `memory.x`)
+const CONFIG_FLASH_OFFSET: u32 = 2048 * 1024 - 4096;
+/// The address the config sector is mapped to by XIP
+const CONFIG_XIP_ADDRESS: u32 = 0x1000_0000 + CONFIG_FLASH_OFFSET;
+/// The size of a flash sector (the smallest erasable unit)
+const SECTOR_SIZE: u32 = 4096;
+/// The size of a flash page (the smallest programmable unit)
+const PAGE_SIZE: usize = 256;
+/// Marker written before the layout version and the config to tell a stored config apart from
+/// erased flash
+const CONFIG_MAGIC: [u8; 2] = [0x50, 0x56];
+/// Marker written before a config stored without a layout version (migrated when it is loaded)
+const LEGACY_CONFIG_MAGIC: [u8; 2] = [0x50, 0x42];
+/// The length of the marker and layout version written before the config
+const HEADER_LEN: usize = CONFIG_MAGIC.len() + 1;
+
+/// Load the config stored in flash (if a valid config has been stored).  A config stored by an
+/// earlier firmware without a layout version is migrated (see `migrate_legacy_config`).
+pub fn load_config() -> Option<Config> {
+    // Safety: the config sector is reserved in `memory.x` and always mapped by XIP
+    let stored = unsafe {
+        core::slice::from_raw_parts(CONFIG_XIP_ADDRESS as *const u8, HEADER_LEN + CONFIG_LEN)
+    };
+
+    if stored[0..LEGACY_CONFIG_MAGIC.len()] == LEGACY_CONFIG_MAGIC {
+        let legacy = &stored[LEGACY_CONFIG_MAGIC.len()..(LEGACY_CONFIG_MAGIC.len() + CONFIG_LEN)];
+        return migrate_legacy_config(legacy.try_into().unwrap());
+    }
+
+    if stored[0..CONFIG_MAGIC.len()] != CONFIG_MAGIC || stored[CONFIG_MAGIC.len()] != CONFIG_LAYOUT_VERSION {
+        return None;
+    }
+
+    let config = Config::unpack(&stored[HEADER_LEN..]).ok()?;
+    config.validate().ok()?;
+    Some(config)
+}
+
+/// Store a config in flash so it is restored on the next boot.
+///
+/// Interrupts are disabled while the sector is rewritten because code cannot execute from flash
+/// during an erase or program.
+pub fn store_config(config: Config) {
+    let mut page = [0xFFu8; PAGE_SIZE];
+    page[0..CONFIG_MAGIC.len()].copy_from_slice(&CONFIG_MAGIC);
+    page[CONFIG_MAGIC.len()] = CONFIG_LAYOUT_VERSION;
+    config.pack(&mut page[HEADER_LEN..]).unwrap();
+
+    cortex_m::interrupt::free(|_| unsafe {
+        rp2040_flash::flash::flash_range_erase(CONFIG_FLASH_OFFSET, SECTOR_SIZE, true);
+        rp2040_flash::flash::flash_range_program(CONFIG_FLASH_OFFSET, &page, true);
+    });
+}